@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use clap::{Parser, Subcommand};
+use public_trading::osi::OsiSymbol;
 use public_trading::public::{AccountType, BarsPeriod, InstrumentType};
 
 #[derive(Parser, Debug)]
@@ -10,6 +11,10 @@ pub struct Cli {
     #[arg(long, default_value = "brokerage")]
     pub account_type: AccountType,
 
+    /// Account id to execute the APIs, instead of the account of `account_type`
+    #[arg(long)]
+    pub account_id: Option<String>,
+
     #[command(subcommand)]
     pub operation: Operation,
 }
@@ -37,7 +42,7 @@ pub enum Operation {
         /// Option symbols in OSI format
         /// Max 250 per request
         #[arg(long)]
-        symbols: Vec<String>,
+        symbols: Vec<OsiSymbol>,
     },
     GetBarsV2 {
         #[arg(long)]
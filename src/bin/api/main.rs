@@ -2,7 +2,7 @@ mod cli_opts;
 
 use clap::Parser;
 use cli_opts::{Cli, Operation};
-use public_trading::public::{Instrument, InstrumentType, PublicClient, PublicError};
+use public_trading::public::{Instrument, PublicClient, PublicError};
 use rustls::crypto::CryptoProvider;
 use serde_json::json;
 use tracing::Level;
@@ -16,7 +16,10 @@ async fn main() -> Result<(), PublicError> {
         .expect("Failed to install default crypto provider");
 
     let mut client = PublicClient::new()?;
-    client.set_account(cli.account_type).await?;
+    match &cli.account_id {
+        Some(account_id) => client.set_account_by_id(account_id).await?,
+        None => client.set_account(cli.account_type).await?,
+    }
 
     match cli.operation {
         Operation::GetAccountPortfolio => {
@@ -35,10 +38,7 @@ async fn main() -> Result<(), PublicError> {
             println!("{:?}", history);
         }
         Operation::GetOptionChain { symbol, expiration } => {
-            let instrument = Instrument {
-                instrument_type: InstrumentType::Equity,
-                symbol,
-            };
+            let instrument = Instrument::equity(&symbol);
             let option_chain = client.get_option_chain(instrument, expiration).await?;
             println!("{}", json!(option_chain));
         }
@@ -54,6 +54,7 @@ async fn main() -> Result<(), PublicError> {
             let instrument = Instrument {
                 symbol,
                 instrument_type,
+                name: None,
             };
             let bars = client
                 .get_bars_v2(instrument, period, String::new())
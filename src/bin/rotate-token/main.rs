@@ -0,0 +1,30 @@
+use clap::Parser;
+use public_trading::bitwarden::Bitwarden;
+use rustls::crypto::CryptoProvider;
+
+/// Stores a new value for a Bitwarden secret, like the Public API token once it expires.
+#[derive(Parser, Debug)]
+#[command(name = "rotate-token", about = "Rotate a Bitwarden secret")]
+struct Cli {
+    /// Key of the secret, like `public_trading_secret_token`
+    #[arg(long)]
+    key: String,
+
+    /// New value of the secret
+    #[arg(long)]
+    value: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider())
+        .expect("Failed to install default crypto provider");
+
+    let bitwarden = Bitwarden::new().await?;
+    bitwarden.rotate_secret(&cli.key, &cli.value).await?;
+    println!("Rotated secret {}", cli.key);
+
+    Ok(())
+}
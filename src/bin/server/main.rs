@@ -29,7 +29,7 @@ pub async fn get_portfolio(public: PublicClient) -> Result<impl warp::Reply, war
     public
         .get_account_portfolio()
         .await
-        .and_then(|ap| Ok(warp::reply::json(&ap)))
+        .map(|ap| warp::reply::json(&ap))
         .map_err(|_e| warp::reject())
 }
 
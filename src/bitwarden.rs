@@ -1,27 +1,112 @@
 use anyhow::{Result, bail};
 use bitwarden::secrets_manager::secrets::{
-    SecretGetRequest, SecretIdentifiersRequest, SecretResponse,
+    SecretGetRequest, SecretIdentifiersByProjectRequest, SecretIdentifiersRequest,
+    SecretPutRequest, SecretResponse,
 };
-use bitwarden::{Client, auth::login::AccessTokenLoginRequest, secrets_manager::ClientSecretsExt};
+use bitwarden::{Client, auth::login::AccessTokenLoginRequest};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use std::{env, fs};
+use tokio::sync::Mutex;
 use tracing::debug;
 use uuid::Uuid;
 
 const BITWARDEN_CONFIG: &str = ".bw.json";
+/// How long the listed secret ids are trusted before listing them again.
+const SECRET_IDS_TTL: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Deserialize)]
 struct BitwardenCreds {
     access_token: String,
     org_id: Uuid,
-    // project_id: Uuid,
+    /// Project holding the secrets, when the organization has other projects.
+    #[serde(default)]
+    project_id: Option<Uuid>,
 }
 
 pub struct Bitwarden {
     client: Client,
     creds: BitwardenCreds,
+    cache: Mutex<SecretCache>,
+}
+
+/// Secrets fetched during the session, so minting tokens doesn't hit the
+/// Bitwarden API every time.
+#[derive(Default)]
+struct SecretCache {
+    /// Secret ids by key.
+    ids: HashMap<String, Uuid>,
+    /// When `ids` were listed, none before the first listing.
+    listed: Option<Instant>,
+    /// Secret values and notes by key.
+    secrets: HashMap<String, (String, String)>,
+}
+
+impl SecretCache {
+    async fn ids<F, Fut>(&mut self, list: F) -> Result<&HashMap<String, Uuid>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<HashMap<String, Uuid>>>,
+    {
+        let fresh = self
+            .listed
+            .is_some_and(|listed| listed.elapsed() < SECRET_IDS_TTL);
+        if !fresh {
+            self.ids = list().await?;
+            self.listed = Some(Instant::now());
+        }
+
+        Ok(&self.ids)
+    }
+
+    /// The cached secret of `key`, otherwise looked up with `list` and `get`.
+    async fn get<L, LFut, G, GFut>(
+        &mut self,
+        key: &str,
+        list: L,
+        get: G,
+    ) -> Result<(String, String)>
+    where
+        L: FnOnce() -> LFut,
+        LFut: Future<Output = Result<HashMap<String, Uuid>>>,
+        G: FnOnce(Uuid) -> GFut,
+        GFut: Future<Output = Result<(String, String)>>,
+    {
+        if let Some(secret) = self.secrets.get(key) {
+            return Ok(secret.clone());
+        }
+        let id = match self.ids(list).await?.get(key) {
+            Some(id) => *id,
+            None => bail!("Secret key <{key}> does not exist in bitwarden"),
+        };
+
+        let secret = get(id).await?;
+        self.secrets.insert(key.to_string(), secret.clone());
+
+        Ok(secret)
+    }
+
+    /// Updates the secret `key` with `update`, then drops everything cached so
+    /// the next lookup fetches the new value.
+    async fn rotate<L, LFut, U, UFut>(&mut self, key: &str, list: L, update: U) -> Result<()>
+    where
+        L: FnOnce() -> LFut,
+        LFut: Future<Output = Result<HashMap<String, Uuid>>>,
+        U: FnOnce(Uuid) -> UFut,
+        UFut: Future<Output = Result<()>>,
+    {
+        let id = match self.ids(list).await?.get(key) {
+            Some(id) => *id,
+            None => bail!("Secret key <{key}> does not exist in bitwarden"),
+        };
+        let updated = update(id).await;
+        *self = SecretCache::default();
+
+        updated
+    }
 }
 
 impl Bitwarden {
@@ -37,47 +122,260 @@ impl Bitwarden {
         Ok(Bitwarden {
             client: bw_client,
             creds,
+            cache: Mutex::default(),
         })
     }
 
     async fn list_secrets(&self) -> Result<HashMap<String, Uuid>> {
         let creds = &self.creds;
-        let res = self
-            .client
-            .secrets()
-            .list(&SecretIdentifiersRequest {
-                organization_id: creds.org_id.clone(),
-            })
-            .await?;
+        let secrets = self.client.secrets();
+        let res = match creds.project_id {
+            Some(project_id) => {
+                secrets
+                    .list_by_project(&SecretIdentifiersByProjectRequest { project_id })
+                    .await?
+            }
+            None => {
+                secrets
+                    .list(&SecretIdentifiersRequest {
+                        organization_id: creds.org_id,
+                    })
+                    .await?
+            }
+        };
         debug!("List Secrets: {:?}", res);
-        let data = res.data;
-        let secrets: HashMap<String, Uuid> = data
-            .iter()
-            .map(|secret| (secret.key.clone(), secret.id))
-            .collect();
 
-        Ok(secrets)
-    }
+        let secrets = res.data.iter().map(|secret| {
+            (
+                secret.key.as_str(),
+                secret.id,
+                secret.project_ids.as_slice(),
+            )
+        });
 
-    pub async fn get_secret(&self, key: &str) -> Result<(String, String)> {
-        let secrets_md = self.list_secrets().await?;
-        let id = match secrets_md.get(key) {
-            Some(id) => id,
-            None => bail!("Secret key <{key}> does not exist in bitwarden"),
-        };
+        Ok(secret_ids(secrets, creds.project_id))
+    }
 
-        let get_secret = SecretGetRequest { id: id.clone() };
+    async fn fetch_secret(&self, id: Uuid) -> Result<(String, String)> {
+        let get_secret = SecretGetRequest { id };
         let res: SecretResponse = self.client.secrets().get(&get_secret).await?;
         debug!("Get Secret: {:?}", res);
 
         Ok((res.value, res.note))
     }
+
+    /// Replaces the value of secret `id`, keeping its key, note and project.
+    async fn update_secret(&self, id: Uuid, value: &str) -> Result<()> {
+        let secrets = self.client.secrets();
+        let current = secrets.get(&SecretGetRequest { id }).await?;
+        let request = SecretPutRequest {
+            id,
+            organization_id: self.creds.org_id,
+            key: current.key,
+            value: value.to_string(),
+            note: current.note,
+            project_ids: current.project_id.map(|project_id| vec![project_id]),
+        };
+        secrets.update(&request).await?;
+        debug!("Updated Secret: {id}");
+
+        Ok(())
+    }
+
+    /// Stores `new_value` as the value of secret `key`, like a new Public API token.
+    /// The cached secrets are dropped, so the next `get_secret` sees the new value.
+    pub async fn rotate_secret(&self, key: &str, new_value: &str) -> Result<()> {
+        let mut cache = self.cache.lock().await;
+        cache
+            .rotate(
+                key,
+                || self.list_secrets(),
+                |id| self.update_secret(id, new_value),
+            )
+            .await
+    }
+
+    /// Value and note of the secret `key`, cached for the lifetime of `self`.
+    pub async fn get_secret(&self, key: &str) -> Result<(String, String)> {
+        let mut cache = self.cache.lock().await;
+        cache
+            .get(key, || self.list_secrets(), |id| self.fetch_secret(id))
+            .await
+    }
+
+    /// Drops the cached secrets, the next lookup goes to Bitwarden again.
+    pub fn invalidate_cache(&mut self) {
+        *self.cache.get_mut() = SecretCache::default();
+    }
+}
+
+/// Ids by key of the listed `(key, id, project ids)`, only the ones in
+/// `project_id` when set.
+fn secret_ids<'a>(
+    secrets: impl Iterator<Item = (&'a str, Uuid, &'a [Uuid])>,
+    project_id: Option<Uuid>,
+) -> HashMap<String, Uuid> {
+    secrets
+        .filter(|(_, _, project_ids)| project_id.is_none_or(|id| project_ids.contains(&id)))
+        .map(|(key, id, _)| (key.to_string(), id))
+        .collect()
+}
+
+/// Path of the Bitwarden config, `~/.bw.json`.
+pub(crate) fn bw_config_path() -> PathBuf {
+    let home_dir = env::home_dir().unwrap_or_default();
+
+    home_dir.join(PathBuf::from(BITWARDEN_CONFIG))
 }
 
 fn load_bw_creds_from_file() -> Result<BitwardenCreds> {
-    let home_dir = env::home_dir().unwrap_or(PathBuf::new());
-    let bw_config = home_dir.join(PathBuf::from(BITWARDEN_CONFIG));
-    let bitwarden_data = fs::read_to_string(bw_config)?;
+    let bitwarden_data = fs::read_to_string(bw_config_path())?;
     let config: BitwardenCreds = serde_json::from_str(&bitwarden_data)?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockSdk {
+        lists: AtomicUsize,
+        gets: AtomicUsize,
+        id: Uuid,
+        value: std::sync::Mutex<String>,
+    }
+
+    impl MockSdk {
+        fn new() -> MockSdk {
+            MockSdk {
+                lists: AtomicUsize::new(0),
+                gets: AtomicUsize::new(0),
+                id: Uuid::new_v4(),
+                value: std::sync::Mutex::new("secret".to_string()),
+            }
+        }
+
+        async fn list(&self) -> Result<HashMap<String, Uuid>> {
+            self.lists.fetch_add(1, Ordering::SeqCst);
+            Ok(HashMap::from([("public_secret".to_string(), self.id)]))
+        }
+
+        async fn get(&self, id: Uuid) -> Result<(String, String)> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(id, self.id);
+            Ok((self.value.lock().unwrap().clone(), "note".to_string()))
+        }
+
+        async fn update(&self, id: Uuid, value: &str) -> Result<()> {
+            assert_eq!(id, self.id);
+            *self.value.lock().unwrap() = value.to_string();
+            Ok(())
+        }
+
+        async fn rotate(&self, cache: &mut SecretCache, key: &str, value: &str) -> Result<()> {
+            cache
+                .rotate(key, || self.list(), |id| self.update(id, value))
+                .await
+        }
+
+        async fn lookup(&self, cache: &mut SecretCache, key: &str) -> Result<(String, String)> {
+            cache.get(key, || self.list(), |id| self.get(id)).await
+        }
+
+        fn calls(&self) -> (usize, usize) {
+            (
+                self.lists.load(Ordering::SeqCst),
+                self.gets.load(Ordering::SeqCst),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secret_cache() {
+        let sdk = MockSdk::new();
+        let mut cache = SecretCache::default();
+        for _ in 0..2 {
+            let secret = sdk.lookup(&mut cache, "public_secret").await.unwrap();
+            assert_eq!(secret, ("secret".to_string(), "note".to_string()));
+        }
+        assert_eq!(sdk.calls(), (1, 1));
+
+        // Unknown keys reuse the listed ids
+        assert!(sdk.lookup(&mut cache, "missing").await.is_err());
+        assert_eq!(sdk.calls(), (1, 1));
+
+        let mut cache = SecretCache::default();
+        sdk.lookup(&mut cache, "public_secret").await.unwrap();
+        assert_eq!(sdk.calls(), (2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_secret() {
+        let sdk = MockSdk::new();
+        let mut cache = SecretCache::default();
+        sdk.lookup(&mut cache, "public_secret").await.unwrap();
+
+        sdk.rotate(&mut cache, "public_secret", "rotated")
+            .await
+            .unwrap();
+        assert_eq!(*sdk.value.lock().unwrap(), "rotated");
+
+        // Fetched again rather than served from the cache
+        let (secret, _) = sdk.lookup(&mut cache, "public_secret").await.unwrap();
+        assert_eq!(secret, "rotated");
+        assert_eq!(sdk.calls(), (2, 2));
+
+        assert!(sdk.rotate(&mut cache, "missing", "rotated").await.is_err());
+    }
+
+    #[test]
+    fn test_bitwarden_creds() {
+        let org_id = Uuid::new_v4();
+        let json = format!(r#"{{"access_token": "token", "org_id": "{org_id}"}}"#);
+        let creds: BitwardenCreds = serde_json::from_str(&json).unwrap();
+        assert_eq!(creds.org_id, org_id);
+        assert_eq!(creds.project_id, None);
+
+        let project_id = Uuid::new_v4();
+        let json = format!(
+            r#"{{"access_token": "token", "org_id": "{org_id}", "project_id": "{project_id}"}}"#
+        );
+        let creds: BitwardenCreds = serde_json::from_str(&json).unwrap();
+        assert_eq!(creds.project_id, Some(project_id));
+    }
+
+    #[test]
+    fn test_secret_ids_by_project() {
+        let (trading, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let secrets = [
+            ("public_trading_secret_token", Uuid::new_v4(), vec![trading]),
+            ("db_password", Uuid::new_v4(), vec![other]),
+            ("shared", Uuid::new_v4(), vec![other, trading]),
+        ];
+        let listed = || {
+            secrets
+                .iter()
+                .map(|(key, id, project_ids)| (*key, *id, project_ids.as_slice()))
+        };
+
+        let mut keys: Vec<String> = secret_ids(listed(), Some(trading)).into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["public_trading_secret_token", "shared"]);
+        assert_eq!(secret_ids(listed(), None).len(), 3);
+        assert!(secret_ids(listed(), Some(Uuid::new_v4())).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_secret_ids_expire() {
+        let sdk = MockSdk::new();
+        let mut cache = SecretCache::default();
+        sdk.lookup(&mut cache, "public_secret").await.unwrap();
+        assert!(sdk.lookup(&mut cache, "missing").await.is_err());
+        assert_eq!(sdk.calls(), (1, 1));
+
+        cache.listed = Instant::now().checked_sub(SECRET_IDS_TTL);
+        assert!(sdk.lookup(&mut cache, "missing").await.is_err());
+        assert_eq!(sdk.calls(), (2, 1));
+    }
+}
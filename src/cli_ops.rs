@@ -1,4 +1,7 @@
+use chrono::NaiveDate;
 use clap::{Parser, Subcommand};
+use public_trading::public::AccountType;
+use std::path::PathBuf;
 use tracing::Level;
 
 #[derive(Parser, Debug)]
@@ -8,6 +11,14 @@ pub struct Cli {
     #[arg(long, short = 'L', default_value = "info", global = true)]
     pub log_level: Level,
 
+    /// Publish a CloudWatch metric for every option exit, in the region of the AWS environment
+    #[arg(long, global = true)]
+    pub metrics: bool,
+
+    /// Account type to trade and analyze with
+    #[arg(long, default_value = "brokerage", global = true)]
+    pub account: AccountType,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -21,29 +32,95 @@ pub enum Command {
         json: bool,
     },
 
+    /// Print the quotes of the `stocks` in the config file
+    ShowStocks {
+        /// Group of `stocks` to quote, like "tech", every group by default
+        #[arg(short = 'g')]
+        group: Option<String>,
+    },
+
     /// Analyze single Option to choose good entries
     AnalyzeOption {
-        /// Symbol of underlying to analyze options for
+        /// Symbol of underlying to analyze options for, the first of `options` in the config by default
+        symbol: Option<String>,
+
+        /// Analyze every symbol of `options` in the config instead of one
+        #[arg(long, conflicts_with_all = ["symbol", "csv"])]
+        all: bool,
+
+        /// Expiration to analyze, like "2026-02-27", the nearest monthly expiration by default
+        #[arg(long, short = 'e')]
+        expiration: Option<NaiveDate>,
+
+        /// Number of strikes to consider on each side of the money
+        #[arg(long, default_value_t = 10)]
+        strikes: usize,
+
+        /// Also write the chain to this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>,
+
+        /// Also print the volume of the chain by strike
+        #[arg(long)]
+        volume_histogram: bool,
+
+        /// Print the report as JSON instead of formatted text
+        #[arg(long)]
+        json: bool,
+
+        /// Only show the strikes with an absolute delta between MIN and MAX
+        #[arg(long, num_args = 2, value_names = ["MIN", "MAX"])]
+        delta_range: Option<Vec<f64>>,
+
+        /// Only show the strikes traded at least this many times today
+        #[arg(long, default_value_t = 0)]
+        min_volume: u64,
+    },
+
+    /// Compare the ATM IV and expected move of expirations of an equity
+    CompareExpirations {
+        /// Symbol of underlying to compare expirations of
         symbol: String,
 
-        /// Expiration to analyze, like "2026-02-27"
-        expiration: String,
+        /// Expirations to compare, like "2026-02-27", every listed expiration by default
+        expirations: Vec<String>,
     },
 
     /// Analyze Options from multiple Equities to choose good entries
     AnalyzeOptions {
         /// Expiration to analyze, like "2026-02-27"
         expiration: String,
-        /// Group of equities to analyze, read from config file.
+        /// Group of equities to analyze, read from config file: a group of the `stocks`
+        /// table like "tech", "stocks" for all of them, or a top level list.
         #[arg(short = 'g')]
         equities_group: String,
+
+        /// Suggest cash-secured puts on the equities instead
+        #[arg(long)]
+        csp: bool,
+
+        /// Absolute delta of the puts suggested with --csp
+        #[arg(long, default_value_t = 0.3)]
+        delta: f64,
+
+        /// Highest strike of the puts suggested with --csp
+        #[arg(long, default_value_t = f64::INFINITY)]
+        max_strike: f64,
+    },
+
+    /// Run every strategy set up in the config file, one after the other
+    Run {
+        /// Keep running every this many minutes, reloading the config file on changes
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        every: Option<u64>,
     },
 
     /// Monitor open options positions and suggest or execute exits
     OptionsStopper {
-        /// Gain-percent threshold below which a position should be exited (e.g. -200.0)
-        #[arg(long, default_value = "-200.0")]
-        threshold: f64,
+        /// Gain-percent threshold below which a position should be exited (e.g. -200.0).
+        /// Overrides `default_loss_threshold` from the `[stopper]` config table.
+        #[arg(long, allow_hyphen_values = true)]
+        threshold: Option<f64>,
 
         /// Print actions without fetching live quotes or placing orders
         #[arg(long)]
@@ -52,5 +129,153 @@ pub enum Command {
         /// Print actions of attempting an exit, but don't execute it.
         #[arg(long)]
         dry_run_exit: bool,
+
+        /// Place the exit orders, only logged otherwise.
+        /// Sets `execute` of the `[stopper]` config table.
+        #[arg(long, conflicts_with_all = ["dry_run", "dry_run_exit"])]
+        execute: bool,
+
+        /// Keep checking the positions every `--interval` minutes, during market hours
+        #[arg(long)]
+        daemon: bool,
+
+        /// Minutes between two checks with `--daemon`
+        #[arg(
+            long,
+            default_value_t = 5,
+            requires = "daemon",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        interval: u64,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_option_args() {
+        let cli = Cli::try_parse_from([
+            "public_trading",
+            "analyze-option",
+            "QCOM",
+            "--expiration",
+            "2026-03-20",
+            "--json",
+            "--delta-range",
+            "0.2",
+            "0.4",
+            "--min-volume",
+            "10",
+            "--account",
+            "high-yield",
+        ])
+        .unwrap();
+        assert_eq!(cli.account, AccountType::HighYield);
+        let Command::AnalyzeOption {
+            symbol,
+            expiration,
+            json,
+            delta_range,
+            min_volume,
+            csv,
+            ..
+        } = cli.command
+        else {
+            panic!("not analyze-option: {:?}", cli.command);
+        };
+        assert_eq!(symbol.as_deref(), Some("QCOM"));
+        assert_eq!(expiration, NaiveDate::from_ymd_opt(2026, 3, 20));
+        assert!(json);
+        assert_eq!(delta_range, Some(vec![0.2, 0.4]));
+        assert_eq!(min_volume, 10);
+        assert_eq!(csv, None);
+    }
+
+    #[test]
+    fn test_analyze_option_defaults() {
+        let cli = Cli::try_parse_from(["public_trading", "analyze-option"]).unwrap();
+        assert_eq!(cli.account, AccountType::Brokerage);
+        let Command::AnalyzeOption {
+            symbol,
+            expiration,
+            json,
+            delta_range,
+            min_volume,
+            ..
+        } = cli.command
+        else {
+            panic!("not analyze-option: {:?}", cli.command);
+        };
+        assert_eq!((symbol, expiration, json), (None, None, false));
+        assert_eq!((delta_range, min_volume), (None, 0));
+
+        // --delta-range takes both bounds
+        assert!(
+            Cli::try_parse_from(["public_trading", "analyze-option", "--delta-range", "0.2"])
+                .is_err()
+        );
+        // --all analyzes the config symbols, without a symbol of its own
+        assert!(Cli::try_parse_from(["public_trading", "analyze-option", "--all"]).is_ok());
+        assert!(
+            Cli::try_parse_from(["public_trading", "analyze-option", "QCOM", "--all"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_run_args() {
+        let every = |args: &[&str]| -> Result<Option<u64>, clap::Error> {
+            let cli = Cli::try_parse_from(["public_trading", "run"].iter().chain(args))?;
+            match cli.command {
+                Command::Run { every } => Ok(every),
+                command => panic!("not run: {command:?}"),
+            }
+        };
+
+        assert_eq!(every(&[]).unwrap(), None);
+        assert_eq!(every(&["--every", "30"]).unwrap(), Some(30));
+        assert!(every(&["--every", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_options_stopper_daemon_args() {
+        let stopper = |args: &[&str]| -> Result<(bool, u64), clap::Error> {
+            let cli =
+                Cli::try_parse_from(["public_trading", "options-stopper"].iter().chain(args))?;
+            match cli.command {
+                Command::OptionsStopper {
+                    daemon, interval, ..
+                } => Ok((daemon, interval)),
+                command => panic!("not options-stopper: {command:?}"),
+            }
+        };
+
+        assert_eq!(stopper(&[]).unwrap(), (false, 5));
+        assert_eq!(stopper(&["--daemon"]).unwrap(), (true, 5));
+        assert_eq!(
+            stopper(&["--daemon", "--interval", "15"]).unwrap(),
+            (true, 15)
+        );
+        // --interval only makes sense with --daemon
+        assert!(stopper(&["--interval", "15"]).is_err());
+        assert!(stopper(&["--daemon", "--interval", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_options_stopper_execute_args() {
+        let execute = |args: &[&str]| -> Result<bool, clap::Error> {
+            let cli =
+                Cli::try_parse_from(["public_trading", "options-stopper"].iter().chain(args))?;
+            match cli.command {
+                Command::OptionsStopper { execute, .. } => Ok(execute),
+                command => panic!("not options-stopper: {command:?}"),
+            }
+        };
+
+        assert!(!execute(&[]).unwrap());
+        assert!(execute(&["--execute"]).unwrap());
+        assert!(execute(&["--execute", "--dry-run"]).is_err());
+        assert!(execute(&["--execute", "--dry-run-exit"]).is_err());
+    }
+}
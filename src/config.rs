@@ -1,29 +1,360 @@
+use crate::influx::InfluxConfig;
+use crate::options::{AnalysisConfig, OptionConfig, StopperConfig};
+use crate::osi::OsiSymbol;
+use crate::public::RateLimit;
+
 use anyhow::{Result, bail};
+use futures::Stream;
 use serde::Deserialize;
 use tokio::fs;
 
-use std::{env, path::PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use toml::Value;
+use tracing::{debug, info, warn};
 
 const PUBLIC_DIR: &str = ".public";
 const PUBLIC_CONFIG: &str = "config.toml";
+#[cfg(feature = "yaml-config")]
+const PUBLIC_CONFIG_YAML: &str = "config.yaml";
+/// Env vars read by `Config::from_env`, with the config key each one sets.
+/// Lists are comma separated.
+const ENV_LISTS: [(&str, &str); 3] = [
+    ("PUBLIC_STOCKS", "stocks"),
+    ("PUBLIC_OPTIONS", "options"),
+    ("PUBLIC_CRYPTO", "crypto"),
+];
+const ENV_PROFILE: &str = "PUBLIC_PROFILE";
+/// How often `Config::watch` checks the config file for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_STOCK_SYMBOL_LEN: usize = 5;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A stop loss at or above zero would exit positions that haven't lost anything.
+    PositiveStopLoss { symbol: String, pct: f64 },
+    /// An option entry that is neither a ticker nor an OSI symbol.
+    InvalidOptionSymbol(String),
+    /// A stock symbol that isn't 1 to 5 capital letters.
+    InvalidStockSymbol(String),
+    /// A profit take below zero would exit positions that haven't gained anything.
+    NegativeProfitTake(f64),
+    /// `risk_per_trade` is a fraction of the account, in (0, 1].
+    InvalidRiskPerTrade(f64),
+    /// A reloaded config without options, rejected by `Config::watch` only.
+    NoOptions,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::PositiveStopLoss { symbol, pct } => {
+                write!(f, "stop loss {pct} of {symbol} must be negative")
+            }
+            ConfigError::InvalidOptionSymbol(s) => {
+                write!(f, "option <{s}> is neither a ticker nor an OSI symbol")
+            }
+            ConfigError::InvalidStockSymbol(s) => {
+                write!(
+                    f,
+                    "stock <{s}> must be 1 to {MAX_STOCK_SYMBOL_LEN} capital letters"
+                )
+            }
+            ConfigError::NegativeProfitTake(pct) => {
+                write!(f, "profit take threshold {pct} must not be negative")
+            }
+            ConfigError::InvalidRiskPerTrade(risk) => {
+                write!(f, "risk per trade {risk} must be in (0, 1]")
+            }
+            ConfigError::NoOptions => write!(f, "the options list is empty"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
 
-#[derive(Debug, Deserialize)]
+/// Errors of `Config::validate`, one per line.
+fn format_errors(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("\n  - {e}"))
+        .collect::<String>()
+}
+
+fn is_stock_symbol(symbol: &str) -> bool {
+    (1..=MAX_STOCK_SYMBOL_LEN).contains(&symbol.len())
+        && symbol.chars().all(|c| c.is_ascii_uppercase())
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 pub struct Config {
-    // pub stocks: Vec<String>,
     // pub options: Vec<String>,
     data: Value,
+    /// Stock symbols by group, read from `data` once.
+    #[serde(skip)]
+    stocks: HashMap<String, Vec<String>>,
+}
+
+/// Group of the stocks listed flat, as in `stocks = ['AAPL']` or `PUBLIC_STOCKS`.
+pub const DEFAULT_STOCKS_GROUP: &str = "default";
+
+/// Symbols of an array of bare symbols or tables with a `symbol`, None for other values.
+fn symbols(value: &Value) -> Option<Vec<String>> {
+    let array = value.as_array()?;
+
+    Some(
+        array
+            .iter()
+            .filter_map(|v| v.as_str().or_else(|| v.get("symbol")?.as_str()))
+            .map(|s| s.to_string())
+            .collect(),
+    )
+}
+
+/// Groups of the `stocks` key: a table of lists by group name, or a flat list
+/// in `DEFAULT_STOCKS_GROUP`.
+fn stock_groups(data: &Value) -> HashMap<String, Vec<String>> {
+    let Some(stocks) = data.get("stocks") else {
+        return HashMap::new();
+    };
+    if let Some(list) = symbols(stocks) {
+        return HashMap::from([(DEFAULT_STOCKS_GROUP.to_string(), list)]);
+    }
+
+    stocks
+        .as_table()
+        .into_iter()
+        .flatten()
+        .filter_map(|(group, list)| match symbols(list) {
+            Some(list) => Some((group.clone(), list)),
+            None => {
+                warn!("Ignoring stocks group {group}, it isn't a list");
+                None
+            }
+        })
+        .collect()
 }
 
 impl Config {
+    /// Reads `~/.public/config.toml`. With the `yaml-config` feature, falls back
+    /// to `~/.public/config.yaml` when the TOML config can't be read and the YAML one exists.
+    ///
+    /// # Panics
+    /// When the config doesn't pass `validate`, listing all of its errors.
     pub async fn new() -> Result<Config> {
+        let config = Self::read().await?;
+        if let Err(errors) = config.validate() {
+            panic!("Invalid config:{}", format_errors(&errors));
+        }
+
+        Ok(config)
+    }
+
+    async fn read() -> Result<Config> {
         let path = public_config_path();
-        println!("finding config in {path:?}");
-        let data = fs::read_to_string(path).await?;
-        Self::from_str(data.as_str())
+        debug!("finding config in {path:?}");
+        let toml = match fs::read_to_string(path).await {
+            Ok(data) => Self::from_str(data.as_str()),
+            Err(e) => Err(e.into()),
+        };
+
+        #[cfg(feature = "yaml-config")]
+        if toml.is_err() {
+            let path = public_config_path().with_file_name(PUBLIC_CONFIG_YAML);
+            if path.exists() {
+                debug!("finding config in {path:?}");
+                let data = fs::read_to_string(&path).await?;
+                return match Self::from_str_yaml(data.as_str()) {
+                    Some(config) => Ok(config),
+                    None => bail!("Err public::config: invalid YAML in {path:?}"),
+                };
+            }
+        }
+
+        toml
+    }
+
+    /// Config from the `PUBLIC_STOCKS`, `PUBLIC_OPTIONS` and `PUBLIC_CRYPTO` lists and the
+    /// `PUBLIC_PROFILE` creds profile, None when none of them is set.
+    pub fn from_env() -> Option<Config> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// The config file, or the env vars without one. None when neither has a config.
+    pub async fn new_with_fallback() -> Option<Config> {
+        match Self::new().await {
+            Ok(config) => Some(config),
+            Err(e) => {
+                info!("No config file ({e}), reading the config from env vars");
+                Self::from_env()
+            }
+        }
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Option<Config> {
+        let mut table = toml::Table::new();
+        for (name, key) in ENV_LISTS {
+            let Some(list) = var(name) else {
+                continue;
+            };
+            let symbols = list
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| Value::String(s.to_string()))
+                .collect();
+            table.insert(key.to_string(), Value::Array(symbols));
+        }
+        if let Some(profile) = var(ENV_PROFILE) {
+            table.insert("profile".to_string(), Value::String(profile));
+        }
+
+        if table.is_empty() {
+            return None;
+        }
+
+        Some(Config::from_value(Value::Table(table)))
+    }
+
+    fn from_value(data: Value) -> Config {
+        Config {
+            stocks: stock_groups(&data),
+            data,
+        }
+    }
+
+    /// Path of the config file, `~/.public/config.toml`.
+    pub fn path() -> PathBuf {
+        public_config_path()
+    }
+
+    /// Emits the config at `path` each time the file is modified, skipping
+    /// edits that don't parse or validate, or that empty the options list.
+    /// Polls the modification time, the config at the time of the call isn't emitted.
+    pub fn watch(path: PathBuf) -> impl Stream<Item = Config> {
+        Self::watch_every(path, WATCH_INTERVAL)
+    }
+
+    fn watch_every(path: PathBuf, interval: Duration) -> impl Stream<Item = Config> {
+        let last = modified(&path);
+        futures::stream::unfold((path, last), move |(path, mut last)| async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let now = modified(&path);
+                if now == last {
+                    continue;
+                }
+                last = now;
+
+                let data = match fs::read_to_string(&path).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("Cannot read config {path:?}: {e}");
+                        continue;
+                    }
+                };
+                let config = match Self::from_str(&data) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        warn!("Ignoring config change: {e}");
+                        continue;
+                    }
+                };
+                if let Err(errors) = config.validate_reload() {
+                    warn!("Ignoring invalid config change:{}", format_errors(&errors));
+                    continue;
+                }
+
+                info!("Reloaded config {path:?}");
+                return Some((config, (path, last)));
+            }
+        })
+    }
+
+    /// Rejects configs with option or stock symbols that can't be traded, stop losses
+    /// that aren't negative, a negative profit take, or a `risk_per_trade` out of
+    /// (0, 1]. Reports every error found, not just the first. Configs without
+    /// options are valid, like stocks only ones.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let options = self.options();
+
+        let stopper = self.stopper().unwrap_or_default();
+        // Options are configured by ticker, or by the full symbol of one contract
+        let option_symbols = options
+            .iter()
+            .map(|o| o.symbol.as_str())
+            .chain(stopper.symbol_overrides.keys().map(String::as_str));
+        for symbol in option_symbols {
+            if !is_stock_symbol(symbol) && OsiSymbol::parse(symbol).is_err() {
+                errors.push(ConfigError::InvalidOptionSymbol(symbol.to_string()));
+            }
+        }
+        for symbol in self.all_stocks() {
+            if !is_stock_symbol(symbol) {
+                errors.push(ConfigError::InvalidStockSymbol(symbol.clone()));
+            }
+        }
+
+        let mut stop_losses: Vec<(&str, f64)> = options
+            .iter()
+            .filter_map(|o| Some((o.symbol.as_str(), o.stop_loss_pct?)))
+            .chain(
+                stopper
+                    .symbol_overrides
+                    .iter()
+                    .map(|(symbol, pct)| (symbol.as_str(), *pct)),
+            )
+            .collect();
+        // Overrides are a HashMap, keep the errors in a stable order
+        stop_losses.sort_by(|a, b| a.0.cmp(b.0));
+        stop_losses.push(("default", stopper.default_loss_threshold));
+        for (symbol, pct) in stop_losses {
+            if pct > 0.0 {
+                errors.push(ConfigError::PositiveStopLoss {
+                    symbol: symbol.to_string(),
+                    pct,
+                });
+            }
+        }
+        if let Some(pct) = stopper.profit_take_threshold
+            && pct < 0.0
+        {
+            errors.push(ConfigError::NegativeProfitTake(pct));
+        }
+
+        if let Some(risk) = self.risk_per_trade()
+            && !(risk > 0.0 && risk <= 1.0)
+        {
+            errors.push(ConfigError::InvalidRiskPerTrade(risk));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
-    fn from_str(data: &str) -> Result<Config> {
+    /// `validate` for a config reloaded by a running strategy, also rejecting an
+    /// empty options list, which is more likely a bad edit than a switch to stocks only.
+    fn validate_reload(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = self.validate().err().unwrap_or_default();
+        if self.options().is_empty() {
+            errors.push(ConfigError::NoOptions);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub(crate) fn from_str(data: &str) -> Result<Config> {
         let value = match toml::from_str(data) {
             Ok(v) => v,
             Err(e) => {
@@ -31,29 +362,152 @@ impl Config {
             }
         };
 
-        Ok(Config { data: value })
+        Ok(Config::from_value(value))
     }
 
+    /// Config from YAML, with the same keys as the TOML config:
+    ///
+    /// ```yaml
+    /// stocks:
+    ///   tech: [AAPL]
+    /// options:
+    ///   - AAPL
+    ///   - { symbol: GOOG, profit_take_pct: 50.0 }
+    ///   - { symbol: RIVN, stop_loss_pct: -100.0, dte_exit: 7 }
+    /// stopper:
+    ///   default_loss_threshold: -50.0
+    /// ```
+    ///
+    /// Tables like `[stopper]` or `[analysis]` are mappings. None when `data`
+    /// isn't YAML, or uses YAML only values like null.
+    #[cfg(feature = "yaml-config")]
+    pub fn from_str_yaml(data: &str) -> Option<Config> {
+        match serde_yaml::from_str(data) {
+            Ok(value) => Some(Config::from_value(value)),
+            Err(e) => {
+                warn!("Err public::config: {e}");
+                None
+            }
+        }
+    }
+
+    /// Symbols listed in `field`, either bare or as the `symbol` of a table.
     pub fn get(&self, field: &str) -> Option<Vec<String>> {
-        if let Some(val) = self.data.get(field) {
-            if let Some(array) = val.as_array() {
-                return Some(
-                    array
-                        .iter()
-                        .map(Value::as_str)
-                        .filter_map(|s| s)
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>(),
-                );
+        symbols(self.data.get(field)?)
+    }
+
+    /// Stocks of every group of the `stocks` table, groups sorted by name,
+    /// each stock once.
+    pub fn all_stocks(&self) -> Vec<&String> {
+        let mut groups: Vec<_> = self.stocks.iter().collect();
+        groups.sort_by_key(|(group, _)| *group);
+        let mut seen = HashSet::new();
+
+        groups
+            .into_iter()
+            .flat_map(|(_, stocks)| stocks)
+            .filter(|stock| seen.insert(*stock))
+            .collect()
+    }
+
+    /// Stocks of `group` in the `stocks` table, like "tech".
+    pub fn stocks_in_group(&self, group: &str) -> Option<&Vec<String>> {
+        self.stocks.get(group)
+    }
+
+    /// Equities of a group to analyze: every stock for "stocks", a group of the
+    /// `stocks` table, or else a top level list like `tech = ['QCOM']`.
+    pub fn equities_group(&self, group: &str) -> Option<Vec<String>> {
+        if group == "stocks" {
+            let stocks = self.all_stocks();
+            return (!stocks.is_empty()).then(|| stocks.into_iter().cloned().collect());
+        }
+
+        self.stocks_in_group(group)
+            .cloned()
+            .or_else(|| self.get(group))
+    }
+
+    /// Creds profile to use, from the top level `profile` key.
+    pub fn profile(&self) -> Option<String> {
+        self.data.get("profile")?.as_str().map(String::from)
+    }
+
+    /// Fraction of the account value risked on one trade, from the top level
+    /// `risk_per_trade` key.
+    pub fn risk_per_trade(&self) -> Option<f64> {
+        let risk = self.data.get("risk_per_trade")?;
+        risk.as_float().or_else(|| Some(risk.as_integer()? as f64))
+    }
+
+    /// Crypto symbols tracked alongside the stocks, from the `crypto` list.
+    pub fn crypto(&self) -> Vec<String> {
+        self.get("crypto").unwrap_or_default()
+    }
+
+    /// Reads the `options` list, with the risk parameters of each symbol.
+    pub fn options(&self) -> Vec<OptionConfig> {
+        self.table("options").unwrap_or_default()
+    }
+
+    /// Reads the `[rate_limit]` table, if present.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.table("rate_limit")
+    }
+
+    /// Reads the `[stopper]` table, if present.
+    pub fn stopper(&self) -> Option<StopperConfig> {
+        self.table("stopper")
+    }
+
+    /// Reads the `[analysis]` table, if present.
+    pub fn analysis(&self) -> Option<AnalysisConfig> {
+        self.table("analysis")
+    }
+
+    /// Equities and expiration to analyze, from the `equities_group` and
+    /// `expiration` of `[analysis]`.
+    pub fn watchlist(&self) -> Option<(Vec<String>, String)> {
+        let analysis = self.analysis()?;
+        let (group, expiration) = (analysis.equities_group?, analysis.expiration?);
+        match self.equities_group(&group) {
+            Some(equities) => Some((equities, expiration)),
+            None => {
+                warn!("Config doesnt contain group \"{group}\"");
+                None
             }
         }
+    }
+
+    /// Reads the `[news]` table, if present.
+    #[cfg(feature = "news")]
+    pub fn news(&self) -> Option<crate::public::NewsConfig> {
+        self.table("news")
+    }
+
+    /// Reads the `[influx]` table, if present.
+    pub fn influx(&self) -> Option<InfluxConfig> {
+        self.table("influx")
+    }
 
-        None
+    fn table<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let table = self.data.get(name)?.clone();
+        match table.try_into() {
+            Ok(t) => Some(t),
+            Err(e) => {
+                warn!("Ignoring invalid [{name}] config: {e}");
+                None
+            }
+        }
     }
 }
 
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
 fn public_config_path() -> PathBuf {
-    let home_dir = env::home_dir().unwrap_or(PathBuf::new());
+    let home_dir = env::home_dir().unwrap_or_default();
 
     home_dir.join(PathBuf::from(format!("{PUBLIC_DIR}/{PUBLIC_CONFIG}")))
 }
@@ -65,10 +519,396 @@ mod tests {
 
     const TEST_CONFIG: &str = include_str!("fixtures/public_config.toml");
 
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_config_from_str_yaml() {
+        let yaml = include_str!("fixtures/public_config.yaml");
+        let config = Config::from_str_yaml(yaml).unwrap();
+        assert_eq!(config, Config::from_str(TEST_CONFIG).unwrap());
+        assert!(config.validate().is_ok());
+        assert!(Config::from_str_yaml("stocks: [AAPL").is_none());
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars = std::collections::HashMap::from([
+            ("PUBLIC_STOCKS", "AAPL, AMZN,,QCOM"),
+            ("PUBLIC_OPTIONS", "QCOM"),
+            ("PUBLIC_PROFILE", "paper"),
+        ]);
+        let config = Config::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.all_stocks(), vec!["AAPL", "AMZN", "QCOM"]);
+        let stocks = config.stocks_in_group(DEFAULT_STOCKS_GROUP).unwrap();
+        assert_eq!(stocks, &vec!["AAPL", "AMZN", "QCOM"]);
+        let options: Vec<String> = config.options().into_iter().map(|o| o.symbol).collect();
+        assert_eq!(options, vec!["QCOM"]);
+        assert_eq!(config.profile().as_deref(), Some("paper"));
+        assert!(config.validate().is_ok());
+
+        let config = Config::from_vars(|name| (name == "PUBLIC_STOCKS").then(String::new)).unwrap();
+        assert!(config.all_stocks().is_empty());
+        assert_eq!(config.get("options"), None);
+        assert_eq!(Config::from_vars(|_| None), None);
+    }
+
+    #[test]
+    fn test_stock_groups() {
+        let config = Config::from_str(TEST_CONFIG).unwrap();
+        let tech = config.stocks_in_group("tech").unwrap();
+        assert_eq!(tech, &vec!["AAPL", "AMD"]);
+        let healthcare = config.stocks_in_group("healthcare").unwrap();
+        assert_eq!(healthcare, &vec!["UNH"]);
+        assert_eq!(config.stocks_in_group("energy"), None);
+        assert_eq!(config.stocks_in_group(DEFAULT_STOCKS_GROUP), None);
+
+        // A stock in several groups is listed once
+        let config = Config::from_str(
+            "[stocks]\ntech = ['AAPL', { symbol = 'AMD' }]\nfavorites = ['AMD', 'UNH']\n",
+        )
+        .unwrap();
+        assert_eq!(config.all_stocks(), vec!["AMD", "UNH", "AAPL"]);
+        let tech = config.stocks_in_group("tech").unwrap();
+        assert_eq!(tech, &vec!["AAPL", "AMD"]);
+
+        // A flat list is the default group
+        let config = Config::from_str("stocks = ['AAPL', 'AMZN']\n").unwrap();
+        assert_eq!(config.all_stocks(), vec!["AAPL", "AMZN"]);
+        let stocks = config.stocks_in_group(DEFAULT_STOCKS_GROUP).unwrap();
+        assert_eq!(stocks, &vec!["AAPL", "AMZN"]);
+
+        assert!(
+            Config::from_str("crypto = ['BTC']\n")
+                .unwrap()
+                .all_stocks()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_config_parse() {
         let config = Config::from_str(TEST_CONFIG).unwrap();
-        assert_eq!(config.get("stocks"), Some(vec!["AAPL".to_string()]));
+        assert_eq!(config.all_stocks(), vec!["UNH", "AAPL", "AMD"]);
         assert_eq!(config.get("options").unwrap().len(), 7);
+        assert_eq!(config.rate_limit(), None);
+        assert_eq!(config.stopper(), None);
+        assert_eq!(config.influx(), None);
+        assert_eq!(config.analysis(), None);
+        assert_eq!(config.profile(), None);
+        assert!(config.crypto().is_empty());
+
+        let config = Config::from_str("crypto = ['BTC', 'ETH']\n").unwrap();
+        assert_eq!(config.crypto(), vec!["BTC", "ETH"]);
+    }
+
+    #[test]
+    fn test_config_profile() {
+        let config = Config::from_str("profile = \"ira\"\n").unwrap();
+        assert_eq!(config.profile(), Some("ira".to_string()));
+    }
+
+    #[test]
+    fn test_config_options() {
+        let config = Config::from_str(TEST_CONFIG).unwrap();
+        let options = config.options();
+        assert_eq!(options.len(), 7);
+        assert_eq!(
+            options[0],
+            OptionConfig {
+                symbol: "AAPL".to_string(),
+                ..OptionConfig::default()
+            }
+        );
+        assert_eq!(options[3].profit_take_pct, Some(50.0));
+        assert_eq!(
+            options[5],
+            OptionConfig {
+                symbol: "RIVN".to_string(),
+                stop_loss_pct: Some(-100.0),
+                profit_take_pct: None,
+                dte_exit: Some(7),
+                max_quantity: None,
+            }
+        );
+        assert_eq!(options[6].max_quantity, Some(2));
+    }
+
+    #[test]
+    fn test_config_options_flat() {
+        let config = Config::from_str("options = ['AAPL', 'AMZN']\n").unwrap();
+        let symbols: Vec<String> = config.options().into_iter().map(|o| o.symbol).collect();
+        assert_eq!(symbols, vec!["AAPL", "AMZN"]);
+        assert!(config.options().iter().all(|o| o.stop_loss_pct.is_none()));
+        assert_eq!(config.get("options").unwrap(), symbols);
+
+        assert!(Config::from_str("").unwrap().options().is_empty());
+    }
+
+    #[test]
+    fn test_config_analysis() {
+        let config = Config::from_str("[analysis]\nvol_oi_threshold = 2.5\n").unwrap();
+        assert_eq!(
+            config.analysis(),
+            Some(AnalysisConfig {
+                vol_oi_threshold: 2.5,
+                ..AnalysisConfig::default()
+            })
+        );
+        let config = Config::from_str("[analysis]\n").unwrap();
+        assert_eq!(config.analysis(), Some(AnalysisConfig::default()));
+    }
+
+    #[test]
+    fn test_config_influx() {
+        let config =
+            Config::from_str("[influx]\nurl = \"http://localhost:8181\"\ndatabase = \"stonks\"\n")
+                .unwrap();
+        assert_eq!(
+            config.influx(),
+            Some(InfluxConfig {
+                url: "http://localhost:8181".to_string(),
+                database: "stonks".to_string(),
+                token: None,
+            })
+        );
+    }
+
+    #[cfg(feature = "news")]
+    #[test]
+    fn test_config_news() {
+        use crate::public::{ALPACA_NEWS_API, NewsConfig};
+
+        let config = Config::from_str("[news]\nkey_id = 'key'\nsecret_key = 'secret'\n").unwrap();
+        assert_eq!(
+            config.news(),
+            Some(NewsConfig {
+                url: ALPACA_NEWS_API.to_string(),
+                key_id: "key".to_string(),
+                secret_key: "secret".to_string(),
+            })
+        );
+        assert_eq!(Config::from_str(TEST_CONFIG).unwrap().news(), None);
+    }
+
+    #[test]
+    fn test_config_stopper() {
+        let config = Config::from_str(
+            r#"
+            [stopper]
+            default_loss_threshold = -150.0
+            profit_take_threshold = 50.0
+            dte_floor = 5
+
+            [stopper.symbol_overrides]
+            QCOM = -100.0
+            "#,
+        )
+        .unwrap();
+        let stopper = config.stopper().unwrap();
+        assert_eq!(stopper.default_loss_threshold, -150.0);
+        assert_eq!(stopper.profit_take_threshold, Some(50.0));
+        assert_eq!(stopper.dte_floor, 5);
+        assert_eq!(stopper.symbol_overrides.get("QCOM"), Some(&-100.0));
+        assert!(!stopper.dry_run);
+        assert!(!stopper.execute);
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let errors = |data: &str| Config::from_str(data).unwrap().validate().unwrap_err();
+
+        assert!(Config::from_str(TEST_CONFIG).unwrap().validate().is_ok());
+        // Options aren't required, like for a stocks only config, unless reloaded
+        let stocks_only = Config::from_str("stocks = ['AAPL']\n").unwrap();
+        assert!(stocks_only.validate().is_ok());
+        assert!(matches!(
+            &stocks_only.validate_reload().unwrap_err()[..],
+            [ConfigError::NoOptions]
+        ));
+
+        let config = "options = [{ symbol = 'QCOM', stop_loss_pct = 50.0 }]\n";
+        assert!(matches!(
+            &errors(config)[..],
+            [ConfigError::PositiveStopLoss { symbol, pct }] if symbol == "QCOM" && *pct == 50.0
+        ));
+
+        let config = "options = ['QCOM']\n[stopper]\ndefault_loss_threshold = 10.0\n";
+        assert!(matches!(
+            &errors(config)[..],
+            [ConfigError::PositiveStopLoss { symbol, .. }] if symbol == "default"
+        ));
+
+        let config = "options = ['QCOM']\n[stopper]\nprofit_take_threshold = -5.0\n";
+        assert!(matches!(
+            errors(config)[..],
+            [ConfigError::NegativeProfitTake(pct)] if pct == -5.0
+        ));
+    }
+
+    #[test]
+    fn test_config_validate_symbols() {
+        let config = Config::from_str(
+            r#"
+            stocks = ['AAPL', 'aapl', 'GOOGLE', '']
+            options = ['QCOM', 'LMND251219C00060000', 'LMND251319C00060000', 'qcom']
+
+            [stopper.symbol_overrides]
+            'MU260417P00830000' = -100.0
+            'MU 830P' = -100.0
+            "#,
+        )
+        .unwrap();
+        let invalid: Vec<String> = config
+            .validate()
+            .unwrap_err()
+            .iter()
+            .map(|e| match e {
+                ConfigError::InvalidOptionSymbol(s) => format!("option {s}"),
+                ConfigError::InvalidStockSymbol(s) => format!("stock {s}"),
+                e => panic!("unexpected error {e}"),
+            })
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![
+                "option LMND251319C00060000",
+                "option qcom",
+                "option MU 830P",
+                "stock aapl",
+                "stock GOOGLE",
+                "stock ",
+            ]
+        );
+
+        // Groups are validated as one list
+        let config = Config::from_str("options = ['QCOM']\n[stocks]\ntech = ['AAPL', 'amd']\n");
+        let errors = config.unwrap().validate().unwrap_err();
+        assert!(matches!(&errors[..], [ConfigError::InvalidStockSymbol(s)] if s == "amd"));
+    }
+
+    #[test]
+    fn test_config_validate_risk_per_trade() {
+        let config = |risk: &str| {
+            Config::from_str(&format!("options = ['QCOM']\nrisk_per_trade = {risk}\n"))
+        };
+        assert_eq!(config("0.02").unwrap().risk_per_trade(), Some(0.02));
+        assert!(config("0.02").unwrap().validate().is_ok());
+        assert!(config("1").unwrap().validate().is_ok());
+        for risk in ["0.0", "-0.1", "1.5", "2"] {
+            assert!(matches!(
+                config(risk).unwrap().validate().unwrap_err()[..],
+                [ConfigError::InvalidRiskPerTrade(_)]
+            ));
+        }
+
+        // Every error is reported at once
+        let config =
+            "stocks = ['aapl']\nrisk_per_trade = 2.0\n[stopper]\ndefault_loss_threshold = 10.0\n";
+        let errors = Config::from_str(config).unwrap().validate().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            format_errors(&errors),
+            "\n  - stock <aapl> must be 1 to 5 capital letters\
+             \n  - stop loss 10 of default must be negative\
+             \n  - risk per trade 2 must be in (0, 1]"
+        );
+    }
+
+    #[test]
+    fn test_config_watchlist() {
+        let config = Config::from_str(
+            "tech = ['QCOM', 'AMD']\n[analysis]\nequities_group = 'tech'\nexpiration = '2026-03-20'\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.watchlist(),
+            Some((
+                vec!["QCOM".to_string(), "AMD".to_string()],
+                "2026-03-20".to_string()
+            ))
+        );
+
+        let config =
+            Config::from_str("[analysis]\nequities_group = 'tech'\nexpiration = '2026-03-20'\n");
+        assert_eq!(config.unwrap().watchlist(), None);
+        assert_eq!(Config::from_str(TEST_CONFIG).unwrap().watchlist(), None);
+
+        // Groups of the stocks table
+        let config = Config::from_str(&format!(
+            "{TEST_CONFIG}\n[analysis]\nequities_group = 'tech'\nexpiration = '2026-03-20'\n"
+        ))
+        .unwrap();
+        let (equities, _) = config.watchlist().unwrap();
+        assert_eq!(equities, vec!["AAPL", "AMD"]);
+    }
+
+    #[test]
+    fn test_equities_group() {
+        let config = Config::from_str(TEST_CONFIG).unwrap();
+        assert_eq!(config.equities_group("tech").unwrap(), vec!["AAPL", "AMD"]);
+        assert_eq!(config.equities_group("healthcare").unwrap(), vec!["UNH"]);
+        let stocks = config.equities_group("stocks").unwrap();
+        assert_eq!(stocks, vec!["UNH", "AAPL", "AMD"]);
+        assert_eq!(config.equities_group("energy"), None);
+
+        // The flat format, and top level lists
+        let config = Config::from_str("stocks = ['AAPL']\ntech = ['QCOM', 'AMD']\n").unwrap();
+        assert_eq!(config.equities_group("stocks").unwrap(), vec!["AAPL"]);
+        assert_eq!(config.equities_group("tech").unwrap(), vec!["QCOM", "AMD"]);
+        let config = Config::from_str("crypto = ['BTC']\n").unwrap();
+        assert_eq!(config.equities_group("stocks"), None);
+    }
+
+    #[tokio::test]
+    async fn test_config_watch() {
+        use futures::StreamExt;
+        use std::fs::File;
+
+        let path = env::temp_dir().join(format!("config_{}.toml", uuid::Uuid::new_v4()));
+        let write = |data: &str, secs: u64| {
+            std::fs::write(&path, data).unwrap();
+            let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        write("options = ['AAPL']\n", 1_000);
+
+        let watch = Config::watch_every(path.clone(), Duration::from_millis(10));
+        let mut watch = Box::pin(watch);
+
+        write("options = ['AAPL', 'QCOM']\n", 2_000);
+        let config = watch.next().await.unwrap();
+        assert_eq!(config.get("options").unwrap(), vec!["AAPL", "QCOM"]);
+
+        // Invalid edits are skipped
+        write("options = ['qcom']\n", 3_000);
+        let next = tokio::time::timeout(Duration::from_millis(100), watch.next()).await;
+        assert!(next.is_err());
+        write("stocks = ['AAPL']\n", 3_500);
+        let next = tokio::time::timeout(Duration::from_millis(100), watch.next()).await;
+        assert!(next.is_err());
+
+        write("options = ['AMD']\n", 4_000);
+        let config = watch.next().await.unwrap();
+        assert_eq!(config, Config::from_str("options = ['AMD']\n").unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_config_rate_limit() {
+        let config =
+            Config::from_str("[rate_limit]\nrequests_per_second = 2\nrequests_per_minute = 60\n")
+                .unwrap();
+        assert_eq!(
+            config.rate_limit(),
+            Some(RateLimit {
+                requests_per_second: 2,
+                requests_per_minute: 60,
+            })
+        );
     }
 }
@@ -0,0 +1,251 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::{fmt, io};
+
+use crate::options::{AnalysisReport, ExitSummary, OptionRow, PositionSummary};
+
+#[derive(Debug)]
+pub enum ExportError {
+    Csv(csv::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Csv(e) => write!(f, "csv export failed: {e}"),
+            ExportError::Io(e) => write!(f, "export write failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<csv::Error> for ExportError {
+    fn from(e: csv::Error) -> Self {
+        ExportError::Csv(e)
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+/// One option of the chain, greeks are left empty when the API had none.
+#[derive(Serialize)]
+struct CsvRow {
+    symbol: String,
+    side: &'static str,
+    strike: f64,
+    bid: f64,
+    ask: f64,
+    mid: f64,
+    volume: u64,
+    open_interest: u64,
+    delta: Option<f64>,
+    gamma: Option<f64>,
+    theta: Option<f64>,
+    vega: Option<f64>,
+    iv: Option<f64>,
+}
+
+impl CsvRow {
+    fn new(row: &OptionRow, side: &'static str) -> CsvRow {
+        let greeks = row.greeks.as_ref().map(|g| &g.greeks);
+        CsvRow {
+            symbol: row.symbol.to_string(),
+            side,
+            strike: row.strike,
+            bid: row.bid,
+            ask: row.ask,
+            mid: (row.bid + row.ask) / 2.0,
+            volume: row.volume,
+            open_interest: row.open_interest,
+            delta: greeks.map(|g| g.delta),
+            gamma: greeks.map(|g| g.gamma),
+            theta: greeks.map(|g| g.theta),
+            vega: greeks.map(|g| g.vega),
+            iv: greeks.map(|g| g.implied_volatility),
+        }
+    }
+}
+
+/// Writes the calls and puts of `report` as CSV, one row per option
+/// sorted by strike, calls before puts.
+pub fn export_to_csv(report: &AnalysisReport, writer: impl io::Write) -> Result<(), ExportError> {
+    let mut rows: Vec<CsvRow> = report
+        .calls
+        .iter()
+        .map(|row| CsvRow::new(row, "call"))
+        .chain(report.puts.iter().map(|row| CsvRow::new(row, "put")))
+        .collect();
+    rows.sort_by(|a, b| a.strike.total_cmp(&b.strike).then(a.side.cmp(b.side)));
+
+    let mut csv = csv::Writer::from_writer(writer);
+    for row in rows {
+        csv.serialize(row)?;
+    }
+    csv.flush()?;
+
+    Ok(())
+}
+
+/// Writes `positions` as pretty JSON to `{dir}/{YYYY-MM-DD}.json` for today,
+/// replacing an earlier snapshot of the day.
+pub fn snapshot_portfolio(positions: &[PositionSummary], dir: &Path) -> Result<(), io::Error> {
+    snapshot_portfolio_on(positions, dir, Local::now().date_naive())
+}
+
+fn snapshot_portfolio_on(
+    positions: &[PositionSummary],
+    dir: &Path,
+    date: NaiveDate,
+) -> Result<(), io::Error> {
+    fs::create_dir_all(dir)?;
+    let data = serde_json::to_string_pretty(positions)?;
+
+    fs::write(dir.join(format!("{date}.json")), data)
+}
+
+/// Writes `summary` as pretty JSON to `{dir}/{YYYY-MM-DD-HH-MM}.json` for now.
+pub fn write_run_summary(summary: &ExitSummary, dir: &Path) -> Result<(), io::Error> {
+    write_run_summary_at(summary, dir, Local::now())
+}
+
+fn write_run_summary_at(
+    summary: &ExitSummary,
+    dir: &Path,
+    time: DateTime<Local>,
+) -> Result<(), io::Error> {
+    fs::create_dir_all(dir)?;
+    let data = serde_json::to_string_pretty(summary)?;
+
+    fs::write(
+        dir.join(format!("{}.json", time.format("%Y-%m-%d-%H-%M"))),
+        data,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::AnalysisConfig;
+    use crate::public::{Greeks, OptionChain, OptionGreeks, OptionType, OrderSide};
+    use std::collections::HashMap;
+    use std::env;
+
+    const OPTION_CHAIN: &str = include_str!("fixtures/option_chain.json");
+
+    #[test]
+    fn test_export_to_csv() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let greeks = HashMap::from([(
+            "LMND251219C00041000".to_string(),
+            OptionGreeks {
+                symbol: "LMND251219C00041000".to_string(),
+                greeks: Greeks {
+                    delta: 0.81,
+                    gamma: 0.01,
+                    theta: -0.02,
+                    vega: 0.05,
+                    rho: 0.03,
+                    implied_volatility: 0.9,
+                },
+            },
+        )]);
+        let report = AnalysisReport::new(
+            "LMND".to_string(),
+            NaiveDate::from_ymd_opt(2025, 12, 19).unwrap(),
+            Some(40.3),
+            &chain,
+            3,
+            &greeks,
+            &AnalysisConfig::default(),
+        );
+
+        let mut out = Vec::new();
+        export_to_csv(&report, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "symbol,side,strike,bid,ask,mid,volume,open_interest,delta,gamma,theta,vega,iv"
+        );
+        assert_eq!(lines.len(), 1 + report.calls.len() + report.puts.len());
+        let with_greeks = lines
+            .iter()
+            .find(|l| l.starts_with("LMND251219C00041000"))
+            .unwrap();
+        assert!(
+            with_greeks.ends_with(",0.81,0.01,-0.02,0.05,0.9"),
+            "{with_greeks}"
+        );
+        let without_greeks = lines.iter().find(|l| l.contains(",put,")).unwrap();
+        assert!(without_greeks.ends_with(",,,,,"), "{without_greeks}");
+    }
+
+    #[test]
+    fn test_snapshot_portfolio() {
+        let dir = env::temp_dir().join(format!("snapshots_{}", uuid::Uuid::new_v4()));
+        let positions = vec![PositionSummary {
+            symbol: "QCOM260320C00150000".to_string(),
+            ticker: "QCOM".to_string(),
+            strike: 150.0,
+            expiration: NaiveDate::from_ymd_opt(2026, 3, 20).unwrap(),
+            side: OrderSide::Buy,
+            option_type: OptionType::Call,
+            quantity: 2,
+            cost: 500.0,
+            last_price: 3.1,
+            gain_value: 120.0,
+            gain_percent: 24.0,
+            delta: Some(0.45),
+            theta: None,
+            implied_volatility: None,
+        }];
+        let date = NaiveDate::from_ymd_opt(2026, 2, 17).unwrap();
+        snapshot_portfolio_on(&positions, &dir, date).unwrap();
+
+        let data = fs::read_to_string(dir.join("2026-02-17.json")).unwrap();
+        let snapshot: Vec<PositionSummary> = serde_json::from_str(&data).unwrap();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].symbol, "QCOM260320C00150000");
+        assert_eq!(snapshot[0].delta, Some(0.45));
+        assert_eq!(snapshot[0].option_type, OptionType::Call);
+
+        snapshot_portfolio(&positions, &dir).unwrap();
+        let today = format!("{}.json", Local::now().date_naive());
+        assert!(dir.join(today).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_run_summary() {
+        use chrono::TimeZone;
+
+        let dir = env::temp_dir().join(format!("runs_{}", uuid::Uuid::new_v4()));
+        let summary = ExitSummary {
+            total_positions: 3,
+            positions_exited: 1,
+            exits_not_placed: 1,
+            total_gain_value_closed: -150.0,
+            exit_reasons: HashMap::from([("stop loss".to_string(), 1)]),
+        };
+        let time = Local.with_ymd_and_hms(2026, 2, 17, 15, 30, 0).unwrap();
+        write_run_summary_at(&summary, &dir, time).unwrap();
+
+        let data = fs::read_to_string(dir.join("2026-02-17-15-30.json")).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(written["total_positions"], 3);
+        assert_eq!(written["exits_not_placed"], 1);
+        assert_eq!(written["total_gain_value_closed"], -150.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
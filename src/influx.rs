@@ -0,0 +1,612 @@
+use chrono::{DateTime, Utc};
+use influxdb::{Client, InfluxDbWriteable, ReadQuery, WriteQuery};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{env, fmt, future::Future};
+
+use crate::osi::OsiSymbol;
+use crate::public::{AccountType, OptionGreeks, Position, Quote};
+
+const QUOTES: &str = "quotes";
+const GREEKS: &str = "greeks";
+const PORTFOLIO: &str = "portfolio";
+const POSITIONS: &str = "positions";
+const INFLUX_TOKEN_VAR: &str = "INFLUXDB3_AUTH_TOKEN";
+
+/// Connection settings from the `[influx]` config table.
+/// The token falls back to the `INFLUXDB3_AUTH_TOKEN` env var.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct InfluxConfig {
+    pub url: String,
+    pub database: String,
+    pub token: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum InfluxError {
+    Influx(influxdb::Error),
+    InvalidTimestamp(String),
+    InvalidQuery(String),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for InfluxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InfluxError::Influx(e) => write!(f, "influxdb error: {e}"),
+            InfluxError::InvalidTimestamp(e) => write!(f, "invalid measurement timestamp: {e}"),
+            InfluxError::InvalidQuery(e) => write!(f, "invalid influxdb query: {e}"),
+            InfluxError::InvalidResponse(e) => write!(f, "invalid influxdb response: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for InfluxError {}
+
+impl From<influxdb::Error> for InfluxError {
+    fn from(e: influxdb::Error) -> Self {
+        InfluxError::Influx(e)
+    }
+}
+
+/// Where measurements are written and read from. Implemented by `influxdb::Client`.
+pub trait InfluxSink {
+    fn write(
+        &self,
+        queries: Vec<WriteQuery>,
+    ) -> impl Future<Output = Result<(), influxdb::Error>> + Send;
+
+    /// Runs an InfluxQL query, returning the JSON body of the response.
+    fn read(
+        &self,
+        query: ReadQuery,
+    ) -> impl Future<Output = Result<String, influxdb::Error>> + Send;
+}
+
+impl InfluxSink for Client {
+    async fn write(&self, queries: Vec<WriteQuery>) -> Result<(), influxdb::Error> {
+        self.query(queries).await.map(|_| ())
+    }
+
+    async fn read(&self, query: ReadQuery) -> Result<String, influxdb::Error> {
+        self.query(query).await
+    }
+}
+
+#[derive(InfluxDbWriteable)]
+struct QuoteMeasure {
+    time: DateTime<Utc>,
+    #[influxdb(tag)]
+    ticker: String,
+    #[influxdb(tag)]
+    instrument_type: String,
+    bid: f64,
+    ask: f64,
+    last: f64,
+    volume: u64,
+    open_interest: u64,
+    /// Left out when there is no market, Influx can't store infinity.
+    spread_pct: Option<f64>,
+}
+
+/// One option position in the `positions` measurement, for P&L over time.
+#[derive(Clone, Debug, InfluxDbWriteable, PartialEq)]
+pub struct OptionPosInflux {
+    pub time: DateTime<Utc>,
+    pub gain_pct: f64,
+    pub gain_value: f64,
+    pub cost: f64,
+    pub quantity: i32,
+    #[influxdb(tag)]
+    pub symbol: String,
+    #[influxdb(tag)]
+    pub ticker: String,
+    #[influxdb(tag)]
+    pub op_type: String,
+    #[influxdb(tag)]
+    pub side: String,
+}
+
+/// A point of the `quotes` measurement, as read back by `QuoteWriter::query_recent`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct StockMeasure {
+    pub time: DateTime<Utc>,
+    pub ticker: String,
+    pub instrument_type: String,
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64,
+    pub volume: u64,
+    pub open_interest: u64,
+    pub spread_pct: Option<f64>,
+}
+
+/// Body of an InfluxQL query response: the rows of each series, as columns and values.
+#[derive(Deserialize)]
+struct ReadResponse {
+    results: Vec<ReadResult>,
+}
+
+#[derive(Deserialize)]
+struct ReadResult {
+    #[serde(default)]
+    series: Vec<ReadSeries>,
+}
+
+#[derive(Deserialize)]
+struct ReadSeries {
+    columns: Vec<String>,
+    values: Vec<Vec<Value>>,
+}
+
+#[derive(InfluxDbWriteable)]
+struct GreeksMeasure {
+    time: DateTime<Utc>,
+    #[influxdb(tag)]
+    ticker: String,
+    #[influxdb(tag)]
+    symbol: String,
+    delta: f64,
+    gamma: f64,
+    theta: f64,
+    vega: f64,
+    rho: f64,
+    implied_volatility: f64,
+}
+
+#[derive(InfluxDbWriteable)]
+struct PortfolioMeasure {
+    time: DateTime<Utc>,
+    #[influxdb(tag)]
+    account_type: String,
+    total_cost: f64,
+    total_gain_value: f64,
+    /// Left out without any cost, Influx can't store NaN.
+    total_gain_pct: Option<f64>,
+    positions_count: u32,
+    options_count: u32,
+}
+
+/// Persists quotes and greeks to InfluxDB, one point per quote or contract.
+#[derive(Clone)]
+pub struct QuoteWriter<S = Client> {
+    sink: S,
+}
+
+impl QuoteWriter<Client> {
+    pub fn new(config: &InfluxConfig) -> Self {
+        let mut client = Client::new(&config.url, &config.database);
+        if let Some(token) = config
+            .token
+            .clone()
+            .or_else(|| env::var(INFLUX_TOKEN_VAR).ok())
+        {
+            client = client.with_token(token);
+        }
+
+        Self { sink: client }
+    }
+}
+
+impl<S: InfluxSink> QuoteWriter<S> {
+    pub fn with_sink(sink: S) -> Self {
+        Self { sink }
+    }
+
+    /// Writes one point per quote, timestamped with the last trade.
+    pub async fn write_quotes(&self, quotes: &[Quote]) -> Result<(), InfluxError> {
+        if quotes.is_empty() {
+            return Ok(());
+        }
+        let queries = quotes
+            .iter()
+            .map(|q| {
+                let spread_pct = q.spread_pct();
+                QuoteMeasure {
+                    time: q.last_timestamp.parse().unwrap_or_else(|_| Utc::now()),
+                    ticker: q.instrument.symbol.clone(),
+                    instrument_type: q.instrument.instrument_type.to_string(),
+                    bid: q.bid,
+                    ask: q.ask,
+                    last: q.last,
+                    volume: q.volume,
+                    open_interest: q.open_interest.unwrap_or(0),
+                    spread_pct: spread_pct.is_finite().then_some(spread_pct),
+                }
+                .try_into_query(QUOTES)
+                .map_err(|e| InfluxError::InvalidTimestamp(format!("{e:?}")))
+            })
+            .collect::<Result<Vec<WriteQuery>, InfluxError>>()?;
+
+        Ok(self.sink.write(queries).await?)
+    }
+
+    /// Writes the greeks of `symbol`, tagged with its underlying.
+    pub async fn write_greeks(
+        &self,
+        symbol: &str,
+        greeks: &OptionGreeks,
+    ) -> Result<(), InfluxError> {
+        let ticker = OsiSymbol::parse(symbol)
+            .map(|parts| parts.ticker)
+            .unwrap_or_else(|_| symbol.to_string());
+        let g = &greeks.greeks;
+        let query = GreeksMeasure {
+            time: Utc::now(),
+            ticker,
+            symbol: symbol.to_string(),
+            delta: g.delta,
+            gamma: g.gamma,
+            theta: g.theta,
+            vega: g.vega,
+            rho: g.rho,
+            implied_volatility: g.implied_volatility,
+        }
+        .try_into_query(GREEKS)
+        .map_err(|e| InfluxError::InvalidTimestamp(format!("{e:?}")))?;
+
+        Ok(self.sink.write(vec![query]).await?)
+    }
+
+    /// Writes the totals of `positions`, one point per call. Positions without
+    /// a cost basis count but don't add to the totals.
+    pub async fn write_portfolio_snapshot(
+        &self,
+        account_type: &AccountType,
+        positions: &[Position],
+    ) -> Result<(), InfluxError> {
+        let (total_cost, total_gain_value, invested) = positions
+            .iter()
+            .filter_map(|p| {
+                let cb = p.cost_basis.as_ref()?;
+                Some((
+                    cb.total_cost.parse::<f64>().ok()?,
+                    cb.gain_value.parse::<f64>().ok()?,
+                ))
+            })
+            .fold((0.0, 0.0, 0.0), |(cost, gain, invested), (c, g)| {
+                (cost + c, gain + g, invested + c.abs())
+            });
+        let query = PortfolioMeasure {
+            time: Utc::now(),
+            account_type: account_type.to_string(),
+            total_cost,
+            total_gain_value,
+            total_gain_pct: (invested > 0.0).then(|| total_gain_value / invested * 100.0),
+            positions_count: positions.len() as u32,
+            options_count: positions.iter().filter(|p| p.is_option()).count() as u32,
+        }
+        .try_into_query(PORTFOLIO)
+        .map_err(|e| InfluxError::InvalidTimestamp(format!("{e:?}")))?;
+
+        Ok(self.sink.write(vec![query]).await?)
+    }
+
+    /// Writes one point per option position.
+    pub async fn write_positions(&self, positions: &[OptionPosInflux]) -> Result<(), InfluxError> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let queries = positions
+            .iter()
+            .map(|p| {
+                p.clone()
+                    .try_into_query(POSITIONS)
+                    .map_err(|e| InfluxError::InvalidTimestamp(format!("{e:?}")))
+            })
+            .collect::<Result<Vec<WriteQuery>, InfluxError>>()?;
+
+        Ok(self.sink.write(queries).await?)
+    }
+
+    /// Quotes of `symbol` written in the last `duration`, an InfluxQL duration like
+    /// "30m" or "2h", oldest first.
+    pub async fn query_recent(
+        &self,
+        symbol: &str,
+        duration: &str,
+    ) -> Result<Vec<StockMeasure>, InfluxError> {
+        if !is_influx_duration(duration) {
+            return Err(InfluxError::InvalidQuery(format!(
+                "invalid duration <{duration}>"
+            )));
+        }
+        // Escaped, as it is quoted in the query
+        let ticker = symbol.replace('\\', "\\\\").replace('\'', "\\'");
+        let query = ReadQuery::new(format!(
+            "SELECT * FROM {QUOTES} WHERE ticker = '{ticker}' AND time > now() - {duration}"
+        ));
+        let body = self.sink.read(query).await?;
+
+        let response: ReadResponse =
+            serde_json::from_str(&body).map_err(|e| InfluxError::InvalidResponse(e.to_string()))?;
+        response
+            .results
+            .into_iter()
+            .flat_map(|result| result.series)
+            .flat_map(|series| {
+                let columns = series.columns;
+                series.values.into_iter().map(move |values| {
+                    let row: Map<String, Value> = columns.iter().cloned().zip(values).collect();
+                    serde_json::from_value(Value::Object(row))
+                        .map_err(|e| InfluxError::InvalidResponse(e.to_string()))
+                })
+            })
+            .collect()
+    }
+
+    /// Average bid and ask of `symbol` over the last `duration`, like `query_recent`.
+    /// None when there is no quote in that time.
+    pub async fn average_bid_ask(
+        &self,
+        symbol: &str,
+        duration: &str,
+    ) -> Result<Option<(f64, f64)>, InfluxError> {
+        let quotes = self.query_recent(symbol, duration).await?;
+        if quotes.is_empty() {
+            return Ok(None);
+        }
+        let count = quotes.len() as f64;
+        let (bid, ask) = quotes
+            .iter()
+            .fold((0.0, 0.0), |(bid, ask), q| (bid + q.bid, ask + q.ask));
+
+        Ok(Some((bid / count, ask / count)))
+    }
+}
+
+/// InfluxQL duration literal, digits followed by a unit.
+fn is_influx_duration(duration: &str) -> bool {
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (count, unit) = duration.split_at(unit_start);
+
+    !count.is_empty() && ["ns", "u", "µ", "ms", "s", "m", "h", "d", "w"].contains(&unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::{AccountPortfolio, Greeks, Instrument, InstrumentType, QuoteOutcome};
+    use influxdb::Query;
+    use std::sync::Mutex;
+
+    /// Sink that keeps the line protocol of every write.
+    #[derive(Default)]
+    struct Recorder {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl InfluxSink for &Recorder {
+        async fn write(&self, queries: Vec<WriteQuery>) -> Result<(), influxdb::Error> {
+            let lines = queries.build()?.get();
+            self.lines
+                .lock()
+                .unwrap()
+                .extend(lines.lines().map(String::from));
+            Ok(())
+        }
+
+        async fn read(&self, _query: ReadQuery) -> Result<String, influxdb::Error> {
+            Ok(r#"{"results":[{"statement_id":0}]}"#.to_string())
+        }
+    }
+
+    fn quote(symbol: &str, bid: f64, ask: f64) -> Quote {
+        Quote {
+            instrument: Instrument {
+                symbol: symbol.to_string(),
+                instrument_type: InstrumentType::Option,
+                name: None,
+            },
+            outcome: QuoteOutcome::Success,
+            last: 0.64,
+            last_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            bid,
+            bid_size: None,
+            bid_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            ask,
+            ask_size: None,
+            ask_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            volume: 12,
+            open_interest: Some(340),
+            previous_close: None,
+            one_day_change: None,
+            option_details: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_quotes() {
+        let recorder = Recorder::default();
+        let writer = QuoteWriter::with_sink(&recorder);
+        let mut btc = quote("BTC", 67405.1, 67419.6);
+        btc.instrument = Instrument::crypto("BTC");
+        writer
+            .write_quotes(&[
+                quote("QCOM260220P00138000", 0.6, 0.68),
+                quote("QCOM260220P00130000", 0.0, 0.0),
+                btc,
+            ])
+            .await
+            .unwrap();
+
+        let lines = recorder.lines.lock().unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("quotes,ticker=QCOM260220P00138000,instrument_type=OPTION "));
+        assert!(lines[0].contains("bid=0.6,ask=0.68,last=0.64,volume=12i,open_interest=340i"));
+        assert!(lines[0].contains("spread_pct=12.5"));
+        assert!(lines[0].ends_with(" 1771361999000000000"));
+        assert!(!lines[1].contains("spread_pct"));
+        // Crypto is told apart from stocks and options by its instrument type tag
+        assert!(lines[2].starts_with("quotes,ticker=BTC,instrument_type=CRYPTO "));
+    }
+
+    #[tokio::test]
+    async fn test_write_greeks() {
+        let recorder = Recorder::default();
+        let writer = QuoteWriter::with_sink(&recorder);
+        let greeks = OptionGreeks {
+            symbol: "QCOM260220P00138000".to_string(),
+            greeks: Greeks {
+                delta: -0.21,
+                gamma: 0.05,
+                theta: -0.12,
+                vega: 0.08,
+                rho: -0.01,
+                implied_volatility: 0.43,
+            },
+        };
+        writer.write_greeks(&greeks.symbol, &greeks).await.unwrap();
+
+        let lines = recorder.lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("greeks,ticker=QCOM,symbol=QCOM260220P00138000 delta=-0.21"));
+        assert!(lines[0].contains("implied_volatility=0.43"));
+    }
+
+    #[tokio::test]
+    async fn test_write_portfolio_snapshot() {
+        let portfolio: AccountPortfolio =
+            serde_json::from_str(include_str!("fixtures/account_portfolio_with_options.json"))
+                .unwrap();
+        let recorder = Recorder::default();
+        let writer = QuoteWriter::with_sink(&recorder);
+        writer
+            .write_portfolio_snapshot(&portfolio.account_type, &portfolio.positions)
+            .await
+            .unwrap();
+        writer
+            .write_portfolio_snapshot(&portfolio.account_type, &[])
+            .await
+            .unwrap();
+
+        let lines = recorder.lines.lock().unwrap();
+        assert_eq!(lines.len(), 2);
+        let options = portfolio.positions.iter().filter(|p| p.is_option()).count();
+        assert!(lines[0].starts_with("portfolio,account_type=BROKERAGE total_cost="));
+        assert!(lines[0].contains("total_gain_pct="));
+        assert!(lines[0].contains(&format!(
+            "positions_count={}i,options_count={options}i",
+            portfolio.positions.len()
+        )));
+        assert!(lines[1].contains("total_cost=0,total_gain_value=0,positions_count=0i"));
+    }
+
+    #[tokio::test]
+    async fn test_write_positions() {
+        use crate::options::OptionPosition;
+
+        let portfolio: AccountPortfolio =
+            serde_json::from_str(include_str!("fixtures/account_portfolio_with_options.json"))
+                .unwrap();
+        let put = portfolio
+            .positions
+            .iter()
+            .find(|p| p.instrument.symbol == "QCOM260220P00138000")
+            .map(|p| OptionPosition::new(p).unwrap())
+            .unwrap();
+        let mut measure = put.to_influx_measure();
+        assert_eq!(measure.symbol, "QCOM260220P00138000");
+        assert_eq!(measure.ticker, "QCOM");
+        assert_eq!(measure.op_type, "Put");
+        assert_eq!(measure.quantity as i64, put.quantity);
+        assert_eq!(measure.gain_pct, put.gain_percent);
+
+        let recorder = Recorder::default();
+        let writer = QuoteWriter::with_sink(&recorder);
+        measure.time = "2026-02-17T20:59:59Z".parse().unwrap();
+        writer.write_positions(&[measure.clone()]).await.unwrap();
+        writer.write_positions(&[]).await.unwrap();
+
+        let lines = recorder.lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        let tags = format!(
+            "positions,symbol=QCOM260220P00138000,ticker=QCOM,op_type=Put,side={} ",
+            measure.side
+        );
+        assert!(lines[0].starts_with(&tags), "{}", lines[0]);
+        assert!(lines[0].contains(&format!(
+            "gain_pct={},gain_value={},cost={},quantity={}i",
+            measure.gain_pct, measure.gain_value, measure.cost, measure.quantity
+        )));
+        assert!(lines[0].ends_with(" 1771361999000000000"));
+    }
+
+    #[tokio::test]
+    async fn test_query_recent() {
+        use crate::public::mock::mock_server;
+        use std::collections::HashMap;
+        use warp::Filter;
+
+        let queries = warp::path!("query")
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|params: HashMap<String, String>| {
+                assert_eq!(params["db"], "stonks");
+                assert_eq!(
+                    params["q"],
+                    "SELECT * FROM quotes WHERE ticker = 'QCOM' AND time > now() - 2h"
+                );
+                warp::reply::json(&serde_json::json!({
+                    "results": [{
+                        "statement_id": 0,
+                        "series": [{
+                            "name": "quotes",
+                            "columns": ["time", "ask", "bid", "instrument_type", "last",
+                                "open_interest", "spread_pct", "ticker", "volume"],
+                            "values": [
+                                ["2026-02-17T20:58:00Z", 140.6, 140.4, "EQUITY", 140.5,
+                                    0, 0.14, "QCOM", 1200],
+                                ["2026-02-17T20:59:00Z", 141.0, 140.6, "EQUITY", 140.8,
+                                    0, null, "QCOM", 1500]
+                            ]
+                        }]
+                    }]
+                }))
+            });
+        let base_url = mock_server(queries).await;
+        let writer = QuoteWriter::new(&InfluxConfig {
+            url: base_url.as_str().trim_end_matches('/').to_string(),
+            database: "stonks".to_string(),
+            token: Some("token".to_string()),
+        });
+
+        let quotes = writer.query_recent("QCOM", "2h").await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(
+            quotes[0].time,
+            "2026-02-17T20:58:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(quotes[0].ticker, "QCOM");
+        assert_eq!((quotes[0].bid, quotes[0].ask), (140.4, 140.6));
+        assert_eq!(quotes[0].spread_pct, Some(0.14));
+        assert_eq!(quotes[1].volume, 1500);
+        assert_eq!(quotes[1].spread_pct, None);
+
+        let (bid, ask) = writer.average_bid_ask("QCOM", "2h").await.unwrap().unwrap();
+        assert!((bid - 140.5).abs() < 1e-9 && (ask - 140.8).abs() < 1e-9);
+
+        assert!(matches!(
+            writer
+                .query_recent("QCOM", "2h; DROP DATABASE stonks")
+                .await,
+            Err(InfluxError::InvalidQuery(_))
+        ));
+        let recorder = Recorder::default();
+        let empty = QuoteWriter::with_sink(&recorder);
+        assert!(empty.query_recent("QCOM", "30m").await.unwrap().is_empty());
+        assert_eq!(empty.average_bid_ask("QCOM", "30m").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_write_no_quotes() {
+        let recorder = Recorder::default();
+        QuoteWriter::with_sink(&recorder)
+            .write_quotes(&[])
+            .await
+            .unwrap();
+        assert!(recorder.lines.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::public::PUBLIC_DIR;
+
+const IV_HISTORY_DIR: &str = "iv_history";
+const DAYS_52W: u64 = 52 * 7;
+
+/// Daily implied volatility samples of one underlying, stored as
+/// `~/.public/iv_history/{symbol}.json`:
+///
+/// `{"symbol": "QCOM", "samples": [["2026-02-17", 0.43], ...]}`
+///
+/// Samples are kept sorted by date, one per day.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct IvHistory {
+    pub symbol: String,
+    pub samples: Vec<(NaiveDate, f64)>,
+}
+
+impl IvHistory {
+    pub fn new(symbol: &str) -> IvHistory {
+        IvHistory {
+            symbol: symbol.to_string(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Path of the history file for `symbol`.
+    pub fn path(symbol: &str) -> PathBuf {
+        let home_dir = env::home_dir().unwrap_or_default();
+
+        home_dir.join(PathBuf::from(format!(
+            "{PUBLIC_DIR}/{IV_HISTORY_DIR}/{symbol}.json"
+        )))
+    }
+
+    /// Loads the history of `symbol`, empty if it was never saved.
+    pub fn load(symbol: &str) -> Result<IvHistory> {
+        Self::load_from(symbol, &Self::path(symbol))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::path(&self.symbol))
+    }
+
+    fn load_from(symbol: &str, path: &Path) -> Result<IvHistory> {
+        if !path.exists() {
+            return Ok(IvHistory::new(symbol));
+        }
+        let data = fs::read_to_string(path)?;
+
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Adds the IV sampled on `date`, replacing an earlier sample of that day.
+    pub fn record(&mut self, date: NaiveDate, iv: f64) {
+        match self.samples.binary_search_by_key(&date, |(d, _)| *d) {
+            Ok(i) => self.samples[i].1 = iv,
+            Err(i) => self.samples.insert(i, (date, iv)),
+        }
+    }
+
+    /// IV of the latest sample on or before `date`, None without one.
+    pub fn iv_on(&self, date: NaiveDate) -> Option<f64> {
+        self.samples
+            .iter()
+            .rev()
+            .find(|(d, _)| *d <= date)
+            .map(|(_, iv)| *iv)
+    }
+
+    /// Percentage of the historical samples below `current_iv`, NaN without history.
+    pub fn rank(&self, current_iv: f64) -> f64 {
+        if self.samples.is_empty() {
+            return f64::NAN;
+        }
+        let below = self
+            .samples
+            .iter()
+            .filter(|(_, iv)| *iv < current_iv)
+            .count();
+
+        below as f64 / self.samples.len() as f64 * 100.0
+    }
+
+    /// Lowest and highest IV of the last 52 weeks, NaN without samples in that range.
+    pub fn percentile_52w(&self) -> (f64, f64) {
+        let today = Local::now().date_naive();
+        let since = today.checked_sub_days(Days::new(DAYS_52W)).unwrap_or(today);
+
+        self.samples
+            .iter()
+            .filter(|(date, _)| *date >= since)
+            .fold((f64::NAN, f64::NAN), |(low, high), (_, iv)| {
+                (low.min(*iv), high.max(*iv))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days_ago(days: u64) -> NaiveDate {
+        Local::now().date_naive() - Days::new(days)
+    }
+
+    #[test]
+    fn test_iv_history_json() {
+        let json = r#"{"symbol":"QCOM","samples":[["2026-02-13",0.38],["2026-02-17",0.43]]}"#;
+        let history: IvHistory = serde_json::from_str(json).unwrap();
+        assert_eq!(history.symbol, "QCOM");
+        assert_eq!(
+            history.samples,
+            vec![
+                (NaiveDate::from_ymd_opt(2026, 2, 13).unwrap(), 0.38),
+                (NaiveDate::from_ymd_opt(2026, 2, 17).unwrap(), 0.43),
+            ]
+        );
+        assert_eq!(serde_json::to_string(&history).unwrap(), json);
+    }
+
+    #[test]
+    fn test_iv_history_save_load() {
+        let path = env::temp_dir().join(format!("iv_history_{}/QCOM.json", uuid::Uuid::new_v4()));
+        assert_eq!(
+            IvHistory::load_from("QCOM", &path).unwrap(),
+            IvHistory::new("QCOM")
+        );
+
+        let mut history = IvHistory::new("QCOM");
+        history.record(days_ago(1), 0.4);
+        history.save_to(&path).unwrap();
+        assert_eq!(IvHistory::load_from("QCOM", &path).unwrap(), history);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_iv_history_record() {
+        let mut history = IvHistory::new("QCOM");
+        history.record(days_ago(1), 0.4);
+        history.record(days_ago(3), 0.3);
+        history.record(days_ago(1), 0.5);
+        assert_eq!(
+            history.samples,
+            vec![(days_ago(3), 0.3), (days_ago(1), 0.5)]
+        );
+
+        assert_eq!(history.iv_on(days_ago(4)), None);
+        assert_eq!(history.iv_on(days_ago(2)), Some(0.3));
+        assert_eq!(history.iv_on(days_ago(0)), Some(0.5));
+    }
+
+    #[test]
+    fn test_iv_rank() {
+        let mut history = IvHistory::new("QCOM");
+        assert!(history.rank(0.4).is_nan());
+
+        for (days, iv) in [(4, 0.2), (3, 0.3), (2, 0.4), (1, 0.5)] {
+            history.record(days_ago(days), iv);
+        }
+        assert_eq!(history.rank(0.1), 0.0);
+        assert_eq!(history.rank(0.35), 50.0);
+        assert_eq!(history.rank(0.4), 50.0);
+        assert_eq!(history.rank(0.6), 100.0);
+    }
+
+    #[test]
+    fn test_iv_percentile_52w() {
+        let mut history = IvHistory::new("QCOM");
+        let (low, high) = history.percentile_52w();
+        assert!(low.is_nan() && high.is_nan());
+
+        history.record(days_ago(400), 0.9);
+        history.record(days_ago(300), 0.25);
+        history.record(days_ago(10), 0.6);
+        history.record(days_ago(1), 0.4);
+        assert_eq!(history.percentile_52w(), (0.25, 0.6));
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use rusqlite::{Connection, params};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::options::ExitReason;
+use crate::osi::OsiSymbol;
+use crate::public::{OrderSide, PUBLIC_DIR};
+use crate::signals::Signal;
+
+const JOURNAL_FILE: &str = "journal.db";
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS exits (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT,
+    symbol TEXT,
+    ticker TEXT,
+    strike REAL,
+    expiration TEXT,
+    quantity INTEGER,
+    gain_pct REAL,
+    exit_reason TEXT,
+    bid REAL,
+    ask REAL
+)";
+
+/// One exit decision of `OptionsStopper`, as stored in the journal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExitRecord {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub ticker: String,
+    pub strike: f64,
+    pub expiration: NaiveDate,
+    pub quantity: i64,
+    pub gain_pct: f64,
+    pub exit_reason: ExitReason,
+    /// NaN when the exit was decided without a quote, like in a dry run.
+    pub bid: f64,
+    pub ask: f64,
+}
+
+/// Trade history kept in SQLite, `~/.public/journal.db` by default.
+pub struct Journal {
+    conn: Connection,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, creating the database and its tables if needed.
+    /// ":memory:" keeps it in memory.
+    pub fn new(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute(SCHEMA, [])?;
+
+        Ok(Self { conn })
+    }
+
+    /// Path of the default journal.
+    pub fn path() -> PathBuf {
+        let home_dir = env::home_dir().unwrap_or_default();
+
+        home_dir.join(PUBLIC_DIR).join(JOURNAL_FILE)
+    }
+
+    /// Records an exit signal, holds aren't recorded. The quantity is signed like the
+    /// position's: negative for the short positions a buy closes.
+    pub fn record_signal(&mut self, signal: &Signal) -> Result<()> {
+        let Signal::Exit {
+            symbol,
+            reason,
+            side,
+            quantity,
+            gain_percent,
+            bid,
+            ask,
+            ..
+        } = signal
+        else {
+            return Ok(());
+        };
+        let parts = OsiSymbol::parse(symbol)?;
+        let quantity = match side {
+            OrderSide::Buy => -i64::from(*quantity),
+            OrderSide::Sell => i64::from(*quantity),
+        };
+
+        self.conn.execute(
+            "INSERT INTO exits (timestamp, symbol, ticker, strike, expiration, quantity,
+                gain_pct, exit_reason, bid, ask)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                symbol,
+                parts.ticker,
+                parts.strike,
+                parts.expiration.to_string(),
+                quantity,
+                gain_percent,
+                reason.to_string(),
+                bid,
+                ask,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Exits recorded in the last `days` days, latest first.
+    pub fn recent_exits(&self, days: u32) -> Result<Vec<ExitRecord>> {
+        let since = Utc::now() - Duration::days(days.into());
+        let mut statement = self.conn.prepare(
+            "SELECT id, timestamp, symbol, ticker, strike, expiration, quantity,
+                gain_pct, exit_reason, bid, ask
+            FROM exits WHERE timestamp >= ?1 ORDER BY timestamp DESC, id DESC",
+        )?;
+        let rows =
+            statement.query_map([since.to_rfc3339_opts(SecondsFormat::Secs, true)], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, f64>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, i64>(6)?,
+                    row.get::<_, f64>(7)?,
+                    row.get::<_, String>(8)?,
+                    // SQLite stores NaN as NULL
+                    row.get::<_, Option<f64>>(9)?.unwrap_or(f64::NAN),
+                    row.get::<_, Option<f64>>(10)?.unwrap_or(f64::NAN),
+                ))
+            })?;
+
+        let mut exits = Vec::new();
+        for row in rows {
+            let (
+                id,
+                timestamp,
+                symbol,
+                ticker,
+                strike,
+                expiration,
+                quantity,
+                gain_pct,
+                reason,
+                bid,
+                ask,
+            ) = row?;
+            exits.push(ExitRecord {
+                id,
+                timestamp: DateTime::parse_from_rfc3339(&timestamp)?.to_utc(),
+                symbol,
+                ticker,
+                strike,
+                expiration: expiration.parse()?,
+                quantity,
+                gain_pct,
+                exit_reason: reason.parse().map_err(anyhow::Error::msg)?,
+                bid,
+                ask,
+            });
+        }
+
+        Ok(exits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exit(reason: ExitReason, bid: f64, ask: f64) -> Signal {
+        Signal::Exit {
+            symbol: "QCOM260220P00138000".to_string(),
+            reason,
+            suggested_limit: (bid + ask) / 2.0,
+            side: OrderSide::Buy,
+            quantity: 1,
+            gain_percent: 8.58,
+            bid,
+            ask,
+        }
+    }
+
+    #[test]
+    fn test_record_signal() {
+        let mut journal = Journal::new(Path::new(":memory:")).unwrap();
+
+        journal
+            .record_signal(&exit(ExitReason::StopLoss, 0.6, 0.68))
+            .unwrap();
+        journal
+            .record_signal(&exit(ExitReason::TimeBased, f64::NAN, f64::NAN))
+            .unwrap();
+        let hold = Signal::Hold {
+            symbol: "QCOM260220P00138000".to_string(),
+        };
+        journal.record_signal(&hold).unwrap();
+
+        let exits = journal.recent_exits(1).unwrap();
+        assert_eq!(exits.len(), 2);
+        let latest = &exits[0];
+        assert_eq!(latest.exit_reason, ExitReason::TimeBased);
+        assert!(latest.bid.is_nan() && latest.ask.is_nan());
+
+        let exit = &exits[1];
+        assert_eq!(exit.symbol, "QCOM260220P00138000");
+        assert_eq!(exit.ticker, "QCOM");
+        assert_eq!(exit.strike, 138.0);
+        assert_eq!(
+            exit.expiration,
+            NaiveDate::from_ymd_opt(2026, 2, 20).unwrap()
+        );
+        assert_eq!(exit.quantity, -1);
+        assert_eq!(exit.gain_pct, 8.58);
+        assert_eq!(exit.exit_reason, ExitReason::StopLoss);
+        assert_eq!((exit.bid, exit.ask), (0.6, 0.68));
+        assert!(Utc::now() - exit.timestamp < Duration::minutes(1));
+
+        // Out of the window once older than it
+        let old = (Utc::now() - Duration::days(10)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        journal
+            .conn
+            .execute(
+                "UPDATE exits SET timestamp = ?1 WHERE id = ?2",
+                params![old, exit.id],
+            )
+            .unwrap();
+        let exits = journal.recent_exits(7).unwrap();
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].exit_reason, ExitReason::TimeBased);
+        assert_eq!(journal.recent_exits(30).unwrap().len(), 2);
+    }
+}
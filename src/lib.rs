@@ -1,4 +1,17 @@
 pub mod bitwarden;
 pub mod config;
+pub mod export;
+pub mod influx;
+pub mod iv;
+pub mod journal;
+pub mod metrics;
 pub mod options;
+pub mod osi;
+pub mod pricing;
 pub mod public;
+pub mod risk;
+pub mod runner;
+pub mod scheduler;
+pub mod secrets;
+pub mod signals;
+pub mod spreads;
@@ -1,18 +1,32 @@
 mod cli_ops;
-mod config;
 
+use chrono::Local;
 use clap::Parser;
 use cli_ops::{Cli, Command};
-use public_trading::options::OptionsAnalyze;
-use public_trading::public::AccountType;
+use futures::StreamExt;
+use public_trading::config::Config;
+use public_trading::export::{ExportError, export_to_csv};
+use public_trading::influx::QuoteWriter;
+use public_trading::journal::Journal;
+use public_trading::metrics::{CloudWatchMetrics, DEFAULT_NAMESPACE};
+use public_trading::options::{
+    AnalysisReport, OptionsAnalyze, StopperConfig, next_monthly_expiration,
+    print_expiration_summaries, print_report,
+};
+use public_trading::osi::OsiSymbol;
+use public_trading::public::Instrument;
+use public_trading::runner::StrategyRunner;
+use public_trading::scheduler::Scheduler;
+use public_trading::signals::{JournalHandler, LoggingHandler, OrderHandler, SignalHandler};
 use public_trading::{options::OptionsStopper, public::PublicClient};
 use rustls::crypto::CryptoProvider;
-use tracing::{Level, error, info};
-use tracing_subscriber;
+use std::fs::File;
+use std::pin::pin;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{Level, error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
-
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
@@ -22,26 +36,65 @@ async fn main() {
     setup_simple_log(cli.log_level);
 
     info!("Public Trading");
-    let _config = Config::new();
+    let config = Config::new_with_fallback().await;
 
-    let mut client = match PublicClient::new() {
+    let client = match config.as_ref().and_then(Config::profile) {
+        Some(profile) => PublicClient::new_with_profile(&profile),
+        None => PublicClient::new(),
+    };
+    let mut client = match client {
         Ok(client) => client,
         Err(e) => {
-            error!("Failed to create client: {e:?}");
+            error!("Failed to create client: {e}");
             return;
         }
     };
+    if let Some(limit) = config.as_ref().and_then(Config::rate_limit) {
+        client = client.with_rate_limit(limit);
+    }
+    #[cfg(feature = "news")]
+    if let Some(news) = config.as_ref().and_then(Config::news) {
+        client = client.with_news(news);
+    }
 
-    match client.set_account(AccountType::Brokerage).await {
+    match client.health_check().await {
+        Ok(status) if !status.api_reachable => {
+            error!("Cannot reach the Public API, not starting");
+            return;
+        }
+        Ok(status) if !status.authenticated => {
+            error!("Public API rejected the credentials, check the secret token");
+            return;
+        }
+        Ok(status) if status.account_count == 0 => {
+            error!("Public API lists no accounts for the credentials, not starting");
+            return;
+        }
+        Ok(status) => info!(
+            "Public API is up, {} accounts listed in {}ms",
+            status.account_count, status.latency_ms
+        ),
+        Err(e) => {
+            error!("Cannot check the Public API: {e}");
+            return;
+        }
+    }
+    match client.set_account(cli.account.clone()).await {
         Ok(()) => {
-            info!("Successfully set account type to BROKERAGE");
+            info!("Successfully set account type to {:?}", cli.account);
         }
         Err(e) => {
-            error!("Client error: {e:?}");
+            error!("Client error: {e}");
             return;
         }
     };
 
+    let metrics = if cli.metrics {
+        Some(CloudWatchMetrics::new(DEFAULT_NAMESPACE).await)
+    } else {
+        None
+    };
+
     match cli.command {
         Command::ShowPortfolio { json } => match client.get_account_portfolio().await {
             Ok(portfolio) => {
@@ -52,51 +105,273 @@ async fn main() {
                 }
             }
             Err(e) => {
-                error!("Failed to get portfolio: {e:?}");
+                error!("Failed to get portfolio: {e}");
             }
         },
 
-        Command::AnalyzeOption { symbol, expiration } => {
+        Command::ShowStocks { group } => {
+            let Some(config) = config else {
+                error!("Cannot show stocks without a config file");
+                return;
+            };
+            let stocks = match &group {
+                Some(group) => match config.stocks_in_group(group) {
+                    Some(stocks) => stocks.iter().collect(),
+                    None => {
+                        error!("Config doesnt contain stocks group \"{group}\"");
+                        return;
+                    }
+                },
+                None => config.all_stocks(),
+            };
+            let instruments = stocks.into_iter().map(|s| Instrument::equity(s)).collect();
+            match client.get_quotes(instruments).await {
+                Ok(quotes) => quotes.iter().for_each(|q| {
+                    println!(
+                        "{}: last {:.2}, bid {:.2}, ask {:.2}",
+                        q.instrument.symbol, q.last, q.bid, q.ask
+                    )
+                }),
+                Err(e) => error!("Failed to get the quotes of the stocks: {e}"),
+            }
+        }
+
+        Command::AnalyzeOption {
+            symbol,
+            all,
+            expiration,
+            strikes,
+            csv,
+            volume_histogram,
+            json,
+            delta_range,
+            min_volume,
+        } => {
+            let expiration =
+                expiration.unwrap_or_else(|| next_monthly_expiration(Local::now().date_naive()));
+            let delta_range = delta_range.map(|range| (range[0], range[1]));
+            let analyzer = OptionsAnalyze::new(client).with_config(
+                config
+                    .as_ref()
+                    .and_then(Config::analysis)
+                    .unwrap_or_default(),
+            );
+            if all {
+                let Some(config) = config.as_ref() else {
+                    error!("Cannot analyze every option without a config file");
+                    return;
+                };
+                let reports: Vec<AnalysisReport> = analyzer
+                    .analyze_portfolio(config, expiration, strikes, delta_range)
+                    .await
+                    .into_iter()
+                    .map(|report| report.filter_rows(min_volume))
+                    .collect();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+                } else {
+                    reports.iter().for_each(print_report);
+                }
+                if volume_histogram {
+                    reports
+                        .iter()
+                        .for_each(OptionsAnalyze::print_volume_histogram);
+                }
+                return;
+            }
+
+            let Some(symbol) = symbol.or_else(|| first_option_ticker(config.as_ref())) else {
+                error!("No symbol given, and no options in the config file");
+                return;
+            };
+            match analyzer
+                .analyze_option(symbol, expiration, strikes, delta_range)
+                .await
+            {
+                Ok(report) => {
+                    let report = report.filter_rows(min_volume);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                    } else {
+                        print_report(&report);
+                    }
+                    if volume_histogram {
+                        OptionsAnalyze::print_volume_histogram(&report);
+                    }
+                    if let Some(path) = csv {
+                        let written = File::create(&path)
+                            .map_err(ExportError::from)
+                            .and_then(|file| export_to_csv(&report, file));
+                        match written {
+                            Ok(()) => info!("Wrote option chain to {path:?}"),
+                            Err(e) => error!("CSV export error: {e}"),
+                        }
+                    }
+                }
+                Err(e) => error!("Analyze Option error: {e}"),
+            }
+        }
+
+        Command::CompareExpirations {
+            symbol,
+            expirations,
+        } => {
+            let instrument = Instrument::equity(&symbol);
+            let price = match client.get_quotes(vec![instrument]).await {
+                Ok(quotes) if !quotes.is_empty() => quotes[0].last,
+                Ok(_) => {
+                    error!("No quote returned for {symbol}");
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to get the quote of {symbol}: {e}");
+                    return;
+                }
+            };
             let analyzer = OptionsAnalyze::new(client);
-            if let Err(e) = analyzer.analyze_option(symbol, expiration).await {
-                error!("Analyze Option error: {e:?}");
+            match analyzer
+                .compare_expirations(&symbol, &expirations, price)
+                .await
+            {
+                Ok(summaries) => print_expiration_summaries(&summaries),
+                Err(e) => error!("Compare Expirations error: {e}"),
             }
         }
 
         Command::AnalyzeOptions {
             expiration,
             equities_group,
+            csp,
+            delta,
+            max_strike,
         } => {
             let analyzer = OptionsAnalyze::new(client);
-            let config = Config::new().await.unwrap();
-            let equities = if let Some(e) = config.get(&equities_group) {
+            let Some(config) = config else {
+                error!("Cannot analyze options without a config file");
+                return;
+            };
+            let equities = if let Some(e) = config.equities_group(&equities_group) {
                 e
             } else {
                 error!("Config doesnt contain group \"{equities_group}\"");
                 return;
             };
 
-            if let Err(e) = analyzer.analyze_options(equities, expiration).await {
-                error!("Analyze Options error: {e:?}");
+            if csp {
+                match analyzer
+                    .find_cash_secured_puts(&equities, &expiration, delta, max_strike)
+                    .await
+                {
+                    Ok(puts) => puts.iter().for_each(|p| println!("{p}")),
+                    Err(e) => error!("Cash-secured puts error: {e}"),
+                }
+            } else if let Err(e) = analyzer.analyze_options(equities, expiration).await {
+                error!("Analyze Options error: {e}");
             }
         }
 
+        Command::Run { every } => {
+            let Some(config) = config else {
+                error!("Cannot run strategies without a config file");
+                return;
+            };
+            let mut runner = strategy_runner(client, &config, metrics);
+            let Some(minutes) = every else {
+                if let Err(e) = runner.run_all().await {
+                    error!("Run error: {e}");
+                }
+                return;
+            };
+
+            let (tx, rx) = watch::channel(config);
+            tokio::spawn(async move {
+                let mut configs = pin!(Config::watch(Config::path()));
+                while let Some(config) = configs.next().await {
+                    if tx.send(config).is_err() {
+                        break;
+                    }
+                }
+            });
+            runner = runner.with_config(rx);
+            runner.run_every(Duration::from_secs(minutes * 60)).await;
+        }
+
         Command::OptionsStopper {
             threshold,
             dry_run,
             dry_run_exit,
+            execute,
+            daemon,
+            interval,
         } => {
-            let opstop = OptionsStopper::new(client, threshold, dry_run, dry_run_exit);
+            let mut stopper_config = config
+                .as_ref()
+                .and_then(Config::stopper)
+                .unwrap_or_default()
+                .with_option_configs(config.as_ref().map(Config::options).unwrap_or_default());
+            if let Some(threshold) = threshold {
+                stopper_config.default_loss_threshold = threshold;
+            }
+            stopper_config.dry_run |= dry_run;
+            stopper_config.dry_run_exit |= dry_run_exit;
+            stopper_config.execute |= execute;
+
+            let handlers = signal_handlers(&client, &stopper_config);
+            let mut opstop = OptionsStopper::new(client.clone(), stopper_config, handlers);
+            if let Some(influx) = config.as_ref().and_then(Config::influx) {
+                opstop = opstop.with_quote_writer(QuoteWriter::new(&influx));
+            }
+            if let Some(metrics) = metrics {
+                opstop = opstop.with_metrics(metrics);
+            }
+            if daemon {
+                // Runs outside of market hours return early in OptionsStopper::run
+                Scheduler::new(opstop, Duration::from_secs(interval * 60))
+                    .run_forever()
+                    .await;
+                return;
+            }
             match opstop.run().await {
                 Ok(()) => {}
                 Err(e) => {
-                    error!("Options Stopper error: {e:?}");
+                    error!("Options Stopper error: {e}");
                 }
             }
         }
     }
 }
 
+/// Options stopper with the `[stopper]` settings, plus the analyzer when
+/// `[analysis]` names an `equities_group` and an `expiration`.
+fn strategy_runner(
+    client: PublicClient,
+    config: &Config,
+    metrics: Option<CloudWatchMetrics>,
+) -> StrategyRunner {
+    let stopper_config = config
+        .stopper()
+        .unwrap_or_default()
+        .with_option_configs(config.options());
+    let handlers = signal_handlers(&client, &stopper_config);
+    let mut stopper = OptionsStopper::new(client.clone(), stopper_config, handlers);
+    if let Some(influx) = config.influx() {
+        stopper = stopper.with_quote_writer(QuoteWriter::new(&influx));
+    }
+    if let Some(metrics) = metrics {
+        stopper = stopper.with_metrics(metrics);
+    }
+    let mut runner = StrategyRunner::new().with_strategy(stopper);
+
+    if let Some((equities, expiration)) = config.watchlist() {
+        let analyzer = OptionsAnalyze::new(client)
+            .with_config(config.analysis().unwrap_or_default())
+            .with_watchlist(equities, expiration);
+        runner = runner.with_strategy(analyzer);
+    }
+
+    runner
+}
+
 fn setup_simple_log(level: Level) {
     tracing_subscriber::fmt()
         .with_max_level(level)
@@ -132,3 +407,32 @@ async fn _setup_cw_logs(log_group: &str, stream_name: &str) {
         )
         .init();
 }
+
+/// Handlers of the stopper signals: logs them, places the exit orders when
+/// `config` executes them, and records the exits in the journal when it opens.
+fn signal_handlers(client: &PublicClient, config: &StopperConfig) -> Vec<Box<dyn SignalHandler>> {
+    let mut handlers: Vec<Box<dyn SignalHandler>> = vec![Box::new(LoggingHandler)];
+    if config.places_orders() {
+        handlers.push(Box::new(OrderHandler::new(client.clone())));
+    }
+    if let Some(journal) = open_journal() {
+        handlers.push(Box::new(JournalHandler::new(journal)));
+    }
+
+    handlers
+}
+
+/// Journal at the default path, None when it cannot be opened.
+fn open_journal() -> Option<Journal> {
+    let path = Journal::path();
+    Journal::new(&path)
+        .inspect_err(|e| warn!("Cannot open the journal {}: {e}", path.display()))
+        .ok()
+}
+
+/// Ticker of the first option symbol in the `options` list of `config`.
+fn first_option_ticker(config: Option<&Config>) -> Option<String> {
+    let symbol = config?.get("options")?.into_iter().next()?;
+
+    Some(OsiSymbol::parse(&symbol).map_or(symbol, |parts| parts.ticker))
+}
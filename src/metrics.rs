@@ -0,0 +1,160 @@
+use aws_sdk_cloudwatch::Client;
+use aws_sdk_cloudwatch::error::DisplayErrorContext;
+use aws_sdk_cloudwatch::types::{Dimension, MetricDatum, StandardUnit};
+use std::future::Future;
+
+use crate::options::ExitReason;
+
+const OPTIONS_EXITED: &str = "OptionsExited";
+const GAIN_AT_EXIT: &str = "GainAtExit";
+pub const DEFAULT_NAMESPACE: &str = "PublicTrading";
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetricsError {
+    #[error("failed to put metric data: {0}")]
+    PutMetricData(String),
+}
+
+/// Where metrics are sent. Implemented by `aws_sdk_cloudwatch::Client`.
+pub trait MetricsSink {
+    fn put_metric_data(
+        &self,
+        namespace: &str,
+        data: Vec<MetricDatum>,
+    ) -> impl Future<Output = Result<(), MetricsError>> + Send;
+}
+
+impl MetricsSink for Client {
+    async fn put_metric_data(
+        &self,
+        namespace: &str,
+        data: Vec<MetricDatum>,
+    ) -> Result<(), MetricsError> {
+        self.put_metric_data()
+            .namespace(namespace)
+            .set_metric_data(Some(data))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| MetricsError::PutMetricData(DisplayErrorContext(e).to_string()))
+    }
+}
+
+/// Publishes what `OptionsStopper` does to CloudWatch, under `namespace`.
+#[derive(Clone)]
+pub struct CloudWatchMetrics<S = Client> {
+    client: S,
+    namespace: String,
+}
+
+impl CloudWatchMetrics<Client> {
+    /// Client for the region and credentials of the AWS environment.
+    pub async fn new(namespace: &str) -> Self {
+        let aws_config = aws_config::load_from_env().await;
+
+        Self::with_client(Client::new(&aws_config), namespace)
+    }
+}
+
+impl<S: MetricsSink> CloudWatchMetrics<S> {
+    pub fn with_client(client: S, namespace: &str) -> Self {
+        Self {
+            client,
+            namespace: namespace.to_string(),
+        }
+    }
+
+    /// Counts one exit of `symbol` in `OptionsExited`, and records its gain in `GainAtExit`.
+    /// Both are dimensioned by symbol and exit reason.
+    pub async fn emit_exit(
+        &self,
+        symbol: &str,
+        reason: &ExitReason,
+        gain_percent: f32,
+    ) -> Result<(), MetricsError> {
+        let dimensions = vec![
+            Dimension::builder().name("symbol").value(symbol).build(),
+            Dimension::builder()
+                .name("reason")
+                .value(reason.to_string())
+                .build(),
+        ];
+        let datum = |name: &str, value: f64, unit: StandardUnit| {
+            MetricDatum::builder()
+                .metric_name(name)
+                .set_dimensions(Some(dimensions.clone()))
+                .value(value)
+                .unit(unit)
+                .build()
+        };
+        let data = vec![
+            datum(OPTIONS_EXITED, 1.0, StandardUnit::Count),
+            datum(GAIN_AT_EXIT, gain_percent as f64, StandardUnit::Percent),
+        ];
+
+        self.client.put_metric_data(&self.namespace, data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Sink that keeps every put request.
+    #[derive(Default)]
+    struct Recorder {
+        puts: Mutex<Vec<(String, Vec<MetricDatum>)>>,
+    }
+
+    impl MetricsSink for &Recorder {
+        async fn put_metric_data(
+            &self,
+            namespace: &str,
+            data: Vec<MetricDatum>,
+        ) -> Result<(), MetricsError> {
+            self.puts
+                .lock()
+                .unwrap()
+                .push((namespace.to_string(), data));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emit_exit() {
+        let recorder = Recorder::default();
+        let metrics = CloudWatchMetrics::with_client(&recorder, DEFAULT_NAMESPACE);
+        metrics
+            .emit_exit("QCOM260220P00138000", &ExitReason::StopLoss, -212.5)
+            .await
+            .unwrap();
+
+        let puts = recorder.puts.lock().unwrap();
+        assert_eq!(puts.len(), 1);
+        let (namespace, data) = &puts[0];
+        assert_eq!(namespace, "PublicTrading");
+
+        let exited = &data[0];
+        assert_eq!(exited.metric_name(), Some("OptionsExited"));
+        assert_eq!(exited.value(), Some(1.0));
+        assert_eq!(exited.unit(), Some(&StandardUnit::Count));
+        let dimensions: Vec<(Option<&str>, Option<&str>)> = exited
+            .dimensions()
+            .iter()
+            .map(|d| (d.name(), d.value()))
+            .collect();
+        assert_eq!(
+            dimensions,
+            vec![
+                (Some("symbol"), Some("QCOM260220P00138000")),
+                (Some("reason"), Some("stop loss")),
+            ]
+        );
+
+        let gain = &data[1];
+        assert_eq!(gain.metric_name(), Some("GainAtExit"));
+        assert_eq!(gain.value(), Some(-212.5));
+        assert_eq!(gain.dimensions(), exited.dimensions());
+    }
+}
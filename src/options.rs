@@ -1,62 +1,216 @@
-use chrono::NaiveDate;
-use serde::Serialize;
-use std::{cmp::Ordering, collections::HashMap};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc, Weekday};
+use futures::future::{BoxFuture, join_all};
+use futures::stream::{FuturesUnordered, StreamExt};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, trace, warn};
 
+use crate::config::Config;
+use crate::export::{snapshot_portfolio, write_run_summary};
+use crate::influx::{OptionPosInflux, QuoteWriter};
+use crate::iv::IvHistory;
+use crate::metrics::CloudWatchMetrics;
+use crate::osi::{OsiError, OsiSymbol};
+use crate::pricing::{DAYS_PER_YEAR, expected_value, probability_itm};
+#[cfg(feature = "news")]
+use crate::public::NewsItem;
 use crate::public::{
-    Greeks, Instrument, InstrumentType, OptionGreeks, OptionType, OrderSide, Position,
+    Greeks, Instrument, InstrumentType, OptionChain, OptionGreeks, OptionType, OrderSide, Position,
     PublicClient, PublicError, Quote,
 };
+use crate::risk::{DEFAULT_VAR_CONFIDENCE, DEFAULT_VAR_HORIZON_DAYS, compute_var};
+use crate::runner::Strategy;
+use crate::signals::{Signal, SignalHandler};
+use crate::spreads::{BullCallSpread, IronCondor};
 
 #[derive(Clone, Debug, Serialize)]
-struct OptionPosition {
-    symbol: String,
-    ticker: String,
-    strike: f64,
-    expiration: NaiveDate,
+pub(crate) struct OptionPosition {
+    pub(crate) symbol: String,
+    pub(crate) ticker: String,
+    pub(crate) strike: f64,
+    pub(crate) expiration: NaiveDate,
     side: OrderSide,
     op_type: OptionType,
+    /// Local date the position was opened on, when Public reports it.
+    opened_at: Option<NaiveDate>,
     cost: f64,
     unit_cost: f64,
     last_price: f64,
     gain_value: f64,
-    gain_percent: f64,
-    quantity: i64,
+    pub(crate) gain_percent: f64,
+    pub(crate) quantity: i64,
     greeks: Option<OptionGreeks>,
 }
 
+/// The key fields of an option position, as written to portfolio snapshots.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PositionSummary {
+    pub symbol: String,
+    pub ticker: String,
+    pub strike: f64,
+    pub expiration: NaiveDate,
+    pub side: OrderSide,
+    pub option_type: OptionType,
+    pub quantity: i64,
+    pub cost: f64,
+    pub last_price: f64,
+    pub gain_value: f64,
+    pub gain_percent: f64,
+    pub delta: Option<f64>,
+    pub theta: Option<f64>,
+    pub implied_volatility: Option<f64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OptionParseError {
+    UnexpectedTokenCount,
+    InvalidStrike(String),
+    InvalidDate(String),
+    InvalidOptionType(String),
+    InvalidSymbol(OsiError),
+    InvalidField(&'static str),
+    UnrecognizedFormat(String),
+}
+
+impl std::fmt::Display for OptionParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UnexpectedTokenCount => write!(f, "unexpected number of tokens in option name"),
+            Self::InvalidStrike(s) => write!(f, "invalid strike <{s}>"),
+            Self::InvalidDate(s) => write!(f, "invalid expiration <{s}>"),
+            Self::InvalidOptionType(s) => write!(f, "invalid option type <{s}>"),
+            Self::InvalidSymbol(e) => write!(f, "{e}"),
+            Self::InvalidField(field) => write!(f, "missing or invalid position field {field}"),
+            Self::UnrecognizedFormat(s) => write!(f, "unrecognized option name format <{s}>"),
+        }
+    }
+}
+
+impl std::error::Error for OptionParseError {}
+
+impl From<OsiError> for OptionParseError {
+    fn from(e: OsiError) -> Self {
+        Self::InvalidSymbol(e)
+    }
+}
+
+/// Parses the display name Public gives option instruments, like
+/// "QCOM $138 Put Feb 20, '26" or "F $12.50 Call Jan 05, '26",
+/// into (ticker, strike, option type, expiration).
+/// Names without the comma after the day ("QCOM $138 Put Feb 20 '26"), and with
+/// a four digit year and no dollar sign ("QCOM 138 Put Feb 20 2026") are read too.
+pub fn parse_option_name(
+    name: &str,
+) -> Result<(String, f64, OptionType, NaiveDate), OptionParseError> {
+    let tokens: Vec<&str> = name.split_whitespace().collect();
+    let [ticker, strike, op_type, month, day, year] = tokens[..] else {
+        return Err(OptionParseError::UnexpectedTokenCount);
+    };
+
+    // The comma after the day and the year tell the formats apart, the original
+    // one first. Only four digit years go without a dollar sign on the strike.
+    let short_year = year.strip_prefix('\'').is_some_and(|y| y.len() == 2);
+    let long_year = year.len() == 4 && year.bytes().all(|b| b.is_ascii_digit());
+    let (date_format, dollar_strike) = match (day.ends_with(','), short_year, long_year) {
+        (true, true, _) => ("%b %d, '%y", true),
+        (false, true, _) => ("%b %d '%y", true),
+        (false, _, true) => ("%b %d %Y", false),
+        _ => return Err(OptionParseError::UnrecognizedFormat(name.to_string())),
+    };
+
+    let strike = if dollar_strike {
+        strike.strip_prefix('$')
+    } else {
+        Some(strike.strip_prefix('$').unwrap_or(strike))
+    }
+    .and_then(|s| s.replace(',', "").parse::<f64>().ok())
+    .filter(|s| s.is_finite() && *s > 0.0)
+    .ok_or_else(|| OptionParseError::InvalidStrike(strike.to_string()))?;
+    let op_type: OptionType = op_type
+        .parse()
+        .map_err(|_| OptionParseError::InvalidOptionType(op_type.to_string()))?;
+
+    // chrono accepts both "5" and "05" for %d
+    let date = format!("{month} {day} {year}");
+    let expiration = NaiveDate::parse_from_str(&date, date_format)
+        .map_err(|_| OptionParseError::InvalidDate(date))?;
+
+    Ok((ticker.to_string(), strike, op_type, expiration))
+}
+
+/// Long positions were bought for a positive total cost, shorts sold for a negative one.
+/// A zero cost (a free or fully written down contract) says nothing, the quantity,
+/// negative for shorts, decides then.
+fn position_side(cost: f64, quantity: i64) -> OrderSide {
+    if cost > 0.0 || (cost == 0.0 && quantity >= 0) {
+        OrderSide::Buy
+    } else {
+        OrderSide::Sell
+    }
+}
+
 impl OptionPosition {
-    fn new(pos: &Position) -> Self {
+    /// Reads the contract from the OSI symbol, falling back to the instrument name.
+    pub(crate) fn new(pos: &Position) -> Result<Self, OptionParseError> {
         let symbol = pos.instrument.symbol.clone();
+        let (ticker, strike, op_type, expiration) = match OsiSymbol::parse(&symbol) {
+            Ok(osi) => (osi.ticker, osi.strike, osi.option_type, osi.expiration),
+            Err(e) => match &pos.instrument.name {
+                Some(name) => {
+                    warn!("{e}, reading contract from name <{name}>");
+                    parse_option_name(name)?
+                }
+                None => return Err(e.into()),
+            },
+        };
 
-        let (ticker, strike, op_type, expiration) = (
-            "ticker".to_string(),
-            0f64,
-            OptionType::Call,
-            NaiveDate::from_ymd_opt(2026, 4, 14).unwrap(),
-        );
-        // parse_option_name(pos.instrument.name.as_ref().unwrap());
-
-        let cb = pos.cost_basis.as_ref().unwrap();
-        let cost = cb.total_cost.parse().unwrap();
-        let unit_cost = cb.unit_cost.parse().unwrap();
-        let last_price = pos.last_price.clone().unwrap().last_price.parse().unwrap();
-        let side = if cost >= 0f64 {
-            OrderSide::Buy
-        } else {
-            OrderSide::Sell
+        let field = |name: &'static str, value: &str| -> Result<f64, OptionParseError> {
+            value
+                .parse()
+                .map_err(|_| OptionParseError::InvalidField(name))
         };
-        let gain_value = cb.gain_value.parse().unwrap();
-        let gain_percent = cb.gain_percentage.parse().unwrap();
-        let quantity = pos.quantity.parse().unwrap();
+        let cb = pos
+            .cost_basis
+            .as_ref()
+            .ok_or(OptionParseError::InvalidField("costBasis"))?;
+        let last_price = pos
+            .last_price
+            .as_ref()
+            .ok_or(OptionParseError::InvalidField("lastPrice"))?;
 
-        Self {
+        let cost = field("totalCost", &cb.total_cost)?;
+        let unit_cost = field("unitCost", &cb.unit_cost)?;
+        let last_price = field("lastPrice", &last_price.last_price)?;
+        let gain_value = field("gainValue", &cb.gain_value)?;
+        let gain_percent = field("gainPercentage", &cb.gain_percentage)?;
+        let quantity = pos
+            .quantity
+            .parse()
+            .map_err(|_| OptionParseError::InvalidField("quantity"))?;
+        let side = position_side(cost, quantity);
+        let opened_at = pos
+            .opened_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Local).date_naive());
+
+        Ok(Self {
             symbol,
             ticker,
             strike,
             expiration,
             side,
             op_type,
+            opened_at,
             cost,
             unit_cost,
             last_price,
@@ -64,15 +218,394 @@ impl OptionPosition {
             gain_percent,
             quantity,
             greeks: None,
+        })
+    }
+
+    fn to_summary(&self) -> PositionSummary {
+        let greeks = self.greeks.as_ref().map(|g| &g.greeks);
+        PositionSummary {
+            symbol: self.symbol.clone(),
+            ticker: self.ticker.clone(),
+            strike: self.strike,
+            expiration: self.expiration,
+            side: self.side.clone(),
+            option_type: self.op_type.clone(),
+            quantity: self.quantity,
+            cost: self.cost,
+            last_price: self.last_price,
+            gain_value: self.gain_value,
+            gain_percent: self.gain_percent,
+            delta: greeks.map(|g| g.delta),
+            theta: greeks.map(|g| g.theta),
+            implied_volatility: greeks.map(|g| g.implied_volatility),
+        }
+    }
+
+    /// The position as a point of the `positions` measurement, timestamped now.
+    pub(crate) fn to_influx_measure(&self) -> OptionPosInflux {
+        OptionPosInflux {
+            time: Utc::now(),
+            gain_pct: self.gain_percent,
+            gain_value: self.gain_value,
+            cost: self.cost,
+            quantity: self.quantity as i32,
+            symbol: self.symbol.clone(),
+            ticker: self.ticker.clone(),
+            op_type: self.op_type.to_string(),
+            side: self.side.to_string(),
+        }
+    }
+
+    /// Contracts held, negative for short positions whatever the sign of the quantity.
+    fn signed_contracts(&self) -> f64 {
+        let contracts = self.quantity.abs() as f64;
+        match self.side {
+            OrderSide::Buy => contracts,
+            OrderSide::Sell => -contracts,
         }
     }
 
-    fn _instrument(&self) -> Instrument {
+    fn instrument(&self) -> Instrument {
         Instrument {
             instrument_type: InstrumentType::Option,
             symbol: self.symbol.clone(),
+            name: None,
+        }
+    }
+
+    /// Calendar days left until expiration, 0 once it is reached.
+    fn days_to_expiration(&self) -> i64 {
+        calendar_days_between(Local::now().date_naive(), self.expiration)
+    }
+
+    /// Weekdays left until expiration, 0 once it is reached.
+    /// Market holidays are counted as trading days.
+    fn trading_days_to_expiration(&self) -> i64 {
+        trading_days_between(Local::now().date_naive(), self.expiration)
+    }
+
+    /// Calendar days since the position was opened, 0 when that isn't known.
+    fn days_held(&self) -> i64 {
+        self.opened_at.map_or(0, |opened| {
+            calendar_days_between(opened, Local::now().date_naive())
+        })
+    }
+
+    /// Splits the gain since entry into time decay over the days held, the
+    /// `underlying_move` in dollars and the `iv_move` in volatility points, each
+    /// priced with the greeks averaged between `entry_greeks` and `current_greeks`.
+    pub(crate) fn compute_pl_attribution(
+        &self,
+        entry_greeks: &OptionGreeks,
+        current_greeks: &OptionGreeks,
+        underlying_move: f64,
+        iv_move: f64,
+    ) -> PlAttribution {
+        let average =
+            |greek: fn(&OptionGreeks) -> f64| (greek(entry_greeks) + greek(current_greeks)) / 2.0;
+        // Per share price changes to dollars of the position, then to percent of its cost
+        let scale = self.signed_contracts() * CONTRACT_SIZE / self.cost.abs() * 100.0;
+
+        PlAttribution {
+            time_decay_pct: average(OptionGreeks::theta_daily) * self.days_held() as f64 * scale,
+            directional_pct: average(|g| g.greeks.delta) * underlying_move * scale,
+            iv_change_pct: average(|g| g.greeks.vega / 100.0) * iv_move * scale,
+        }
+    }
+
+    /// Attribution of the gain with the current greeks, the IV move since the
+    /// position was opened by `iv_history`, and the move of the underlying left
+    /// to explain the change in the option price. None without greeks.
+    fn estimate_pl_attribution(&self, iv_history: &IvHistory) -> Option<PlAttribution> {
+        let greeks = self.greeks.as_ref()?;
+        let g = &greeks.greeks;
+        let iv_move = self
+            .opened_at
+            .and_then(|opened| iv_history.iv_on(opened))
+            .map_or(0.0, |entry_iv| (g.implied_volatility - entry_iv) * 100.0);
+        let unexplained = self.last_price
+            - self.unit_cost
+            - greeks.theta_daily() * self.days_held() as f64
+            - g.vega / 100.0 * iv_move;
+        let underlying_move = if g.delta == 0.0 {
+            0.0
+        } else {
+            unexplained / g.delta
+        };
+
+        Some(self.compute_pl_attribution(greeks, greeks, underlying_move, iv_move))
+    }
+
+    /// Chance the position is in the money at expiration when long, out of it when short.
+    /// None without the IV from the greeks, or once expired.
+    fn probability_of_profit(&self, underlying_price: f64, risk_free_rate: f64) -> Option<f64> {
+        let iv = self.greeks.as_ref()?.greeks.implied_volatility;
+        let itm = probability_itm_at(
+            underlying_price,
+            self.strike,
+            self.expiration,
+            iv,
+            risk_free_rate,
+            &self.op_type,
+        )?;
+
+        Some(match self.side {
+            OrderSide::Buy => itm,
+            OrderSide::Sell => 1.0 - itm,
+        })
+    }
+
+    /// Premium collected over the capital at risk, annualized, in percent: the strike
+    /// for puts, `underlying_price` for calls covered by shares.
+    /// None for long positions, or once expired.
+    fn annualized_return(&self, underlying_price: f64) -> Option<f64> {
+        let dte = self.days_to_expiration();
+        if self.side != OrderSide::Sell || dte <= 0 {
+            return None;
+        }
+        let collateral_price = match self.op_type {
+            OptionType::Put => self.strike,
+            OptionType::Call => underlying_price,
+        };
+        let credit = -self.cost;
+        let collateral = collateral_price * CONTRACT_SIZE * self.quantity.abs() as f64;
+        let dte_years = dte as f64 / DAYS_PER_YEAR;
+
+        Some(credit / collateral / dte_years * 100.0)
+    }
+
+    /// Most the position can lose at expiration, in dollars: the premium paid when
+    /// long, the strike value less the credit for a short put, unbounded for a short call.
+    fn max_loss(&self) -> f64 {
+        match (&self.side, &self.op_type) {
+            (OrderSide::Buy, _) => self.cost,
+            (OrderSide::Sell, OptionType::Call) => f64::INFINITY,
+            (OrderSide::Sell, OptionType::Put) => self.strike_value() + self.cost,
+        }
+    }
+
+    /// Most the position can make at expiration, in dollars: the credit when short,
+    /// the strike value less the premium for a long put, unbounded for a long call.
+    fn max_profit(&self) -> f64 {
+        match (&self.side, &self.op_type) {
+            (OrderSide::Sell, _) => -self.cost,
+            (OrderSide::Buy, OptionType::Call) => f64::INFINITY,
+            (OrderSide::Buy, OptionType::Put) => self.strike_value() - self.cost,
+        }
+    }
+
+    /// Value of the shares the contracts are for, at the strike.
+    fn strike_value(&self) -> f64 {
+        self.strike * CONTRACT_SIZE * self.quantity.abs() as f64
+    }
+}
+
+/// `probability_itm` with the time left until `expiration`, None once expired or without a usable IV.
+fn probability_itm_at(
+    underlying_price: f64,
+    strike: f64,
+    expiration: NaiveDate,
+    iv: f64,
+    risk_free_rate: f64,
+    option_type: &OptionType,
+) -> Option<f64> {
+    let days = calendar_days_between(Local::now().date_naive(), expiration);
+    if days <= 0 || !(iv > 0.0 && underlying_price > 0.0) {
+        return None;
+    }
+
+    Some(probability_itm(
+        underlying_price,
+        strike,
+        risk_free_rate,
+        days as f64 / DAYS_PER_YEAR,
+        iv,
+        option_type.clone(),
+    ))
+}
+
+/// Gain of a position split by where it came from, each in percent of its cost.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlAttribution {
+    pub time_decay_pct: f64,
+    pub directional_pct: f64,
+    pub iv_change_pct: f64,
+}
+
+impl PlAttribution {
+    /// Gain explained by the three components together.
+    pub fn total_pct(&self) -> f64 {
+        self.time_decay_pct + self.directional_pct + self.iv_change_pct
+    }
+}
+
+impl std::fmt::Display for PlAttribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "time decay {:+.2}%, directional {:+.2}%, IV {:+.2}%",
+            self.time_decay_pct, self.directional_pct, self.iv_change_pct
+        )
+    }
+}
+
+/// Shares of the underlying per option contract.
+const CONTRACT_SIZE: f64 = 100.0;
+
+/// Greeks summed over positions and scaled to the contract size, so a delta
+/// of 50 moves like 50 shares of the underlying.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PortfolioGreeks {
+    pub delta: f64,
+    pub gamma: f64,
+    /// Dollars over a calendar day, see `OptionGreeks::dollar_theta`.
+    pub theta: f64,
+    /// Dollars for a 1 point move in IV, see `OptionGreeks::dollar_vega`.
+    pub vega: f64,
+    /// Total cost of the positions, negative for a net credit.
+    pub net_premium: f64,
+}
+
+impl PortfolioGreeks {
+    /// Dollar change of the positions for a 1% move of the underlying at `underlying_price`.
+    pub fn dollar_delta(&self, underlying_price: f64) -> f64 {
+        self.delta * underlying_price / 100.0
+    }
+}
+
+impl std::fmt::Display for PortfolioGreeks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Delta:{:.2} Gamma:{:.4} Theta:{:.2} Vega:{:.2} Net premium:${:.2}",
+            self.delta, self.gamma, self.theta, self.vega, self.net_premium
+        )
+    }
+}
+
+/// Sums the greeks of `positions`, short positions negated.
+/// Positions without greeks only count towards the net premium.
+fn compute_portfolio_greeks(positions: &[OptionPosition]) -> PortfolioGreeks {
+    positions
+        .iter()
+        .fold(PortfolioGreeks::default(), |mut total, pos| {
+            total.net_premium += pos.cost;
+            if let Some(g) = &pos.greeks {
+                let scale = pos.signed_contracts() * CONTRACT_SIZE;
+                let contracts = pos.signed_contracts() as i32;
+                total.delta += g.greeks.delta * scale;
+                total.gamma += g.greeks.gamma * scale;
+                total.theta += g.dollar_theta(contracts, CONTRACT_SIZE as u32);
+                total.vega += g.dollar_vega(contracts, CONTRACT_SIZE as u32);
+            }
+            total
+        })
+}
+
+/// Mean implied volatility of the positions with greeks, None without any.
+fn average_iv(positions: &[OptionPosition]) -> Option<f64> {
+    let ivs: Vec<f64> = positions
+        .iter()
+        .filter_map(|p| Some(p.greeks.as_ref()?.greeks.implied_volatility))
+        .collect();
+
+    (!ivs.is_empty()).then(|| ivs.iter().sum::<f64>() / ivs.len() as f64)
+}
+
+/// Shares to trade so the delta of a portfolio gets back to zero.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HedgeSuggestion {
+    pub ticker: String,
+    /// Signed like the trade, negative to sell short.
+    pub shares: i64,
+    pub direction: OrderSide,
+    /// Dollar value of the shares at the underlying price.
+    pub notional: f64,
+}
+
+impl std::fmt::Display for HedgeSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.shares == 0 {
+            return write!(f, "{} is delta neutral", self.ticker);
         }
+        write!(
+            f,
+            "Hedge: {} {} {} shares (${:.2})",
+            self.direction,
+            self.shares.abs(),
+            self.ticker,
+            self.notional
+        )
+    }
+}
+
+/// Each share of the underlying has a delta of 1, so the hedge is the
+/// portfolio delta in shares, the other way.
+pub fn suggest_delta_hedge(
+    portfolio_greeks: &PortfolioGreeks,
+    ticker: &str,
+    underlying_price: f64,
+) -> HedgeSuggestion {
+    let shares = -portfolio_greeks.delta.round() as i64;
+    let direction = if shares < 0 {
+        OrderSide::Sell
+    } else {
+        OrderSide::Buy
+    };
+
+    HedgeSuggestion {
+        ticker: ticker.to_string(),
+        shares,
+        direction,
+        notional: shares.abs() as f64 * underlying_price,
+    }
+}
+
+/// Fills in the greeks of `positions`, positions Public has no greeks for are left without.
+async fn fetch_position_greeks(
+    public: &PublicClient,
+    positions: &mut [OptionPosition],
+) -> Result<(), PublicError> {
+    let symbols: Vec<OsiSymbol> = positions
+        .iter()
+        .filter_map(|pos| pos.symbol.parse().ok())
+        .collect();
+    let greeks = public.get_option_greeks_batch(&symbols).await?;
+    for pos in positions {
+        pos.greeks = greeks.get(&pos.symbol).cloned();
+    }
+
+    Ok(())
+}
+
+fn calendar_days_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    (to - from).num_days().max(0)
+}
+
+/// Standard monthly expiration, the third Friday of the month, nearest on or after `today`.
+pub fn next_monthly_expiration(today: NaiveDate) -> NaiveDate {
+    let third_friday = |year: i32, month: u32| {
+        NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Fri, 3)
+            .expect("every month has a third Friday")
+    };
+    let this_month = third_friday(today.year(), today.month());
+    if this_month >= today {
+        return this_month;
     }
+
+    match today.month() {
+        12 => third_friday(today.year() + 1, 1),
+        month => third_friday(today.year(), month + 1),
+    }
+}
+
+/// Weekdays after `from`, up to and including `to`.
+fn trading_days_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    from.iter_days()
+        .skip(1)
+        .take_while(|day| *day <= to)
+        .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+        .count() as i64
 }
 
 struct _Stats {
@@ -111,7 +644,7 @@ impl std::fmt::Display for Spread {
             self.buy_side.strike,
             self.buy_side.unit_cost,
             self.count,
-            self.buy_side.cost * -1.0,
+            -self.buy_side.cost,
             self.buy_side.gain_value,
             (self.buy_side.gain_value / self.buy_side.cost.abs()) * 100.0
         )?;
@@ -126,45 +659,372 @@ impl std::fmt::Display for Spread {
     }
 }
 
+const DEFAULT_LOSS_THRESHOLD: f64 = -200.0;
+/// Quotes older than this aren't trusted to price an exit.
+const MAX_QUOTE_AGE: Duration = Duration::from_secs(60);
+/// Exits are skipped when the spread is wider than this percentage of the mid.
+const MAX_SPREAD_PCT: f64 = 50.0;
+
+/// Exit rules for `OptionsStopper`, read from the `[stopper]` config table.
+/// Thresholds are gain percentages, as reported in the position cost basis.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StopperConfig {
+    /// Exit when the gain falls to or below this, e.g. -200.0.
+    pub default_loss_threshold: f64,
+    /// Loss thresholds by ticker ("QCOM") or full option symbol.
+    pub symbol_overrides: HashMap<String, f64>,
+    /// Exit when the gain reaches this, if set.
+    pub profit_take_threshold: Option<f64>,
+    /// Exit when fewer days than this are left to expiration. 0 never exits.
+    pub dte_floor: u32,
+    /// Signal exits without fetching quotes, and skip the handlers placing orders.
+    pub dry_run: bool,
+    /// Fetch quotes for exits, but skip the handlers placing orders.
+    pub dry_run_exit: bool,
+    /// Place the exit orders. Off by default, exits are only logged with their limit.
+    pub execute: bool,
+    /// Risk parameters by ticker or full option symbol, from the `options` config list.
+    #[serde(skip)]
+    pub option_configs: HashMap<String, OptionConfig>,
+    /// Directory of the daily `{YYYY-MM-DD}.json` portfolio snapshots, none to skip them.
+    pub snapshot_dir: Option<PathBuf>,
+    /// Directory of the `{YYYY-MM-DD-HH-MM}.json` summaries of each run, none to skip them.
+    pub runs_dir: Option<PathBuf>,
+}
+
+/// Risk parameters for one entry of the `options` config list. Entries are
+/// either a bare ticker, the older flat format, or a table:
+///
+/// `{ symbol = "QCOM", stop_loss_pct = -100.0, dte_exit = 7 }`
+///
+/// Unset fields fall back to the `[stopper]` settings.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(from = "OptionConfigEntry")]
+pub struct OptionConfig {
+    pub symbol: String,
+    /// Exit when the gain falls to or below this percentage, like `default_loss_threshold`.
+    pub stop_loss_pct: Option<f64>,
+    /// Exit when the gain reaches this percentage.
+    pub profit_take_pct: Option<f64>,
+    /// Exit when fewer days than this are left to expiration.
+    pub dte_exit: Option<u32>,
+    /// Most contracts closed by a single exit order.
+    pub max_quantity: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OptionConfigEntry {
+    Symbol(String),
+    Table {
+        symbol: String,
+        stop_loss_pct: Option<f64>,
+        profit_take_pct: Option<f64>,
+        dte_exit: Option<u32>,
+        max_quantity: Option<u32>,
+    },
+}
+
+impl From<OptionConfigEntry> for OptionConfig {
+    fn from(entry: OptionConfigEntry) -> Self {
+        match entry {
+            OptionConfigEntry::Symbol(symbol) => OptionConfig {
+                symbol,
+                ..OptionConfig::default()
+            },
+            OptionConfigEntry::Table {
+                symbol,
+                stop_loss_pct,
+                profit_take_pct,
+                dte_exit,
+                max_quantity,
+            } => OptionConfig {
+                symbol,
+                stop_loss_pct,
+                profit_take_pct,
+                dte_exit,
+                max_quantity,
+            },
+        }
+    }
+}
+
+impl Default for StopperConfig {
+    fn default() -> Self {
+        Self {
+            default_loss_threshold: DEFAULT_LOSS_THRESHOLD,
+            symbol_overrides: HashMap::new(),
+            profit_take_threshold: None,
+            dte_floor: 0,
+            dry_run: false,
+            dry_run_exit: false,
+            execute: false,
+            option_configs: HashMap::new(),
+            snapshot_dir: None,
+            runs_dir: None,
+        }
+    }
+}
+
+/// What one run of `OptionsStopper` decided, over all the option positions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ExitSummary {
+    pub total_positions: u32,
+    /// Exits whose order was placed.
+    pub positions_exited: u32,
+    /// Exits without a placed order: dry runs, exits not executed, without a
+    /// limit price or whose order failed.
+    pub exits_not_placed: u32,
+    /// Gain of the exited positions, in dollars.
+    pub total_gain_value_closed: f64,
+    /// Exited positions by reason, like "stop loss".
+    pub exit_reasons: HashMap<String, u32>,
+}
+
+impl ExitSummary {
+    /// Summary of the `decisions` of a run, the exits of the symbols in `placed`
+    /// having their order placed.
+    fn new(decisions: &[(&OptionPosition, ExitReason)], placed: &HashSet<String>) -> Self {
+        let mut summary = ExitSummary {
+            total_positions: decisions.len() as u32,
+            ..ExitSummary::default()
+        };
+        for (pos, reason) in decisions.iter().filter(|(_, r)| *r != ExitReason::Hold) {
+            if !placed.contains(&pos.symbol) {
+                summary.exits_not_placed += 1;
+                continue;
+            }
+            summary.positions_exited += 1;
+            summary.total_gain_value_closed += pos.gain_value;
+            *summary.exit_reasons.entry(reason.to_string()).or_default() += 1;
+        }
+
+        summary
+    }
+}
+
+impl std::fmt::Display for ExitSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Exited {} of {} positions, closing ${:.2} of gains",
+            self.positions_exited, self.total_positions, self.total_gain_value_closed
+        )?;
+        let mut reasons: Vec<_> = self.exit_reasons.iter().collect();
+        reasons.sort();
+        for (reason, count) in reasons {
+            write!(f, ", {count} on {reason}")?;
+        }
+        if self.exits_not_placed > 0 {
+            write!(f, ", {} exits not placed", self.exits_not_placed)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum ExitReason {
+    StopLoss,
+    ProfitTake,
+    TimeBased,
+    Hold,
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::StopLoss => write!(f, "stop loss"),
+            Self::ProfitTake => write!(f, "profit take"),
+            Self::TimeBased => write!(f, "days to expiration"),
+            Self::Hold => write!(f, "hold"),
+        }
+    }
+}
+
+impl FromStr for ExitReason {
+    type Err = String;
+
+    /// Reads back the `Display` form.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop loss" => Ok(Self::StopLoss),
+            "profit take" => Ok(Self::ProfitTake),
+            "days to expiration" => Ok(Self::TimeBased),
+            "hold" => Ok(Self::Hold),
+            _ => Err(format!("unknown exit reason '{s}'")),
+        }
+    }
+}
+
+impl StopperConfig {
+    /// Whether the handlers placing orders get the signals: only with `execute`,
+    /// outside of the dry runs.
+    pub fn places_orders(&self) -> bool {
+        self.execute && !self.dry_run && !self.dry_run_exit
+    }
+
+    /// Adds per-symbol risk parameters, keyed by their symbol.
+    pub fn with_option_configs(mut self, configs: Vec<OptionConfig>) -> Self {
+        self.option_configs
+            .extend(configs.into_iter().map(|c| (c.symbol.clone(), c)));
+        self
+    }
+
+    /// Risk parameters of the full option symbol, or else of its ticker.
+    fn option_config(&self, position: &OptionPosition) -> Option<&OptionConfig> {
+        self.option_configs
+            .get(&position.symbol)
+            .or_else(|| self.option_configs.get(&position.ticker))
+    }
+
+    fn loss_threshold(&self, position: &OptionPosition) -> f64 {
+        self.option_config(position)
+            .and_then(|c| c.stop_loss_pct)
+            .or_else(|| {
+                self.symbol_overrides
+                    .get(&position.symbol)
+                    .or_else(|| self.symbol_overrides.get(&position.ticker))
+                    .copied()
+            })
+            .unwrap_or(self.default_loss_threshold)
+    }
+
+    fn should_exit(&self, position: &OptionPosition) -> ExitReason {
+        let option_config = self.option_config(position);
+        if position.gain_percent <= self.loss_threshold(position) {
+            return ExitReason::StopLoss;
+        }
+        if let Some(profit_take) = option_config
+            .and_then(|c| c.profit_take_pct)
+            .or(self.profit_take_threshold)
+            && position.gain_percent >= profit_take
+        {
+            return ExitReason::ProfitTake;
+        }
+        let dte_floor = option_config
+            .and_then(|c| c.dte_exit)
+            .unwrap_or(self.dte_floor);
+        if position.days_to_expiration() < i64::from(dte_floor) {
+            return ExitReason::TimeBased;
+        }
+
+        ExitReason::Hold
+    }
+
+    /// Contracts one exit order closes, capped by the symbol's `max_quantity`.
+    fn exit_quantity(&self, position: &OptionPosition) -> u32 {
+        let quantity = position.quantity.unsigned_abs() as u32;
+        self.option_config(position)
+            .and_then(|c| c.max_quantity)
+            .map_or(quantity, |max| quantity.min(max))
+    }
+}
+
+/// Checks the option positions against the exit rules of `config`, and hands
+/// a `Signal` for each of them to every handler, which act on the exits.
 pub struct OptionsStopper {
     public: PublicClient,
-    _threshold: f64,
-    _dry_run: bool,
-    _dry_run_exit: bool,
+    config: StopperConfig,
+    handlers: Vec<Box<dyn SignalHandler>>,
+    quote_writer: Option<QuoteWriter>,
+    metrics: Option<CloudWatchMetrics>,
+    last_summary: Mutex<Option<ExitSummary>>,
 }
 
 impl OptionsStopper {
     pub fn new(
         client: PublicClient,
-        threshold: f64,
-        dry_run: bool,
-        dry_run_exit: bool,
+        config: StopperConfig,
+        handlers: Vec<Box<dyn SignalHandler>>,
     ) -> OptionsStopper {
         Self {
             public: client,
-            _threshold: threshold,
-            _dry_run: dry_run,
-            _dry_run_exit: dry_run_exit,
+            config,
+            handlers,
+            quote_writer: None,
+            metrics: None,
+            last_summary: Mutex::new(None),
+        }
+    }
+
+    /// Summary of the latest run that checked the positions, None before one did.
+    /// A copy, as the next run replaces it.
+    pub fn last_run_summary(&self) -> Option<ExitSummary> {
+        self.last_summary.lock().unwrap().clone()
+    }
+
+    /// Logs `summary`, writes it to the runs directory and keeps it as the last one.
+    fn record_summary(&self, summary: ExitSummary) {
+        info!("{summary}");
+        if let Some(dir) = &self.config.runs_dir
+            && let Err(e) = write_run_summary(&summary, dir)
+        {
+            warn!("Failed to write the run summary to {dir:?}: {e}");
         }
+        *self.last_summary.lock().unwrap() = Some(summary);
+    }
+
+    /// Persists every quote fetched during a run.
+    pub fn with_quote_writer(mut self, writer: QuoteWriter) -> Self {
+        self.quote_writer = Some(writer);
+        self
+    }
+
+    /// Publishes a metric for every exit triggered.
+    pub fn with_metrics(mut self, metrics: CloudWatchMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 
     pub async fn run(&self) -> Result<(), PublicError> {
-        let all_holdings = self.public.get_account_portfolio().await?;
-        let options: Vec<OptionPosition> = all_holdings
-            .positions
+        match self
+            .public
+            .get_market_hours(Local::now().date_naive())
+            .await
+        {
+            Ok(hours) if !hours.is_currently_open() => {
+                info!("Market is closed, not checking positions");
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Cannot get the market hours, checking positions anyway: {e}"),
+        }
+        let portfolio = self.public.get_account_portfolio().await?;
+        let positions = &portfolio.positions;
+        let mut options: Vec<OptionPosition> = positions
             .iter()
             .filter(|p| p.is_option())
-            .map(OptionPosition::new)
+            .filter_map(|p| match OptionPosition::new(p) {
+                Ok(o) => Some(o),
+                Err(e) => {
+                    error!("Skipping position: {e}");
+                    None
+                }
+            })
             .collect();
         debug!("filtered options {options:?}");
+        if let Some(writer) = &self.quote_writer {
+            let measures: Vec<OptionPosInflux> = options
+                .iter()
+                .map(OptionPosition::to_influx_measure)
+                .collect();
+            if let Err(e) = writer.write_positions(&measures).await {
+                warn!("Failed to persist the option positions: {e}");
+            }
+        }
+        if let Err(e) = fetch_position_greeks(&self.public, &mut options).await {
+            warn!("Cannot get the greeks of the positions: {e}");
+        }
 
         let mut pos_groups: HashMap<String, Vec<OptionPosition>> = HashMap::new();
-        for o in options {
+        for o in &options {
             let key = format!("{}-{}", o.ticker, o.expiration);
             if let Some(group) = pos_groups.get_mut(&key) {
-                group.push(o);
+                group.push(o.clone());
             } else {
-                let _ = pos_groups.insert(key, vec![o]);
+                let _ = pos_groups.insert(key, vec![o.clone()]);
             }
         }
 
@@ -206,21 +1066,903 @@ impl OptionsStopper {
         for s in strategies {
             println!("{s}");
         }
+        if let Err(e) = self.print_hedges(&options).await {
+            warn!("Cannot suggest delta hedges: {e}");
+        }
+
+        let decisions: Vec<(&OptionPosition, ExitReason)> = options
+            .iter()
+            .map(|pos| (pos, self.config.should_exit(pos)))
+            .collect();
+        for (pos, reason) in decisions.iter().filter(|(_, r)| *r != ExitReason::Hold) {
+            info!(
+                "{} is at {:.2}% with {} DTE ({} trading), max loss ${:.2} max profit ${:.2}, exiting on {reason}",
+                pos.symbol,
+                pos.gain_percent,
+                pos.days_to_expiration(),
+                pos.trading_days_to_expiration(),
+                pos.max_loss(),
+                pos.max_profit()
+            );
+            let iv_history = IvHistory::load(&pos.ticker).unwrap_or_else(|e| {
+                warn!("Cannot load the IV history of {}: {e}", pos.ticker);
+                IvHistory::new(&pos.ticker)
+            });
+            if let Some(attribution) = pos.estimate_pl_attribution(&iv_history) {
+                info!("{} gain attribution: {attribution}", pos.symbol);
+            }
+        }
+
+        let signals = self.signals(&decisions).await?;
+        let placed = self.dispatch(&signals).await;
+        for signal in signals.iter().filter(|s| matches!(s, Signal::Exit { .. })) {
+            println!("{signal}");
+        }
+        self.record_summary(ExitSummary::new(&decisions, &placed));
+
+        if let Some(dir) = &self.config.snapshot_dir {
+            let positions: Vec<PositionSummary> =
+                options.iter().map(OptionPosition::to_summary).collect();
+            if let Err(e) = snapshot_portfolio(&positions, dir) {
+                warn!("Failed to snapshot the portfolio to {dir:?}: {e}");
+            }
+        }
+        if let Some(writer) = &self.quote_writer
+            && let Err(e) = writer
+                .write_portfolio_snapshot(&portfolio.account_type, positions)
+                .await
+        {
+            warn!("Failed to persist the portfolio snapshot: {e}");
+        }
 
         Ok(())
     }
-}
 
-pub struct OptionsAnalyze {
-    public: PublicClient,
-}
+    /// Prints the greeks of the positions on each underlying, with the shares hedging
+    /// their delta, and the probability of profit and annualized return of each position.
+    async fn print_hedges(&self, options: &[OptionPosition]) -> Result<(), PublicError> {
+        let mut by_ticker: HashMap<&str, Vec<OptionPosition>> = HashMap::new();
+        for o in options.iter().filter(|o| o.greeks.is_some()) {
+            by_ticker.entry(&o.ticker).or_default().push(o.clone());
+        }
+        if by_ticker.is_empty() {
+            return Ok(());
+        }
 
-#[derive(Debug)]
-pub struct OptionResult {
-    pub symbol: String,
-    pub good_put: Option<OptionResultData>,
-    pub good_call: Option<OptionResultData>,
-}
+        let instruments = by_ticker
+            .keys()
+            .map(|ticker| Instrument::equity(ticker))
+            .collect();
+        let prices: HashMap<String, f64> = self
+            .public
+            .get_quotes(instruments)
+            .await?
+            .into_iter()
+            .map(|q| (q.instrument.symbol, q.last))
+            .collect();
+
+        for (ticker, positions) in &by_ticker {
+            let greeks = compute_portfolio_greeks(positions);
+            println!("{ticker} positions: {greeks}");
+            let Some(price) = prices.get(*ticker) else {
+                warn!("No quote returned for {ticker}, cannot size its hedge");
+                continue;
+            };
+            println!(
+                "{ticker} dollar greeks: ${:.2} per 1% move, ${:.2} per IV point, ${:.2} per day",
+                greeks.dollar_delta(*price),
+                greeks.vega,
+                greeks.theta
+            );
+            println!("{}", suggest_delta_hedge(&greeks, ticker, *price));
+            if let Some(iv) = average_iv(positions) {
+                let var = compute_var(
+                    &greeks,
+                    *price,
+                    iv,
+                    DEFAULT_VAR_CONFIDENCE,
+                    DEFAULT_VAR_HORIZON_DAYS,
+                );
+                println!(
+                    "{ticker} {DEFAULT_VAR_HORIZON_DAYS} day VaR at {:.0}%: ${var:.2}",
+                    DEFAULT_VAR_CONFIDENCE * 100.0
+                );
+            }
+            for pos in positions {
+                if let Some(pop) = pos.probability_of_profit(*price, DEFAULT_RISK_FREE_RATE) {
+                    println!("{} PoP:{:.0}%", pos.symbol, pop * 100.0);
+                }
+                if let Some(annualized) = pos.annualized_return(*price) {
+                    println!("{} annualized return: {annualized:.1}%", pos.symbol);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Signal of each position, with the limit price of the exits taken from
+    /// quotes fetched in one request. No quotes are fetched in a dry run.
+    async fn signals(
+        &self,
+        decisions: &[(&OptionPosition, ExitReason)],
+    ) -> Result<Vec<Signal>, PublicError> {
+        let exits: Vec<Instrument> = decisions
+            .iter()
+            .filter(|(_, reason)| *reason != ExitReason::Hold)
+            .map(|(pos, _)| pos.instrument())
+            .collect();
+        let quotes = if self.config.dry_run || exits.is_empty() {
+            vec![]
+        } else {
+            self.public.get_quotes(exits).await?
+        };
+        if let Some(writer) = &self.quote_writer
+            && !quotes.is_empty()
+            && let Err(e) = writer.write_quotes(&quotes).await
+        {
+            warn!("Failed to persist quotes: {e}");
+        }
+        let quotes: HashMap<&str, &Quote> = quotes
+            .iter()
+            .map(|q| (q.instrument.symbol.as_str(), q))
+            .collect();
+
+        Ok(decisions
+            .iter()
+            .map(|(pos, reason)| {
+                if *reason == ExitReason::Hold {
+                    return Signal::Hold {
+                        symbol: pos.symbol.clone(),
+                    };
+                }
+                let quote = quotes.get(pos.symbol.as_str()).copied();
+                let side = match pos.side {
+                    OrderSide::Buy => OrderSide::Sell,
+                    OrderSide::Sell => OrderSide::Buy,
+                };
+                Signal::Exit {
+                    symbol: pos.symbol.clone(),
+                    reason: *reason,
+                    suggested_limit: self.limit_price(pos, quote),
+                    side,
+                    quantity: self.config.exit_quantity(pos),
+                    gain_percent: pos.gain_percent,
+                    bid: quote.map_or(f64::NAN, |q| q.bid),
+                    ask: quote.map_or(f64::NAN, |q| q.ask),
+                }
+            })
+            .collect())
+    }
+
+    /// Mid of `quote` rounded to the cent, NaN in a dry run or when the quote
+    /// isn't a market to close the position in.
+    fn limit_price(&self, pos: &OptionPosition, quote: Option<&Quote>) -> f64 {
+        if self.config.dry_run {
+            info!("[dry run] would exit {}", pos.symbol);
+            return f64::NAN;
+        }
+
+        let Some(quote) = quote else {
+            warn!("No quote returned for {}", pos.symbol);
+            return f64::NAN;
+        };
+        if let Some(reason) = quote.validity_reason() {
+            warn!(
+                "Quote for {} is not a valid market, {reason}, not closing",
+                pos.symbol
+            );
+            return f64::NAN;
+        }
+        if quote.is_stale(MAX_QUOTE_AGE) {
+            warn!(
+                "Quote for {} is stale ({}), not closing",
+                pos.symbol, quote.bid_timestamp
+            );
+            return f64::NAN;
+        }
+        if quote.spread_pct() > MAX_SPREAD_PCT {
+            warn!(
+                "Spread for {} is too wide ({}-{}, {:.1}%), not closing",
+                pos.symbol,
+                quote.bid,
+                quote.ask,
+                quote.spread_pct()
+            );
+            return f64::NAN;
+        }
+        let limit_price = (quote.mid_price() * 100.0).round() / 100.0;
+        info!(
+            "Can probably close {} between {}-{}, limit ${limit_price:.2}",
+            pos.symbol, quote.bid, quote.ask
+        );
+
+        limit_price
+    }
+
+    /// Hands every signal to every handler, the signals concurrently, and
+    /// publishes the exit metrics. Handlers placing orders are left out unless
+    /// the config executes the exits. Returns the symbols of the exits whose
+    /// order was placed, by every handler placing orders.
+    async fn dispatch(&self, signals: &[Signal]) -> HashSet<String> {
+        let places_orders = self.config.places_orders();
+        let handlers: Vec<&dyn SignalHandler> = self
+            .handlers
+            .iter()
+            .map(|h| h.as_ref())
+            .filter(|h| places_orders || !h.places_orders())
+            .collect();
+
+        let mut pending: FuturesUnordered<_> = signals
+            .iter()
+            .map(|signal| {
+                let handlers = &handlers;
+                async move {
+                    let mut orders_failed = false;
+                    for handler in handlers {
+                        if let Err(e) = handler.handle(signal).await {
+                            error!("Failed to handle {signal}: {e}");
+                            orders_failed |= handler.places_orders();
+                        }
+                    }
+                    let Signal::Exit {
+                        symbol,
+                        reason,
+                        suggested_limit,
+                        gain_percent,
+                        ..
+                    } = signal
+                    else {
+                        return None;
+                    };
+                    if self.config.dry_run_exit {
+                        info!("[dry run exit] would place {signal}");
+                    } else if !places_orders && !self.config.dry_run {
+                        info!("Not executing, would place {signal}");
+                    }
+                    if let Some(metrics) = &self.metrics
+                        && let Err(e) = metrics
+                            .emit_exit(symbol, reason, *gain_percent as f32)
+                            .await
+                    {
+                        warn!("Failed to emit exit metrics of {symbol}: {e}");
+                    }
+
+                    // Order handlers skip the exits without a limit price
+                    let placed = handlers.iter().any(|h| h.places_orders())
+                        && !suggested_limit.is_nan()
+                        && !orders_failed;
+                    placed.then(|| symbol.clone())
+                }
+            })
+            .collect();
+
+        let mut placed = HashSet::new();
+        while let Some(symbol) = pending.next().await {
+            placed.extend(symbol);
+        }
+        placed
+    }
+}
+
+pub struct OptionsAnalyze {
+    public: PublicClient,
+    config: AnalysisConfig,
+    /// Equities and expiration analyzed when run as a `Strategy`.
+    watchlist: Option<(Vec<String>, String)>,
+}
+
+/// Tolerance when matching strikes parsed from OSI symbols.
+const STRIKE_EPSILON: f64 = 1e-6;
+
+/// Volume over open interest from which `unusual_options_activity` flags a strike.
+const DEFAULT_VOL_OI_THRESHOLD: f64 = 1.0;
+const DEFAULT_CONDOR_WING_WIDTH: f64 = 5.0;
+const DEFAULT_CONDOR_MIN_CREDIT: f64 = 0.25;
+const DEFAULT_RISK_FREE_RATE: f64 = 0.045;
+/// Symbols `analyze_portfolio` analyzes at the same time.
+const MAX_CONCURRENT_ANALYSES: usize = 4;
+/// Characters of the longest bar of `OptionsAnalyze::print_volume_histogram`.
+const HISTOGRAM_WIDTH: usize = 40;
+/// Iron condors `AnalysisReport` keeps, best credit to width first.
+const REPORT_CONDORS: usize = 3;
+/// Calls and puts with the most open interest `AnalysisReport` keeps.
+const REPORT_TOP_OI: usize = 3;
+/// Latest articles `analyze_option` adds to a report.
+#[cfg(feature = "news")]
+const REPORT_NEWS: u32 = 5;
+
+/// Settings for `OptionsAnalyze`, read from the `[analysis]` config table.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    pub vol_oi_threshold: f64,
+    /// Distance between the short and long strikes of iron condor wings.
+    pub condor_wing_width: f64,
+    /// Skip iron condors opening for less than this credit per share.
+    pub condor_min_credit: f64,
+    /// Annualized rate used for the probabilities of profit.
+    pub risk_free_rate: f64,
+    /// Config group of equities analyzed by the `run` command.
+    pub equities_group: Option<String>,
+    /// Expiration analyzed by the `run` command, like "2026-02-27".
+    pub expiration: Option<String>,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            vol_oi_threshold: DEFAULT_VOL_OI_THRESHOLD,
+            condor_wing_width: DEFAULT_CONDOR_WING_WIDTH,
+            condor_min_credit: DEFAULT_CONDOR_MIN_CREDIT,
+            risk_free_rate: DEFAULT_RISK_FREE_RATE,
+            equities_group: None,
+            expiration: None,
+        }
+    }
+}
+
+/// A strike trading well above its open interest.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UnusualActivity {
+    pub symbol: OsiSymbol,
+    pub side: OptionType,
+    pub volume: u64,
+    pub open_interest: u64,
+    pub ratio: f64,
+    pub bid: f64,
+    pub ask: f64,
+}
+
+impl std::fmt::Display for UnusualActivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} {:>7}/{:<7} Vol:{:<6} OI:{:<6} Vol/OI:{:.2}",
+            self.symbol, self.bid, self.ask, self.volume, self.open_interest, self.ratio
+        )
+    }
+}
+
+/// Delta `AnalysisReport` highlights for short premium entries.
+const TARGET_DELTA: f64 = 0.16;
+/// Delta of the wings compared by `OptionsAnalyze::skew_ratio`.
+const SKEW_DELTA: f64 = 0.25;
+/// Distance of the skew ratio from 1 below which the skew reads as flat.
+const FLAT_SKEW: f64 = 0.1;
+
+/// Put over call IV at 25 delta, next to the ATM IV.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SkewSummary {
+    /// NaN without greeks for the ATM strikes.
+    pub atm_iv: f64,
+    /// NaN without greeks for both wings.
+    pub skew_ratio: f64,
+    pub skew_interpretation: String,
+}
+
+impl SkewSummary {
+    fn new(atm_iv: f64, skew_ratio: f64) -> Self {
+        let skew_interpretation = if skew_ratio.is_nan() {
+            "unknown"
+        } else if skew_ratio > 1.0 + FLAT_SKEW {
+            "put skew, downside protection is bid"
+        } else if skew_ratio < 1.0 - FLAT_SKEW {
+            "call skew, upside is bid"
+        } else {
+            "flat"
+        };
+
+        Self {
+            atm_iv,
+            skew_ratio,
+            skew_interpretation: skew_interpretation.to_string(),
+        }
+    }
+}
+
+/// Snapshot of one expiration of an option chain, around the money.
+#[derive(Clone, Debug, Serialize)]
+pub struct AnalysisReport {
+    pub symbol: String,
+    pub expiration: NaiveDate,
+    pub underlying_price: Option<f64>,
+    /// Price of the ATM straddle, NaN without an underlying price.
+    pub expected_move_dollar: f64,
+    /// `expected_move_dollar` as a percentage of the underlying price.
+    pub expected_move_pct: f64,
+    /// Nearest the money first.
+    pub puts: Vec<OptionRow>,
+    /// Nearest the money first.
+    pub calls: Vec<OptionRow>,
+    pub put_call_ratio: f64,
+    pub total_oi: u64,
+    pub max_pain: f64,
+    /// Highest volume over open interest first.
+    pub unusual_activity: Vec<UnusualActivity>,
+    /// Best iron condors by credit to width, empty without an underlying price.
+    pub iron_condors: Vec<IronCondor>,
+    /// Rank of the ATM IV against the stored history, in percent.
+    pub iv_rank: Option<f64>,
+    /// 52 week low and high of the stored IV history.
+    pub iv_52w: Option<(f64, f64)>,
+    /// Greeks of the option positions held on the symbol, None without positions.
+    pub portfolio_greeks: Option<PortfolioGreeks>,
+    /// Shares neutralizing `portfolio_greeks`, None without positions or underlying price.
+    pub delta_hedge: Option<HedgeSuggestion>,
+    /// Volume of the whole chain by strike.
+    pub volume_profile: BTreeMap<OrderedFloat<f64>, u64>,
+    /// Open interest of the whole chain by strike.
+    pub open_interest_profile: BTreeMap<OrderedFloat<f64>, u64>,
+    pub skew: SkewSummary,
+    /// Calls of the whole chain with the most open interest, the easiest to close.
+    pub top_oi_calls: Vec<OptionRow>,
+    /// Puts of the whole chain with the most open interest.
+    pub top_oi_puts: Vec<OptionRow>,
+    /// Latest news about the symbol, empty without a `[news]` config.
+    #[cfg(feature = "news")]
+    pub news: Vec<NewsItem>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OptionRow {
+    pub symbol: OsiSymbol,
+    pub strike: f64,
+    pub bid: f64,
+    pub ask: f64,
+    pub volume: u64,
+    pub open_interest: u64,
+    pub greeks: Option<OptionGreeks>,
+    /// Chance that selling the contract pays off, it expiring out of the money.
+    /// None without greeks or underlying price.
+    pub probability_of_profit: Option<f64>,
+    /// What selling the contract at the mid is expected to make per share, given
+    /// the expected payoff at its IV. None without greeks or underlying price.
+    pub expected_value: Option<f64>,
+}
+
+impl AnalysisReport {
+    /// Builds the report from a fetched chain, centered on the underlying price
+    /// (or on max pain without one). `greeks` is keyed by OSI symbol.
+    pub(crate) fn new(
+        symbol: String,
+        expiration: NaiveDate,
+        underlying_price: Option<f64>,
+        chain: &OptionChain,
+        strikes: usize,
+        greeks: &HashMap<String, OptionGreeks>,
+        config: &AnalysisConfig,
+    ) -> AnalysisReport {
+        let max_pain = OptionsAnalyze::find_max_pain(chain);
+        let (calls, puts) =
+            strikes_around_atm(chain, underlying_price.unwrap_or(max_pain), strikes);
+        let rows = |quotes: Vec<&Quote>| -> Vec<OptionRow> {
+            quotes
+                .into_iter()
+                .filter_map(|q| {
+                    let row = OptionRow::new(q, greeks.get(&q.instrument.symbol).cloned())?;
+                    Some(match underlying_price {
+                        Some(price) => row
+                            .with_probability_of_profit(price, config.risk_free_rate)
+                            .with_expected_value(q, price, config.risk_free_rate),
+                        None => row,
+                    })
+                })
+                .collect()
+        };
+
+        let (expected_move_dollar, expected_move_pct) = underlying_price
+            .map_or((f64::NAN, f64::NAN), |price| {
+                OptionsAnalyze::expected_move(chain, price)
+            });
+        let mut iron_condors = underlying_price.map_or(vec![], |price| {
+            OptionsAnalyze::find_iron_condors(
+                chain,
+                price,
+                config.condor_wing_width,
+                config.condor_min_credit,
+            )
+        });
+        iron_condors.truncate(REPORT_CONDORS);
+        let skew_ratio = chain
+            .atm_strike(underlying_price.unwrap_or(max_pain))
+            .map_or(f64::NAN, |atm| {
+                OptionsAnalyze::skew_ratio(chain, greeks, atm)
+            });
+        let (top_oi_calls, top_oi_puts) =
+            OptionsAnalyze::find_highest_open_interest_strikes(chain, REPORT_TOP_OI);
+
+        let mut report = AnalysisReport {
+            symbol,
+            expiration,
+            underlying_price,
+            expected_move_dollar,
+            expected_move_pct,
+            puts: rows(puts),
+            calls: rows(calls),
+            put_call_ratio: chain.put_call_ratio(),
+            total_oi: chain.total_open_interest(),
+            max_pain,
+            unusual_activity: OptionsAnalyze::unusual_options_activity(
+                chain,
+                config.vol_oi_threshold,
+            ),
+            iron_condors,
+            iv_rank: None,
+            iv_52w: None,
+            portfolio_greeks: None,
+            delta_hedge: None,
+            volume_profile: chain.volume_profile(),
+            open_interest_profile: chain.open_interest_profile(),
+            skew: SkewSummary::new(f64::NAN, skew_ratio),
+            top_oi_calls: rows(top_oi_calls.iter().collect()),
+            top_oi_puts: rows(top_oi_puts.iter().collect()),
+            #[cfg(feature = "news")]
+            news: vec![],
+        };
+        report.skew.atm_iv = report.atm_iv().unwrap_or(f64::NAN);
+
+        report
+    }
+
+    /// IV of the nearest the money call and put, averaged.
+    pub fn atm_iv(&self) -> Option<f64> {
+        let ivs: Vec<f64> = [self.calls.first(), self.puts.first()]
+            .into_iter()
+            .flatten()
+            .filter_map(|row| Some(row.greeks.as_ref()?.greeks.implied_volatility))
+            .collect();
+
+        (!ivs.is_empty()).then(|| ivs.iter().sum::<f64>() / ivs.len() as f64)
+    }
+
+    /// Keeps the rows traded at least `min_volume` times.
+    pub fn filter_rows(mut self, min_volume: u64) -> Self {
+        self.calls.retain(|row| row.volume >= min_volume);
+        self.puts.retain(|row| row.volume >= min_volume);
+
+        self
+    }
+
+    /// Keeps the rows of the contracts still in `chain`.
+    fn retain_contracts(mut self, chain: &OptionChain) -> Self {
+        let symbols: HashSet<String> = chain
+            .calls
+            .iter()
+            .chain(&chain.puts)
+            .map(|q| q.instrument.symbol.clone())
+            .collect();
+        self.calls
+            .retain(|row| symbols.contains(&row.symbol.to_string()));
+        self.puts
+            .retain(|row| symbols.contains(&row.symbol.to_string()));
+
+        self
+    }
+
+    /// Ranks the ATM IV against `history`, left unset without history.
+    fn with_iv_history(mut self, history: &IvHistory) -> AnalysisReport {
+        if let Some(iv) = self.atm_iv()
+            && !history.samples.is_empty()
+        {
+            self.iv_rank = Some(history.rank(iv));
+            self.iv_52w = Some(history.percentile_52w()).filter(|(low, _)| !low.is_nan());
+        }
+
+        self
+    }
+
+    /// Row whose absolute delta is closest to `target`, among rows with greeks.
+    pub fn closest_to_delta(rows: &[OptionRow], target: f64) -> Option<&OptionRow> {
+        rows.iter()
+            .filter_map(|row| Some((row, row.greeks.as_ref()?.greeks.delta)))
+            .min_by(|(_, a), (_, b)| {
+                (a.abs() - target)
+                    .abs()
+                    .total_cmp(&(b.abs() - target).abs())
+            })
+            .map(|(row, _)| row)
+    }
+}
+
+impl OptionRow {
+    /// None when the quote isn't for a valid OSI symbol.
+    fn new(quote: &Quote, greeks: Option<OptionGreeks>) -> Option<OptionRow> {
+        let symbol: OsiSymbol = quote.instrument.symbol.parse().ok()?;
+        let greeks = greeks.or_else(|| {
+            let g = quote.option_details.as_ref()?.greeks.clone()?;
+            Some(OptionGreeks {
+                symbol: symbol.to_string(),
+                greeks: g,
+            })
+        });
+
+        Some(OptionRow {
+            strike: symbol.parts().strike,
+            symbol,
+            bid: quote.bid,
+            ask: quote.ask,
+            volume: quote.volume,
+            open_interest: quote.open_interest.unwrap_or(0),
+            greeks,
+            probability_of_profit: None,
+            expected_value: None,
+        })
+    }
+
+    fn with_expected_value(
+        mut self,
+        quote: &Quote,
+        underlying_price: f64,
+        risk_free_rate: f64,
+    ) -> Self {
+        self.expected_value = self
+            .greeks
+            .as_ref()
+            .map(|g| short_expected_value(quote, underlying_price, g, risk_free_rate))
+            .filter(|ev| !ev.is_nan());
+
+        self
+    }
+
+    fn with_probability_of_profit(mut self, underlying_price: f64, risk_free_rate: f64) -> Self {
+        let parts = self.symbol.parts();
+        self.probability_of_profit = self.greeks.as_ref().and_then(|g| {
+            let itm = probability_itm_at(
+                underlying_price,
+                parts.strike,
+                parts.expiration,
+                g.greeks.implied_volatility,
+                risk_free_rate,
+                &parts.option_type,
+            )?;
+            Some(1.0 - itm)
+        });
+
+        self
+    }
+}
+
+impl std::fmt::Display for OptionRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} {:>7}/{:<7} Vol:{:<6} OI:{:<6}",
+            self.symbol, self.bid, self.ask, self.volume, self.open_interest
+        )?;
+        if let Some(g) = &self.greeks {
+            let g = &g.greeks;
+            write!(
+                f,
+                " Delta:{:>7.4} Gamma:{:.4} Theta:{:.4} Vega:{:.4} IV:{:.2}",
+                g.delta, g.gamma, g.theta, g.vega, g.implied_volatility
+            )?;
+        }
+        if let Some(pop) = self.probability_of_profit {
+            write!(f, " PoP:{:.0}%", pop * 100.0)?;
+        }
+        if let Some(ev) = self.expected_value {
+            write!(f, " EV:{ev:.2}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints an `AnalysisReport`, puts and calls ordered by strike.
+pub fn print_report(report: &AnalysisReport) {
+    let price = report
+        .underlying_price
+        .map_or("n/a".to_string(), |p| format!("${p:.2}"));
+    println!("{} {} @ {price}", report.symbol, report.expiration);
+    if !report.expected_move_dollar.is_nan() {
+        println!(
+            "Expected move: ±${:.2} ({:.1}%)",
+            report.expected_move_dollar, report.expected_move_pct
+        );
+    }
+    println!(
+        "Open interest: {}, P/C OI: {:.2}, Max pain: ${:.2}",
+        report.total_oi, report.put_call_ratio, report.max_pain
+    );
+    if let Some(iv) = report.atm_iv() {
+        let rank = report
+            .iv_rank
+            .map_or("n/a".to_string(), |r| format!("{r:.0}%"));
+        let range = report.iv_52w.map_or("n/a".to_string(), |(low, high)| {
+            format!("{low:.2}-{high:.2}")
+        });
+        println!("ATM IV: {iv:.2}, IV rank: {rank}, 52w range: {range}");
+    }
+    if !report.skew.skew_ratio.is_nan() {
+        println!(
+            "25 delta skew: {:.2}, {}",
+            report.skew.skew_ratio, report.skew.skew_interpretation
+        );
+    }
+    report
+        .puts
+        .iter()
+        .rev()
+        .chain(report.calls.iter())
+        .for_each(|row| println!("{row}"));
+
+    if !report.unusual_activity.is_empty() {
+        println!("Unusual activity:");
+        report
+            .unusual_activity
+            .iter()
+            .for_each(|activity| println!("{activity}"));
+    }
+
+    for condor in &report.iron_condors {
+        println!(
+            "Iron condor {}/{}P {}/{}C credit ${:.2} max loss ${:.2} break even {:.2}-{:.2}",
+            condor.long_put.strike().unwrap_or_default(),
+            condor.short_put.strike().unwrap_or_default(),
+            condor.short_call.strike().unwrap_or_default(),
+            condor.long_call.strike().unwrap_or_default(),
+            condor.net_credit(),
+            condor.max_loss(),
+            condor.lower_break_even(),
+            condor.upper_break_even()
+        );
+    }
+
+    if !(report.top_oi_calls.is_empty() && report.top_oi_puts.is_empty()) {
+        println!("Most liquid strikes:");
+        report
+            .top_oi_puts
+            .iter()
+            .chain(report.top_oi_calls.iter())
+            .for_each(|row| println!("{row}"));
+    }
+
+    if let Some(greeks) = &report.portfolio_greeks {
+        println!("Positions: {greeks}");
+    }
+    if let Some(hedge) = &report.delta_hedge {
+        println!("{hedge}");
+    }
+
+    let good_put = AnalysisReport::closest_to_delta(&report.puts, TARGET_DELTA);
+    let good_call = AnalysisReport::closest_to_delta(&report.calls, TARGET_DELTA);
+    println!(
+        "Good Put: {}",
+        good_put.map_or("none".to_string(), |r| r.to_string())
+    );
+    println!(
+        "Good Call: {}",
+        good_call.map_or("none".to_string(), |r| r.to_string())
+    );
+
+    #[cfg(feature = "news")]
+    if !report.news.is_empty() {
+        println!("News:");
+        report.news.iter().for_each(|item| println!("{item}"));
+    }
+}
+
+/// ATM figures of one expiration, to compare the term structure of a symbol.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ExpirationSummary {
+    pub expiration: NaiveDate,
+    /// NaN without greeks for the ATM strikes.
+    pub atm_iv: f64,
+    pub expected_move_pct: f64,
+    pub put_call_ratio: f64,
+    pub total_oi: u64,
+}
+
+impl std::fmt::Display for ExpirationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} {:>8.2} {:>8.1}% {:>6.2} {:>9}",
+            self.expiration,
+            self.atm_iv,
+            self.expected_move_pct,
+            self.put_call_ratio,
+            self.total_oi
+        )
+    }
+}
+
+/// Prints `summaries` as a table, in their order.
+pub fn print_expiration_summaries(summaries: &[ExpirationSummary]) {
+    println!("Expiration   ATM IV     Move    P/C  Open int");
+    summaries.iter().for_each(|s| println!("{s}"));
+}
+
+/// Out of the money call to sell against shares held, from `OptionsAnalyze::find_covered_calls`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CoveredCallSuggestion {
+    pub ticker: String,
+    pub shares_held: i64,
+    pub recommended_strike: f64,
+    pub mid_price: f64,
+    /// Mid price over the share price, annualized, in percent.
+    pub annualized_yield: f64,
+}
+
+impl std::fmt::Display for CoveredCallSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} ({} shares): sell the {} call at {:.2}, {:.1}% annualized",
+            self.ticker,
+            self.shares_held,
+            self.recommended_strike,
+            self.mid_price,
+            self.annualized_yield
+        )
+    }
+}
+
+/// Put to sell backed by cash, from `OptionsAnalyze::find_cash_secured_puts`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CspSuggestion {
+    pub ticker: String,
+    pub strike: f64,
+    pub mid_price: f64,
+    /// Cash set aside to be assigned one contract.
+    pub capital_required: f64,
+    /// Mid price over the strike, annualized, in percent.
+    pub annualized_yield: f64,
+    /// NaN without greeks.
+    pub delta: f64,
+    /// What selling the put at the mid is expected to make per share, see
+    /// `OptionRow::expected_value`. NaN without greeks or underlying price.
+    pub expected_value: f64,
+}
+
+impl std::fmt::Display for CspSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}: sell the {} put at {:.2} (delta {:.2}), ${:.0} secured, {:.1}% annualized, EV {:.2}",
+            self.ticker,
+            self.strike,
+            self.mid_price,
+            self.delta,
+            self.capital_required,
+            self.annualized_yield,
+            self.expected_value
+        )
+    }
+}
+
+/// The `n` quotes with the highest `key`, highest first, in chain order on ties.
+fn top_quotes(quotes: &[Quote], n: usize, key: impl Fn(&Quote) -> u64) -> Vec<Quote> {
+    let mut quotes = quotes.to_vec();
+    quotes.sort_by_key(|q| std::cmp::Reverse(key(q)));
+    quotes.truncate(n);
+
+    quotes
+}
+
+/// Credit of selling the contract of `quote` at the mid, less its expected payoff.
+fn short_expected_value(
+    quote: &Quote,
+    underlying_price: f64,
+    greeks: &OptionGreeks,
+    risk_free_rate: f64,
+) -> f64 {
+    let credit = (quote.bid + quote.ask) / 2.0;
+
+    credit - expected_value(quote, underlying_price, greeks, risk_free_rate)
+}
+
+/// `premium` over `collateral_price` per share, annualized until `expiration`, in percent.
+/// None once expired.
+fn annualized_yield(premium: f64, collateral_price: f64, expiration: NaiveDate) -> Option<f64> {
+    let dte = calendar_days_between(Local::now().date_naive(), expiration);
+    if dte == 0 {
+        return None;
+    }
+
+    Some(premium / collateral_price / (dte as f64 / DAYS_PER_YEAR) * 100.0)
+}
 
 // TODO: add expiration date
 #[derive(Debug)]
@@ -248,8 +1990,7 @@ impl Ord for OptionResultData {
 
 impl PartialOrd for OptionResultData {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.capital_efficiency()
-            .partial_cmp(&other.capital_efficiency())
+        Some(self.cmp(other))
     }
 }
 
@@ -265,50 +2006,27 @@ impl PartialEq for OptionResultData {
 impl Eq for OptionResultData {}
 
 impl OptionResultData {
-    fn capital_efficiency(&self) -> f64 {
-        let diff = self.q_ask - self.q_bid;
-        let realistic_price = diff / 3.0;
-        (self.q_bid + realistic_price) / self.strike
-    }
-}
+    /// Builds the row from a chain quote and greeks fetched separately.
+    fn with_greeks(quote: &Quote, greeks: &Greeks) -> OptionResultData {
+        let intrument = &quote.instrument;
+        let osi = OsiSymbol::parse(&intrument.symbol).expect("Cannot parse OSI symbol from quote");
+        let (symbol, opt_type, strike) = (osi.ticker, osi.option_type, osi.strike);
 
-impl Into<OptionResultData> for &Quote {
-    fn into(self) -> OptionResultData {
-        let intrument = &self.instrument;
-        let (symbol, opt_type) = parse_symbol_and_type_from_full_symbol(&intrument.symbol);
+        let q_bid = quote.bid;
+        let q_ask = quote.ask;
+        let volume = quote.volume;
 
-        let q_bid = self.bid.parse().expect("Cannot parse bid from quote");
-        let q_ask = self.ask.parse().expect("Cannot parse ask from quote");
-        let volume = self.volume;
-
-        let opt_details = self.option_details.as_ref().unwrap();
-        let greeks = opt_details.greeks.as_ref().unwrap();
-        let iv = greeks
-            .implied_volatility
-            .parse()
-            .expect("Cannot parse greeks");
-        let delta = greeks
-            .delta
-            .parse()
-            .expect("Cannot parse delta from greeks");
-        let gamma = greeks
-            .gamma
-            .parse()
-            .expect("Cannot parse gamma from greeks");
-        let theta = greeks
-            .theta
-            .parse()
-            .expect("Cannot parse theta from greeks");
-        let vega = greeks.vega.parse().expect("Cannot parse vega from greeks");
-        let rho = greeks.rho.parse().expect("Cannot parse rho from greeks");
+        let iv = greeks.implied_volatility;
+        let delta = greeks.delta;
+        let gamma = greeks.gamma;
+        let theta = greeks.theta;
+        let vega = greeks.vega;
+        let rho = greeks.rho;
 
         OptionResultData {
             symbol,
             opt_type,
-            strike: opt_details
-                .strike_price
-                .parse()
-                .expect("Cannot parse strike price"),
+            strike,
             q_bid,
             q_ask,
             volume,
@@ -320,10 +2038,25 @@ impl Into<OptionResultData> for &Quote {
             rho,
         }
     }
+
+    fn capital_efficiency(&self) -> f64 {
+        let diff = self.q_ask - self.q_bid;
+        let realistic_price = diff / 3.0;
+        (self.q_bid + realistic_price) / self.strike
+    }
+}
+
+impl From<&Quote> for OptionResultData {
+    fn from(quote: &Quote) -> OptionResultData {
+        let opt_details = quote.option_details.as_ref().unwrap();
+        let greeks = opt_details.greeks.as_ref().unwrap();
+
+        OptionResultData::with_greeks(quote, greeks)
+    }
 }
 
-impl ToString for OptionResultData {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for OptionResultData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         let opt_type = &self.opt_type;
         let sym = &self.symbol;
         let strike = self.strike;
@@ -332,44 +2065,31 @@ impl ToString for OptionResultData {
         let delta = self.delta;
         let ce = self.capital_efficiency() * 100.0;
 
-        format!("{sym:>5} {opt_type}@${strike:<6} {bid:>5}/{ask:<5} Delta:{delta:>8} CE:{ce:.2}")
+        write!(
+            f,
+            "{sym:>5} {opt_type}@${strike:<6} {bid:>5}/{ask:<5} Delta:{delta:>8} CE:{ce:.2}"
+        )
     }
 }
 
-/// Gets OptionType from an option symbol like "MU260417P00830000"
-fn parse_symbol_and_type_from_full_symbol(symbol: &str) -> (String, OptionType) {
-    let opt_idx = symbol.len() - 9;
-    let mut chars = symbol.char_indices();
-
-    let mut sym_done = false;
-    let mut sym_chars: Vec<char> = Vec::new();
-
-    while let Some((idx, char)) = chars.next() {
-        if !sym_done {
-            if char.is_alphabetic() {
-                sym_chars.push(char);
-            } else {
-                sym_done = true;
-            }
-        }
-
-        if opt_idx != idx {
-            continue;
-        }
-        let symbol = sym_chars.iter().collect();
-        match char {
-            'P' => return (symbol, OptionType::Put),
-            'C' => return (symbol, OptionType::Call),
-            _ => break,
+impl OptionsAnalyze {
+    pub fn new(client: PublicClient) -> Self {
+        Self {
+            public: client,
+            config: AnalysisConfig::default(),
+            watchlist: None,
         }
     }
 
-    panic!("Could not parse OptionType from symbol <{}>", symbol);
-}
+    /// Sets the equities and expiration analyzed when run as a `Strategy`.
+    pub fn with_watchlist(mut self, equities: Vec<String>, expiration: String) -> Self {
+        self.watchlist = Some((equities, expiration));
+        self
+    }
 
-impl OptionsAnalyze {
-    pub fn new(client: PublicClient) -> Self {
-        Self { public: client }
+    pub fn with_config(mut self, config: AnalysisConfig) -> Self {
+        self.config = config;
+        self
     }
 
     /// Collect option chain data for single instrument
@@ -380,10 +2100,7 @@ impl OptionsAnalyze {
         expiration: &str,
     ) -> Result<(Vec<OptionResultData>, Vec<OptionResultData>), PublicError> {
         debug!("Fetching option chain for {equity_symbol}:{expiration}");
-        let instrument = Instrument {
-            instrument_type: InstrumentType::Equity,
-            symbol: equity_symbol.to_string(),
-        };
+        let instrument = Instrument::equity(equity_symbol);
         // let quote = self.public.get_quotes(vec![instrument.clone()]).await?;
         let chain = self
             .public
@@ -396,50 +2113,651 @@ impl OptionsAnalyze {
         Ok((calls, puts))
     }
 
-    /// TODO: ### BROKEN ###
-    pub async fn analyze_option(
-        &self,
-        equity_symbol: String,
-        expiration: String,
-    ) -> Result<OptionResult, PublicError> {
-        let (calls, puts) = self
-            .fetch_single_opt_data(&equity_symbol, &expiration)
-            .await?;
-
-        let target_delta = 0.16;
-        let mut good_put = None;
-        let mut good_call = None;
-        let mut put_d_dist = 1.0;
-        let mut call_d_dist = 1.0;
-
-        for put in puts {
-            let d_dist = (put.delta.abs() - target_delta).abs();
-            if d_dist <= put_d_dist {
-                put_d_dist = d_dist;
-                good_put = Some(put);
-            }
-        }
+    /// Max pain: the strike where the options in the chain would pay their
+    /// holders the least if the underlying expired there.
+    /// Returns NaN for a chain without any strikes.
+    pub fn find_max_pain(chain: &OptionChain) -> f64 {
+        let open = |quotes: &[Quote]| -> Vec<(f64, f64)> {
+            quotes
+                .iter()
+                .filter_map(|q| Some((q.strike()?, q.open_interest.unwrap_or(0) as f64)))
+                .collect()
+        };
+        let calls = open(&chain.calls);
+        let puts = open(&chain.puts);
 
-        for call in calls {
-            let d_dist = (call.delta.abs() - target_delta).abs();
-            if d_dist < call_d_dist {
-                call_d_dist = d_dist;
-                good_call = Some(call);
-            }
-        }
+        let payout = |price: f64| -> f64 {
+            let calls: f64 = calls
+                .iter()
+                .map(|(strike, oi)| (price - strike).max(0.0) * oi)
+                .sum();
+            let puts: f64 = puts
+                .iter()
+                .map(|(strike, oi)| (strike - price).max(0.0) * oi)
+                .sum();
+            calls + puts
+        };
 
-        info!("============{}============", &equity_symbol);
-        info!("Good Put: {good_put:?}");
-        info!("Good Call: {good_call:?}");
-        info!("============================");
+        calls
+            .iter()
+            .chain(puts.iter())
+            .map(|(strike, _)| (*strike, payout(*strike)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(f64::NAN, |(strike, _)| strike)
+    }
 
-        Ok(OptionResult {
-            symbol: equity_symbol,
-            good_call,
-            good_put,
+    /// Bull call spreads over every pair of calls struck `width` apart,
+    /// in chain order of the long call.
+    pub fn find_bull_call_spreads(chain: &OptionChain, width: f64) -> Vec<BullCallSpread> {
+        let calls: Vec<(f64, &Quote)> = chain
+            .calls
+            .iter()
+            .filter_map(|q| Some((q.strike()?, q)))
+            .collect();
+
+        calls
+            .iter()
+            .filter_map(|(strike, long_call)| {
+                let (_, short_call) = calls
+                    .iter()
+                    .find(|(s, _)| (s - strike - width).abs() < STRIKE_EPSILON)?;
+                Some(BullCallSpread {
+                    long_call: (*long_call).clone(),
+                    short_call: (*short_call).clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Iron condors shorting a put below and a call above `underlying`, each
+    /// protected by a long option `wing_width` further out. Keeps the ones
+    /// opening for at least `min_credit` with a defined loss, best credit to
+    /// width first.
+    pub fn find_iron_condors(
+        chain: &OptionChain,
+        underlying: f64,
+        wing_width: f64,
+        min_credit: f64,
+    ) -> Vec<IronCondor> {
+        let put_wings = vertical_wings(&chain.puts, underlying, -wing_width);
+        let call_wings = vertical_wings(&chain.calls, underlying, wing_width);
+
+        let mut condors: Vec<IronCondor> = put_wings
+            .iter()
+            .flat_map(|put| call_wings.iter().map(move |call| (put, call)))
+            .map(
+                |((short_put, long_put), (short_call, long_call))| IronCondor {
+                    short_put: (*short_put).clone(),
+                    long_put: (*long_put).clone(),
+                    short_call: (*short_call).clone(),
+                    long_call: (*long_call).clone(),
+                },
+            )
+            .filter(|condor| condor.net_credit() >= min_credit && condor.max_loss() > 0.0)
+            .collect();
+        condors.sort_by(|a, b| b.credit_to_width().total_cmp(&a.credit_to_width()));
+
+        condors
+    }
+
+    /// Strikes whose volume is at least `vol_oi_threshold` times their open
+    /// interest (at least 1), highest ratio first.
+    pub fn unusual_options_activity(
+        chain: &OptionChain,
+        vol_oi_threshold: f64,
+    ) -> Vec<UnusualActivity> {
+        let sides = [
+            (&chain.calls, OptionType::Call),
+            (&chain.puts, OptionType::Put),
+        ];
+        let mut unusual: Vec<UnusualActivity> = sides
+            .into_iter()
+            .flat_map(|(quotes, side)| quotes.iter().map(move |q| (q, side.clone())))
+            .filter_map(|(q, side)| {
+                let open_interest = q.open_interest.unwrap_or(0);
+                let ratio = q.volume as f64 / open_interest.max(1) as f64;
+                if ratio < vol_oi_threshold {
+                    return None;
+                }
+                Some(UnusualActivity {
+                    symbol: q.instrument.symbol.parse().ok()?,
+                    side,
+                    volume: q.volume,
+                    open_interest,
+                    ratio,
+                    bid: q.bid,
+                    ask: q.ask,
+                })
+            })
+            .collect();
+        unusual.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+
+        unusual
+    }
+
+    /// The `n` calls and `n` puts with the most open interest, highest first.
+    /// Missing open interest counts as 0.
+    pub fn find_highest_open_interest_strikes(
+        chain: &OptionChain,
+        n: usize,
+    ) -> (Vec<Quote>, Vec<Quote>) {
+        let oi = |q: &Quote| q.open_interest.unwrap_or(0);
+
+        (
+            top_quotes(&chain.calls, n, oi),
+            top_quotes(&chain.puts, n, oi),
+        )
+    }
+
+    /// The `n` calls and `n` puts with the most volume, highest first.
+    pub fn find_highest_volume_strikes(chain: &OptionChain, n: usize) -> (Vec<Quote>, Vec<Quote>) {
+        let volume = |q: &Quote| q.volume;
+
+        (
+            top_quotes(&chain.calls, n, volume),
+            top_quotes(&chain.puts, n, volume),
+        )
+    }
+
+    /// Expected move until expiration, priced as the ATM straddle: the mid of
+    /// the call plus the mid of the put at the strike nearest `underlying_price`.
+    /// Returns the move in dollars and as a percentage of the underlying,
+    /// NaN without both a call and a put at that strike.
+    pub fn expected_move(chain: &OptionChain, underlying_price: f64) -> (f64, f64) {
+        let Some(atm) = chain.atm_strike(underlying_price) else {
+            return (f64::NAN, f64::NAN);
+        };
+        let mid_at_atm = |quotes: &[Quote]| -> Option<f64> {
+            quotes
+                .iter()
+                .find(|q| q.strike() == Some(atm))
+                .map(Quote::mid_price)
+        };
+
+        match (mid_at_atm(&chain.calls), mid_at_atm(&chain.puts)) {
+            (Some(call), Some(put)) => {
+                let dollars = call + put;
+                (dollars, dollars / underlying_price * 100.0)
+            }
+            _ => (f64::NAN, f64::NAN),
+        }
+    }
+
+    /// IV of the 25 delta put over the IV of the 25 delta call, the out of the money
+    /// contracts of `chain` on each side of `atm_strike` with their absolute delta in
+    /// `greeks_map` closest to 0.25. NaN without greeks for either side.
+    pub fn skew_ratio(
+        chain: &OptionChain,
+        greeks_map: &HashMap<String, OptionGreeks>,
+        atm_strike: f64,
+    ) -> f64 {
+        let wing_iv = |quotes: &[Quote], out_of_the_money: &dyn Fn(f64) -> bool| {
+            quotes
+                .iter()
+                .filter(|q| q.strike().is_some_and(out_of_the_money))
+                .filter_map(|q| Some(&greeks_map.get(&q.instrument.symbol)?.greeks))
+                .min_by(|a, b| {
+                    (a.delta.abs() - SKEW_DELTA)
+                        .abs()
+                        .total_cmp(&(b.delta.abs() - SKEW_DELTA).abs())
+                })
+                .map(|g| g.implied_volatility)
+        };
+        let put_iv = wing_iv(&chain.puts, &|strike| strike < atm_strike);
+        let call_iv = wing_iv(&chain.calls, &|strike| strike > atm_strike);
+
+        match (put_iv, call_iv) {
+            (Some(put), Some(call)) if call > 0.0 => put / call,
+            _ => f64::NAN,
+        }
+    }
+
+    /// Report on the `strikes` nearest out of the money strikes on each side,
+    /// with greeks fetched for each of them. With a `delta_range`, only the strikes
+    /// with an absolute delta within it are kept; the chain-wide figures still
+    /// cover the whole chain.
+    pub async fn analyze_option(
+        &self,
+        equity_symbol: String,
+        expiration: NaiveDate,
+        strikes: usize,
+        delta_range: Option<(f64, f64)>,
+    ) -> Result<AnalysisReport, PublicError> {
+        let instrument = Instrument::equity(&equity_symbol);
+        let quotes = self.public.get_quotes(vec![instrument.clone()]).await?;
+        let underlying_price = quotes.first().map(|q| q.last);
+        if underlying_price.is_none() {
+            warn!("No quote returned for {equity_symbol}, centering on max pain");
+        }
+        let chain = self
+            .public
+            .get_option_chain(instrument, expiration.to_string())
+            .await?;
+
+        let center = underlying_price.unwrap_or_else(|| Self::find_max_pain(&chain));
+        let (calls, puts) = strikes_around_atm(&chain, center, strikes);
+        let symbols: Vec<OsiSymbol> = calls
+            .iter()
+            .chain(puts.iter())
+            .filter_map(|q| q.instrument.symbol.parse().ok())
+            .collect();
+        let greeks = self.greeks_with_progress(&symbols).await?;
+
+        let mut report = AnalysisReport::new(
+            equity_symbol,
+            expiration,
+            underlying_price,
+            &chain,
+            strikes,
+            &greeks,
+            &self.config,
+        );
+        if let Some((min_delta, max_delta)) = delta_range {
+            report = report.retain_contracts(&chain.filter_by_delta(&greeks, min_delta, max_delta));
+        }
+        match self.public.can_trade_spreads().await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("Options level of the account can't trade spreads, skipping iron condors");
+                report.iron_condors.clear();
+            }
+            Err(e) => warn!("Cannot check the options level of the account: {e}"),
+        }
+        report.portfolio_greeks = self
+            .position_greeks(&report.symbol)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(
+                    "Cannot get the greeks of the {} positions: {e}",
+                    report.symbol
+                );
+                None
+            });
+        report.delta_hedge = report
+            .portfolio_greeks
+            .as_ref()
+            .zip(underlying_price)
+            .map(|(greeks, price)| suggest_delta_hedge(greeks, &report.symbol, price));
+        #[cfg(feature = "news")]
+        {
+            report.news = match self.public.get_news(&report.symbol, REPORT_NEWS).await {
+                Ok(news) => news,
+                Err(PublicError::MissingNewsConfig) => vec![],
+                Err(e) => {
+                    warn!("Cannot get the news of {}: {e}", report.symbol);
+                    vec![]
+                }
+            };
+        }
+        let Some(iv) = report.atm_iv() else {
+            return Ok(report);
+        };
+        let mut history = IvHistory::load(&report.symbol).unwrap_or_else(|e| {
+            warn!("Failed to load IV history of {}: {e}", report.symbol);
+            IvHistory::new(&report.symbol)
+        });
+        let report = report.with_iv_history(&history);
+        history.record(Local::now().date_naive(), iv);
+        if let Err(e) = history.save() {
+            warn!("Failed to save IV history of {}: {e}", report.symbol);
+        }
+
+        Ok(report)
+    }
+
+    /// `analyze_option` of every symbol in the `options` of `config`, or of the ticker
+    /// of its OSI contracts, a few at a time, sorted by symbol. Symbols that fail to analyze are logged and left out.
+    pub async fn analyze_portfolio(
+        &self,
+        config: &Config,
+        expiration: NaiveDate,
+        strikes: usize,
+        delta_range: Option<(f64, f64)>,
+    ) -> Vec<AnalysisReport> {
+        // Contracts of the list are analyzed through their underlying
+        let mut symbols: Vec<String> = config
+            .options()
+            .into_iter()
+            .map(|o| OsiSymbol::parse(&o.symbol).map_or(o.symbol, |parts| parts.ticker))
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+
+        let permits = Semaphore::new(MAX_CONCURRENT_ANALYSES);
+        let results = join_all(symbols.into_iter().map(|symbol| {
+            let permits = &permits;
+            async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                let report = self
+                    .analyze_option(symbol.clone(), expiration, strikes, delta_range)
+                    .await;
+                (symbol, report)
+            }
+        }))
+        .await;
+
+        results
+            .into_iter()
+            .filter_map(|(symbol, report)| match report {
+                Ok(report) => Some(report),
+                Err(e) => {
+                    error!("Cannot analyze {symbol}: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Greeks of `symbols`, counted on a progress bar with the `progress` feature.
+    async fn greeks_with_progress(
+        &self,
+        symbols: &[OsiSymbol],
+    ) -> Result<HashMap<String, OptionGreeks>, PublicError> {
+        #[cfg(feature = "progress")]
+        {
+            use std::io::IsTerminal;
+
+            let bar = greeks_progress_bar(symbols.len() as u64, std::io::stdout().is_terminal());
+            let greeks = self
+                .public
+                .get_option_greeks_batch_with_progress(symbols, |n| bar.inc(n as u64))
+                .await;
+            bar.finish_and_clear();
+            greeks
+        }
+        #[cfg(not(feature = "progress"))]
+        self.public.get_option_greeks_batch(symbols).await
+    }
+
+    /// Prints the volume profile of `report`, one bar per traded strike.
+    pub fn print_volume_histogram(report: &AnalysisReport) {
+        let max = report.volume_profile.values().copied().max().unwrap_or(0);
+        if max == 0 {
+            println!("No volume traded");
+            return;
+        }
+        for (strike, volume) in report.volume_profile.iter().filter(|(_, v)| **v > 0) {
+            let width = (*volume as f64 / max as f64 * HISTOGRAM_WIDTH as f64).ceil() as usize;
+            println!(
+                "{:>9.2} {:<HISTOGRAM_WIDTH$} {volume}",
+                strike.0,
+                "#".repeat(width)
+            );
+        }
+    }
+
+    /// Summaries of `expirations` of `symbol`, earliest first, every listed expiration
+    /// when empty. Chains are fetched concurrently, unparsable dates are skipped.
+    pub async fn compare_expirations(
+        &self,
+        symbol: &str,
+        expirations: &[String],
+        underlying_price: f64,
+    ) -> Result<Vec<ExpirationSummary>, PublicError> {
+        let instrument = Instrument::equity(symbol);
+        let expirations = if expirations.is_empty() {
+            self.public
+                .get_option_expirations(instrument.clone())
+                .await?
+        } else {
+            expirations.to_vec()
+        };
+        let expirations: Vec<NaiveDate> = expirations
+            .iter()
+            .filter_map(|e| match e.parse() {
+                Ok(date) => Some(date),
+                Err(err) => {
+                    warn!("Skipping expiration {e}: {err}");
+                    None
+                }
+            })
+            .collect();
+
+        let summaries = join_all(expirations.into_iter().map(|expiration| {
+            self.summarize_expiration(instrument.clone(), expiration, underlying_price)
+        }))
+        .await;
+        let mut summaries = summaries
+            .into_iter()
+            .collect::<Result<Vec<ExpirationSummary>, PublicError>>()?;
+        summaries.sort_by_key(|s| s.expiration);
+
+        Ok(summaries)
+    }
+
+    async fn summarize_expiration(
+        &self,
+        instrument: Instrument,
+        expiration: NaiveDate,
+        underlying_price: f64,
+    ) -> Result<ExpirationSummary, PublicError> {
+        let chain = self
+            .public
+            .get_option_chain(instrument.clone(), expiration.to_string())
+            .await?;
+        let (calls, puts) = strikes_around_atm(&chain, underlying_price, 1);
+        let symbols: Vec<OsiSymbol> = calls
+            .iter()
+            .chain(puts.iter())
+            .filter_map(|q| q.instrument.symbol.parse().ok())
+            .collect();
+        let greeks = self.public.get_option_greeks_batch(&symbols).await?;
+        let report = AnalysisReport::new(
+            instrument.symbol,
+            expiration,
+            Some(underlying_price),
+            &chain,
+            1,
+            &greeks,
+            &self.config,
+        );
+
+        Ok(ExpirationSummary {
+            expiration,
+            atm_iv: report.atm_iv().unwrap_or(f64::NAN),
+            expected_move_pct: report.expected_move_pct,
+            put_call_ratio: report.put_call_ratio,
+            total_oi: report.total_oi,
         })
     }
 
+    /// Covered calls expiring on `expiration` for the equity positions holding at least
+    /// one contract worth of shares, best annualized yield first. Calls are picked out
+    /// of the money, with the delta closest to `delta_target`.
+    pub async fn find_covered_calls(
+        &self,
+        equity_positions: &[Position],
+        expiration: &str,
+        delta_target: f64,
+    ) -> Result<Vec<CoveredCallSuggestion>, PublicError> {
+        let suggestions = join_all(
+            equity_positions
+                .iter()
+                .filter(|p| p.instrument.instrument_type == InstrumentType::Equity)
+                .map(|p| self.covered_call(p, expiration, delta_target)),
+        )
+        .await;
+        let mut suggestions: Vec<CoveredCallSuggestion> = suggestions
+            .into_iter()
+            .collect::<Result<Vec<_>, PublicError>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        suggestions.sort_by(|a, b| b.annualized_yield.total_cmp(&a.annualized_yield));
+
+        Ok(suggestions)
+    }
+
+    /// None when `position` can't cover a contract, is missing its price,
+    /// or no call of the chain has greeks.
+    async fn covered_call(
+        &self,
+        position: &Position,
+        expiration: &str,
+        delta_target: f64,
+    ) -> Result<Option<CoveredCallSuggestion>, PublicError> {
+        let ticker = &position.instrument.symbol;
+        let shares_held = position.quantity.parse::<f64>().unwrap_or(0.0) as i64;
+        if (shares_held as f64) < CONTRACT_SIZE {
+            debug!("Not enough {ticker} shares to cover a call: {shares_held}");
+            return Ok(None);
+        }
+        let Some(price) = position
+            .last_price
+            .as_ref()
+            .and_then(|p| p.last_price.parse::<f64>().ok())
+        else {
+            warn!("No last price for {ticker}, skipping covered calls");
+            return Ok(None);
+        };
+
+        let chain = self
+            .public
+            .get_option_chain(position.instrument.clone(), expiration.to_string())
+            .await?;
+        let Some(call) = self
+            .closest_with_greeks(&chain.calls_above(price), delta_target)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mid_price = (call.bid + call.ask) / 2.0;
+        let Some(annualized_yield) =
+            annualized_yield(mid_price, price, call.symbol.parts().expiration)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(CoveredCallSuggestion {
+            ticker: ticker.clone(),
+            shares_held,
+            recommended_strike: call.strike,
+            mid_price,
+            annualized_yield,
+        }))
+    }
+
+    /// Cash-secured puts, one contract each, expiring on `expiration` for `symbols`,
+    /// best expected value first, then best annualized yield. Puts are picked struck
+    /// at most `max_strike`, with the absolute delta closest to `delta_target`.
+    pub async fn find_cash_secured_puts(
+        &self,
+        symbols: &[String],
+        expiration: &str,
+        delta_target: f64,
+        max_strike: f64,
+    ) -> Result<Vec<CspSuggestion>, PublicError> {
+        let suggestions = join_all(
+            symbols
+                .iter()
+                .map(|s| self.cash_secured_put(s, expiration, delta_target, max_strike)),
+        )
+        .await;
+        let mut suggestions: Vec<CspSuggestion> = suggestions
+            .into_iter()
+            .collect::<Result<Vec<_>, PublicError>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        // Without an underlying price the expected value is NaN, rank those last
+        suggestions.sort_by(|a, b| {
+            a.expected_value
+                .is_nan()
+                .cmp(&b.expected_value.is_nan())
+                .then(b.expected_value.total_cmp(&a.expected_value))
+                .then(b.annualized_yield.total_cmp(&a.annualized_yield))
+        });
+
+        Ok(suggestions)
+    }
+
+    /// None when no put under `max_strike` has greeks.
+    async fn cash_secured_put(
+        &self,
+        symbol: &str,
+        expiration: &str,
+        delta_target: f64,
+        max_strike: f64,
+    ) -> Result<Option<CspSuggestion>, PublicError> {
+        let instrument = Instrument::equity(symbol);
+        let quotes = self.public.get_quotes(vec![instrument.clone()]).await?;
+        let underlying_price = quotes.first().map(|q| q.last);
+        let chain = self
+            .public
+            .get_option_chain(instrument, expiration.to_string())
+            .await?;
+        let puts: Vec<&Quote> = chain
+            .puts
+            .iter()
+            .filter(|q| q.strike().is_some_and(|s| s <= max_strike))
+            .collect();
+        let Some(put) = self.closest_with_greeks(&puts, delta_target).await? else {
+            return Ok(None);
+        };
+        let mid_price = (put.bid + put.ask) / 2.0;
+        let Some(annualized_yield) =
+            annualized_yield(mid_price, put.strike, put.symbol.parts().expiration)
+        else {
+            return Ok(None);
+        };
+        let quote = puts
+            .iter()
+            .find(|q| q.instrument.symbol == put.symbol.as_str());
+        let expected_value = match (quote, underlying_price, &put.greeks) {
+            (Some(quote), Some(price), Some(greeks)) => {
+                short_expected_value(quote, price, greeks, self.config.risk_free_rate)
+            }
+            _ => f64::NAN,
+        };
+
+        Ok(Some(CspSuggestion {
+            ticker: symbol.to_string(),
+            strike: put.strike,
+            mid_price,
+            capital_required: put.strike * CONTRACT_SIZE,
+            annualized_yield,
+            delta: put.greeks.as_ref().map_or(f64::NAN, |g| g.greeks.delta),
+            expected_value,
+        }))
+    }
+
+    /// Contract of `quotes` with the absolute delta closest to `delta_target`,
+    /// after fetching their greeks.
+    async fn closest_with_greeks(
+        &self,
+        quotes: &[&Quote],
+        delta_target: f64,
+    ) -> Result<Option<OptionRow>, PublicError> {
+        let symbols: Vec<OsiSymbol> = quotes
+            .iter()
+            .filter_map(|q| q.instrument.symbol.parse().ok())
+            .collect();
+        let greeks = self.public.get_option_greeks_batch(&symbols).await?;
+        let rows: Vec<OptionRow> = quotes
+            .iter()
+            .filter_map(|q| OptionRow::new(q, greeks.get(&q.instrument.symbol).cloned()))
+            .collect();
+
+        Ok(AnalysisReport::closest_to_delta(&rows, delta_target).cloned())
+    }
+
+    /// Greeks of the account's option positions on `ticker`, None without positions.
+    async fn position_greeks(&self, ticker: &str) -> Result<Option<PortfolioGreeks>, PublicError> {
+        let positions = self.public.get_positions().await?;
+        let mut options: Vec<OptionPosition> = positions
+            .iter()
+            .filter(|p| p.is_option())
+            .filter_map(|p| OptionPosition::new(p).ok())
+            .filter(|o| o.ticker == ticker)
+            .collect();
+        if options.is_empty() {
+            return Ok(None);
+        }
+        fetch_position_greeks(&self.public, &mut options).await?;
+
+        Ok(Some(compute_portfolio_greeks(&options)))
+    }
+
     pub async fn analyze_options(
         &self,
         equities: Vec<String>,
@@ -475,18 +2793,120 @@ impl OptionsAnalyze {
 
         all_puts.sort();
         println!();
-        all_puts
-            .iter()
-            .for_each(|put| println!("{}", put.to_string()));
+        all_puts.iter().for_each(|put| println!("{put}"));
 
         Ok(())
     }
 }
 
+impl Strategy for OptionsStopper {
+    fn name(&self) -> &str {
+        "options-stopper"
+    }
+
+    fn run(&self) -> BoxFuture<'_, Result<(), PublicError>> {
+        Box::pin(OptionsStopper::run(self))
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.config = config
+            .stopper()
+            .unwrap_or_default()
+            .with_option_configs(config.options());
+    }
+}
+
+impl Strategy for OptionsAnalyze {
+    fn name(&self) -> &str {
+        "options-analyze"
+    }
+
+    fn run(&self) -> BoxFuture<'_, Result<(), PublicError>> {
+        Box::pin(async move {
+            let Some((equities, expiration)) = &self.watchlist else {
+                warn!("No watchlist to analyze");
+                return Ok(());
+            };
+            self.analyze_options(equities.clone(), expiration.clone())
+                .await
+        })
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.config = config.analysis().unwrap_or_default();
+        self.watchlist = config.watchlist();
+    }
+}
+
+/// Bar counting the greeks fetched, drawn on stderr to keep stdout for `--json`.
+/// Hidden unless `visible`, like when stdout isn't a terminal.
+#[cfg(feature = "progress")]
+fn greeks_progress_bar(len: u64, visible: bool) -> indicatif::ProgressBar {
+    use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+    if !visible {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stderr());
+    bar.set_style(
+        ProgressStyle::with_template("Fetching Greeks [{pos}/{len}]")
+            .expect("valid progress template"),
+    );
+
+    bar
+}
+
+/// Short and long legs of vertical spreads: short strikes on the `offset`
+/// side of the underlying, long strikes `offset` further out.
+fn vertical_wings(quotes: &[Quote], underlying: f64, offset: f64) -> Vec<(&Quote, &Quote)> {
+    let by_strike: Vec<(f64, &Quote)> = quotes
+        .iter()
+        .filter_map(|q| Some((q.strike()?, q)))
+        .collect();
+
+    by_strike
+        .iter()
+        .filter(|(strike, _)| (strike - underlying) * offset > 0.0)
+        .filter_map(|(strike, short)| {
+            let (_, long) = by_strike
+                .iter()
+                .find(|(s, _)| (s - strike - offset).abs() < STRIKE_EPSILON)?;
+            Some((*short, *long))
+        })
+        .collect()
+}
+
+/// The `strikes` calls right above and puts right below the ATM strike,
+/// each ordered from the nearest strike outwards.
+fn strikes_around_atm(
+    chain: &OptionChain,
+    underlying_price: f64,
+    strikes: usize,
+) -> (Vec<&Quote>, Vec<&Quote>) {
+    let Some(atm) = chain.atm_strike(underlying_price) else {
+        return (vec![], vec![]);
+    };
+    let by_distance = |quotes: &mut Vec<&Quote>| {
+        quotes.sort_by(|a, b| {
+            let a = (a.strike().unwrap_or_default() - atm).abs();
+            let b = (b.strike().unwrap_or_default() - atm).abs();
+            a.total_cmp(&b)
+        });
+        quotes.truncate(strikes);
+    };
+
+    let mut calls = chain.calls_above(atm);
+    let mut puts = chain.puts_below(atm);
+    by_distance(&mut calls);
+    by_distance(&mut puts);
+
+    (calls, puts)
+}
+
 fn _print_op_quote(q: &Quote, g: Option<&Greeks>) {
     let sym = q.instrument.symbol.clone();
-    let bid = q.bid.clone();
-    let ask = q.ask.clone();
+    let bid = q.bid;
+    let ask = q.ask;
     let vol = q.volume;
 
     info!("{sym}: {bid}/{ask} Volume: {vol} {g:?}");
@@ -495,20 +2915,1433 @@ fn _print_op_quote(q: &Quote, g: Option<&Greeks>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::public::AccountPortfolio;
+    use crate::signals::OrderHandler;
+    use chrono::Days;
+    use proptest::prelude::*;
+    use std::include_str;
+
+    const PORTFOLIO_WITH_OPTIONS: &str =
+        include_str!("fixtures/account_portfolio_with_options.json");
+    const OPTION_CHAIN: &str = include_str!("fixtures/option_chain.json");
+
+    fn option_positions() -> Vec<OptionPosition> {
+        let portfolio: AccountPortfolio = serde_json::from_str(PORTFOLIO_WITH_OPTIONS).unwrap();
+        portfolio
+            .positions
+            .iter()
+            .filter(|p| p.is_option())
+            .map(|p| OptionPosition::new(p).unwrap())
+            .collect()
+    }
 
     #[test]
-    fn test_parse_symbol_and_type_from_full_symbol_put() {
-        let option_symbol = "MU260417P00830000";
-        let (symbol, op_type) = parse_symbol_and_type_from_full_symbol(option_symbol);
-        assert_eq!(symbol, "MU".to_string());
-        assert_eq!(op_type, OptionType::Put);
+    fn test_option_position_from_osi_symbol() {
+        let positions = option_positions();
+        assert_eq!(positions.len(), 4);
+
+        let qcom = &positions[0];
+        assert_eq!(qcom.symbol, "QCOM260220P00138000");
+        assert_eq!(qcom.ticker, "QCOM");
+        assert_eq!(qcom.strike, 138.0);
+        assert_eq!(qcom.op_type, OptionType::Put);
+        assert_eq!(
+            qcom.expiration,
+            NaiveDate::from_ymd_opt(2026, 2, 20).unwrap()
+        );
+        assert!(matches!(qcom.side, OrderSide::Sell));
+    }
+
+    #[test]
+    fn test_position_summary() {
+        let mut qcom = option_positions().remove(0);
+        let summary = qcom.to_summary();
+        assert_eq!(summary.symbol, "QCOM260220P00138000");
+        assert_eq!(summary.option_type, OptionType::Put);
+        assert_eq!(summary.quantity, qcom.quantity);
+        assert_eq!(summary.gain_percent, qcom.gain_percent);
+        assert_eq!(summary.delta, None);
+
+        qcom.greeks = Some(OptionGreeks {
+            symbol: qcom.symbol.clone(),
+            greeks: Greeks {
+                delta: -0.3,
+                gamma: 0.02,
+                theta: -0.05,
+                vega: 0.1,
+                rho: -0.01,
+                implied_volatility: 0.42,
+            },
+        });
+        let summary = qcom.to_summary();
+        assert_eq!(summary.delta, Some(-0.3));
+        assert_eq!(summary.implied_volatility, Some(0.42));
+    }
+
+    fn with_greeks(
+        mut pos: OptionPosition,
+        side: OrderSide,
+        quantity: i64,
+        delta: f64,
+    ) -> OptionPosition {
+        pos.side = side;
+        pos.quantity = quantity;
+        pos.greeks = Some(OptionGreeks {
+            symbol: pos.symbol.clone(),
+            greeks: Greeks {
+                delta,
+                gamma: 0.02,
+                // -$0.05 a day, $0.10 per IV point
+                theta: -18.25,
+                vega: 10.0,
+                rho: 0.0,
+                implied_volatility: 0.4,
+            },
+        });
+        pos
+    }
+
+    #[test]
+    fn test_compute_portfolio_greeks() {
+        let pos = option_positions().remove(0);
+        let long_call = with_greeks(pos.clone(), OrderSide::Buy, 2, 0.5);
+        let short_put = with_greeks(pos.clone(), OrderSide::Sell, -1, -0.3);
+        let greeks = compute_portfolio_greeks(&[long_call.clone(), short_put.clone()]);
+        // 2 * 100 * 0.5 long, and -1 * 100 * -0.3 short
+        assert!((greeks.delta - 130.0).abs() < 1e-9);
+        assert!((greeks.gamma - 2.0).abs() < 1e-9);
+        // $5 a day and $10 per IV point over the 2 - 1 contracts
+        assert!((greeks.theta + 5.0).abs() < 1e-9);
+        assert!((greeks.vega - 10.0).abs() < 1e-9);
+        assert_eq!(greeks.net_premium, long_call.cost + short_put.cost);
+        // $1.40 for a 1% move of a $140 underlying, per share of delta
+        let dollar_delta = long_call
+            .greeks
+            .as_ref()
+            .unwrap()
+            .dollar_delta(140.0, 2, 100)
+            + short_put
+                .greeks
+                .as_ref()
+                .unwrap()
+                .dollar_delta(140.0, -1, 100);
+        assert!((greeks.dollar_delta(140.0) - 182.0).abs() < 1e-9);
+        assert!((greeks.dollar_delta(140.0) - dollar_delta).abs() < 1e-9);
+
+        // Shorts reported with a positive quantity are still negated
+        let short_put = with_greeks(pos.clone(), OrderSide::Sell, 1, -0.3);
+        assert!((compute_portfolio_greeks(&[short_put]).delta - 30.0).abs() < 1e-9);
+
+        let no_greeks = OptionPosition {
+            greeks: None,
+            ..pos
+        };
+        let greeks = compute_portfolio_greeks(std::slice::from_ref(&no_greeks));
+        assert_eq!(greeks.delta, 0.0);
+        assert_eq!(greeks.net_premium, no_greeks.cost);
+        assert_eq!(compute_portfolio_greeks(&[]), PortfolioGreeks::default());
+    }
+
+    #[test]
+    fn test_probability_of_profit() {
+        let expiring = |strike: f64, op_type: OptionType, side: OrderSide| {
+            let mut pos = with_greeks(option_positions().remove(0), side, 1, 0.5);
+            pos.strike = strike;
+            pos.op_type = op_type;
+            pos.expiration = Local::now().date_naive() + Days::new(30);
+            pos
+        };
+
+        let deep_itm_call = expiring(100.0, OptionType::Call, OrderSide::Buy);
+        assert!(deep_itm_call.probability_of_profit(140.0, 0.045).unwrap() > 0.9);
+        let far_otm_put = expiring(100.0, OptionType::Put, OrderSide::Buy);
+        let pop = far_otm_put.probability_of_profit(140.0, 0.045).unwrap();
+        assert!(pop < 0.1);
+        let short_put = expiring(100.0, OptionType::Put, OrderSide::Sell);
+        let short_pop = short_put.probability_of_profit(140.0, 0.045).unwrap();
+        assert!((short_pop - (1.0 - pop)).abs() < 1e-12);
+
+        let mut expired = expiring(100.0, OptionType::Call, OrderSide::Buy);
+        expired.expiration = Local::now().date_naive();
+        assert_eq!(expired.probability_of_profit(140.0, 0.045), None);
+        let no_greeks = OptionPosition {
+            greeks: None,
+            ..deep_itm_call
+        };
+        assert_eq!(no_greeks.probability_of_profit(140.0, 0.045), None);
+    }
+
+    #[test]
+    fn test_annualized_return() {
+        let mut short_put = with_greeks(option_positions().remove(0), OrderSide::Sell, -1, -0.3);
+        short_put.op_type = OptionType::Put;
+        short_put.strike = 100.0;
+        short_put.cost = -150.0;
+        short_put.expiration = Local::now().date_naive() + Days::new(45);
+        // $150 on $10,000 of collateral for 45 days
+        let expected = 150.0 / 10_000.0 / (45.0 / 365.0) * 100.0;
+        let annualized = short_put.annualized_return(95.0).unwrap();
+        assert!((annualized - expected).abs() < expected * 0.01);
+        assert!((annualized - 12.1667).abs() < 1e-3);
+
+        let short_call = OptionPosition {
+            op_type: OptionType::Call,
+            ..short_put.clone()
+        };
+        assert!((short_call.annualized_return(50.0).unwrap() - expected * 2.0).abs() < 1e-9);
+
+        let long_put = OptionPosition {
+            side: OrderSide::Buy,
+            ..short_put.clone()
+        };
+        assert_eq!(long_put.annualized_return(95.0), None);
+        let expired = OptionPosition {
+            expiration: Local::now().date_naive(),
+            ..short_put
+        };
+        assert_eq!(expired.annualized_return(95.0), None);
+    }
+
+    #[test]
+    fn test_max_loss_and_profit() {
+        let position = |side: OrderSide, op_type: OptionType, cost: f64| {
+            let mut pos = option_positions().remove(0);
+            pos.side = side;
+            pos.op_type = op_type;
+            pos.strike = 50.0;
+            pos.quantity = 2;
+            pos.cost = cost;
+            pos
+        };
+
+        let long_call = position(OrderSide::Buy, OptionType::Call, 300.0);
+        assert_eq!(long_call.max_loss(), 300.0);
+        assert_eq!(long_call.max_profit(), f64::INFINITY);
+
+        // Shares worth $10,000 at the strike
+        let long_put = position(OrderSide::Buy, OptionType::Put, 300.0);
+        assert_eq!(long_put.max_loss(), 300.0);
+        assert_eq!(long_put.max_profit(), 9_700.0);
+
+        let short_call = position(OrderSide::Sell, OptionType::Call, -250.0);
+        assert_eq!(short_call.max_profit(), 250.0);
+        assert_eq!(short_call.max_loss(), f64::INFINITY);
+
+        let mut short_put = position(OrderSide::Sell, OptionType::Put, -250.0);
+        assert_eq!(short_put.max_profit(), 250.0);
+        assert_eq!(short_put.max_loss(), 9_750.0);
+        short_put.quantity = -2;
+        assert_eq!(short_put.max_loss(), 9_750.0);
     }
 
     #[test]
-    fn test_parse_symbol_and_type_from_full_symbol_call() {
-        let option_symbol = "LITE260417C01410000";
-        let (symbol, op_type) = parse_symbol_and_type_from_full_symbol(option_symbol);
-        assert_eq!(symbol, "LITE".to_string());
+    fn test_suggest_delta_hedge() {
+        let greeks = |delta: f64| PortfolioGreeks {
+            delta,
+            ..PortfolioGreeks::default()
+        };
+
+        let hedge = suggest_delta_hedge(&greeks(130.4), "QCOM", 140.0);
+        assert_eq!(hedge.shares, -130);
+        assert_eq!(hedge.direction, OrderSide::Sell);
+        assert_eq!(hedge.notional, 18200.0);
+        assert_eq!(hedge.to_string(), "Hedge: sell 130 QCOM shares ($18200.00)");
+
+        let hedge = suggest_delta_hedge(&greeks(-45.6), "QCOM", 140.0);
+        assert_eq!(hedge.shares, 46);
+        assert_eq!(hedge.direction, OrderSide::Buy);
+
+        let hedge = suggest_delta_hedge(&greeks(0.2), "QCOM", 140.0);
+        assert_eq!(hedge.shares, 0);
+        assert_eq!(hedge.to_string(), "QCOM is delta neutral");
+    }
+
+    fn position_with_gain(gain_percent: f64) -> OptionPosition {
+        let mut pos = option_positions().remove(0);
+        pos.gain_percent = gain_percent;
+        pos
+    }
+
+    #[test]
+    fn test_should_exit_stop_loss() {
+        let config = StopperConfig::default();
+        assert_eq!(
+            config.should_exit(&position_with_gain(-200.0)),
+            ExitReason::StopLoss
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(-250.0)),
+            ExitReason::StopLoss
+        );
+    }
+
+    #[test]
+    fn test_should_exit_symbol_override() {
+        let config = StopperConfig {
+            symbol_overrides: HashMap::from([("QCOM".to_string(), -50.0)]),
+            ..StopperConfig::default()
+        };
+        assert_eq!(
+            config.should_exit(&position_with_gain(-60.0)),
+            ExitReason::StopLoss
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(-40.0)),
+            ExitReason::Hold
+        );
+    }
+
+    #[test]
+    fn test_should_exit_profit_take() {
+        let config = StopperConfig {
+            profit_take_threshold: Some(50.0),
+            ..StopperConfig::default()
+        };
+        assert_eq!(
+            config.should_exit(&position_with_gain(50.0)),
+            ExitReason::ProfitTake
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(49.9)),
+            ExitReason::Hold
+        );
+    }
+
+    #[test]
+    fn test_should_exit_time_based() {
+        let config = StopperConfig {
+            dte_floor: 3,
+            ..StopperConfig::default()
+        };
+        let today = Local::now().date_naive();
+
+        let mut pos = position_with_gain(10.0);
+        pos.expiration = today + Days::new(2);
+        assert_eq!(config.should_exit(&pos), ExitReason::TimeBased);
+
+        pos.expiration = today + Days::new(3);
+        assert_eq!(config.should_exit(&pos), ExitReason::Hold);
+
+        // Loss and profit exits take priority
+        pos.expiration = today;
+        pos.gain_percent = -300.0;
+        assert_eq!(config.should_exit(&pos), ExitReason::StopLoss);
+    }
+
+    #[test]
+    fn test_should_exit_option_config() {
+        let config = StopperConfig {
+            symbol_overrides: HashMap::from([("QCOM".to_string(), -50.0)]),
+            profit_take_threshold: Some(50.0),
+            ..StopperConfig::default()
+        }
+        .with_option_configs(vec![OptionConfig {
+            symbol: "QCOM".to_string(),
+            stop_loss_pct: Some(-80.0),
+            profit_take_pct: Some(30.0),
+            dte_exit: Some(5),
+            max_quantity: Some(1),
+        }]);
+        let today = Local::now().date_naive();
+        let position = |gain_percent: f64| -> OptionPosition {
+            let mut pos = position_with_gain(gain_percent);
+            pos.expiration = today + Days::new(30);
+            pos
+        };
+        assert_eq!(config.should_exit(&position(-60.0)), ExitReason::Hold);
+        assert_eq!(config.should_exit(&position(-80.0)), ExitReason::StopLoss);
+        assert_eq!(config.should_exit(&position(35.0)), ExitReason::ProfitTake);
+
+        let mut pos = position(10.0);
+        pos.expiration = today + Days::new(4);
+        assert_eq!(config.should_exit(&pos), ExitReason::TimeBased);
+
+        pos.quantity = -3;
+        assert_eq!(config.exit_quantity(&pos), 1);
+        assert_eq!(StopperConfig::default().exit_quantity(&pos), 3);
+    }
+
+    #[test]
+    fn test_should_exit_option_config_fallback() {
+        // A bare ticker entry keeps the [stopper] settings.
+        let config = StopperConfig {
+            profit_take_threshold: Some(50.0),
+            ..StopperConfig::default()
+        }
+        .with_option_configs(vec![OptionConfig {
+            symbol: "QCOM".to_string(),
+            ..OptionConfig::default()
+        }]);
+        assert_eq!(
+            config.should_exit(&position_with_gain(-200.0)),
+            ExitReason::StopLoss
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(40.0)),
+            ExitReason::Hold
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(50.0)),
+            ExitReason::ProfitTake
+        );
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_days_to_expiration_past_and_today() {
+        let today = date(2026, 2, 18);
+        assert_eq!(calendar_days_between(today, date(2026, 2, 13)), 0);
+        assert_eq!(trading_days_between(today, date(2026, 2, 13)), 0);
+        assert_eq!(calendar_days_between(today, today), 0);
+        assert_eq!(trading_days_between(today, today), 0);
+
+        // QCOM put in the fixture expired on 2026-02-20
+        assert_eq!(option_positions()[0].days_to_expiration(), 0);
+    }
+
+    #[test]
+    fn test_days_to_expiration_across_weekends() {
+        // Friday to the next Monday
+        let friday = date(2026, 2, 13);
+        assert_eq!(calendar_days_between(friday, date(2026, 2, 16)), 3);
+        assert_eq!(trading_days_between(friday, date(2026, 2, 16)), 1);
+
+        // Wednesday to the Friday two weeks out
+        let wednesday = date(2026, 2, 18);
+        assert_eq!(calendar_days_between(wednesday, date(2026, 3, 6)), 16);
+        assert_eq!(trading_days_between(wednesday, date(2026, 3, 6)), 12);
+
+        // Expiring on a Saturday only counts the Friday before
+        assert_eq!(trading_days_between(wednesday, date(2026, 2, 21)), 2);
+
+        let mut pos = position_with_gain(0.0);
+        pos.expiration = Local::now().date_naive() + Days::new(7);
+        assert_eq!(pos.days_to_expiration(), 7);
+        assert_eq!(pos.trading_days_to_expiration(), 5);
+    }
+
+    #[test]
+    fn test_pl_attribution() {
+        let greeks = |delta: f64, theta: f64, vega: f64| OptionGreeks {
+            symbol: "QCOM260220P00138000".to_string(),
+            greeks: Greeks {
+                delta,
+                gamma: 0.01,
+                theta,
+                vega,
+                rho: 0.01,
+                implied_volatility: 0.4,
+            },
+        };
+        // Annualized theta and vega per unit of IV, like the API: -0.02 and -0.04
+        // a day, 0.10 and 0.08 per IV point
+        let (entry, current) = (greeks(-0.3, -7.3, 10.0), greeks(-0.2, -14.6, 8.0));
+        // Short put sold at 1.50 ten days ago, now worth what the averaged greeks
+        // give for a $2 rally and IV down a point: 1.50 - 0.30 - 0.50 - 0.09
+        let mut pos = option_positions().remove(0);
+        pos.opened_at = Some(Local::now().date_naive() - Days::new(10));
+        (pos.cost, pos.unit_cost, pos.last_price) = (-150.0, 1.5, 0.61);
+        pos.gain_percent = 89.0 / 150.0 * 100.0;
+
+        let attribution = pos.compute_pl_attribution(&entry, &current, 2.0, -1.0);
+        assert!((attribution.time_decay_pct - 20.0).abs() < 1e-9);
+        assert!((attribution.directional_pct - 100.0 / 3.0).abs() < 1e-9);
+        assert!((attribution.iv_change_pct - 6.0).abs() < 1e-9);
+        // The components make up the whole gain
+        assert!((attribution.total_pct() / pos.gain_percent - 1.0).abs() < 1e-9);
+        assert_eq!(
+            attribution.to_string(),
+            "time decay +20.00%, directional +33.33%, IV +6.00%"
+        );
+
+        // Estimated from the current greeks, the underlying move is what's left
+        pos.greeks = Some(current);
+        let mut iv_history = IvHistory::new("QCOM");
+        iv_history.record(Local::now().date_naive() - Days::new(11), 0.41);
+        let estimate = pos.estimate_pl_attribution(&iv_history).unwrap();
+        assert!((estimate.time_decay_pct - 26.0 - 2.0 / 3.0).abs() < 1e-9);
+        assert!((estimate.iv_change_pct - 16.0 / 3.0).abs() < 1e-9);
+        assert!((estimate.total_pct() / pos.gain_percent - 1.0).abs() < 1e-9);
+
+        pos.greeks = None;
+        assert_eq!(pos.estimate_pl_attribution(&iv_history), None);
+    }
+
+    #[tokio::test]
+    async fn test_exit_positions_concurrently() {
+        use crate::public::mock::{mock_client, mock_server};
+        use serde_json::{Value, json};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+        use warp::Filter;
+
+        let quote_requests = Arc::new(AtomicUsize::new(0));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let requests = quote_requests.clone();
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                requests.fetch_add(1, AtomicOrdering::SeqCst);
+                let now = chrono::Utc::now().to_rfc3339();
+                let quotes: Vec<Value> = body["instruments"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|instrument| {
+                        json!({
+                            "instrument": instrument,
+                            "outcome": "SUCCESS",
+                            "last": "0.62",
+                            "lastTimestamp": now,
+                            "bid": "0.60",
+                            "bidTimestamp": now,
+                            "ask": "0.64",
+                            "askTimestamp": now,
+                            "volume": 10
+                        })
+                    })
+                    .collect();
+                warp::reply::json(&json!({ "quotes": quotes }))
+            });
+
+        let (current, max) = (in_flight.clone(), max_in_flight.clone());
+        let orders = warp::path!("userapigateway" / "trading" / "TEST" / "order")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(move |body: Value| {
+                let (current, max) = (current.clone(), max.clone());
+                async move {
+                    let now = current.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+                    max.fetch_max(now, AtomicOrdering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    current.fetch_sub(1, AtomicOrdering::SeqCst);
+                    Ok::<_, warp::Rejection>(warp::reply::json(
+                        &json!({ "orderId": body["orderId"] }),
+                    ))
+                }
+            });
+
+        let client = mock_client(mock_server(quotes.or(orders)).await);
+        let recorded = Arc::new(std::sync::Mutex::new(vec![]));
+        let stopper = OptionsStopper::new(
+            client.clone(),
+            StopperConfig {
+                default_loss_threshold: 1000.0,
+                execute: true,
+                ..StopperConfig::default()
+            },
+            vec![
+                Box::new(OrderHandler::new(client)),
+                Box::new(RecordingHandler(recorded.clone())),
+            ],
+        );
+        let positions = option_positions();
+        let decisions: Vec<(&OptionPosition, ExitReason)> = positions
+            .iter()
+            .map(|pos| (pos, stopper.config.should_exit(pos)))
+            .collect();
+
+        let signals = stopper.signals(&decisions).await.unwrap();
+        let placed = stopper.dispatch(&signals).await;
+
+        assert_eq!(signals.len(), 4);
+        assert_eq!(
+            placed,
+            positions
+                .iter()
+                .map(|p| p.symbol.clone())
+                .collect::<HashSet<_>>()
+        );
+        assert!(signals.iter().all(|s| matches!(s, Signal::Exit {
+            reason: ExitReason::StopLoss,
+            suggested_limit,
+            ..
+        } if *suggested_limit == 0.62)));
+        assert_eq!(recorded.lock().unwrap().len(), 4);
+        assert_eq!(quote_requests.load(AtomicOrdering::SeqCst), 1);
+        assert!(max_in_flight.load(AtomicOrdering::SeqCst) > 1);
+    }
+
+    /// Handler keeping every signal it is given.
+    struct RecordingHandler(std::sync::Arc<std::sync::Mutex<Vec<Signal>>>);
+
+    impl SignalHandler for RecordingHandler {
+        fn handle<'a>(&'a self, signal: &'a Signal) -> BoxFuture<'a, Result<(), PublicError>> {
+            self.0.lock().unwrap().push(signal.clone());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exit_summary() {
+        use crate::public::mock::{mock_client, mock_server};
+        use warp::Filter;
+
+        let mut stopped = position_with_gain(-250.0);
+        stopped.gain_value = -150.0;
+        let mut stopped_too = position_with_gain(-300.0);
+        (stopped_too.symbol, stopped_too.gain_value) = ("QCOM260220P00130000".to_string(), -200.0);
+        let mut not_placed = position_with_gain(-400.0);
+        not_placed.symbol = "QCOM260220P00120000".to_string();
+        let held = position_with_gain(8.58);
+        let config = StopperConfig {
+            runs_dir: Some(std::env::temp_dir().join(format!("runs_{}", uuid::Uuid::new_v4()))),
+            ..StopperConfig::default()
+        };
+        let decisions: Vec<(&OptionPosition, ExitReason)> =
+            [&stopped, &stopped_too, &not_placed, &held]
+                .into_iter()
+                .map(|pos| (pos, config.should_exit(pos)))
+                .collect();
+        let placed = HashSet::from([stopped.symbol.clone(), stopped_too.symbol.clone()]);
+
+        let summary = ExitSummary::new(&decisions, &placed);
+        assert_eq!(summary.total_positions, 4);
+        assert_eq!(summary.positions_exited, 2);
+        assert_eq!(summary.exits_not_placed, 1);
+        assert_eq!(summary.total_gain_value_closed, -350.0);
+        assert_eq!(
+            summary.exit_reasons,
+            HashMap::from([("stop loss".to_string(), 2)])
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Exited 2 of 4 positions, closing $-350.00 of gains, 2 on stop loss, 1 exits not placed"
+        );
+
+        // Nothing is exited without placed orders, like in a dry run
+        let dry_run = ExitSummary::new(&decisions, &HashSet::new());
+        assert_eq!((dry_run.positions_exited, dry_run.exits_not_placed), (0, 3));
+        assert_eq!(dry_run.total_gain_value_closed, 0.0);
+
+        let client = mock_client(mock_server(warp::any().map(warp::reply)).await);
+        let stopper = OptionsStopper::new(client, config, vec![]);
+        assert_eq!(stopper.last_run_summary(), None);
+        stopper.record_summary(summary.clone());
+        assert_eq!(stopper.last_run_summary(), Some(summary));
+
+        let dir = stopper.config.runs_dir.as_ref().unwrap();
+        let files: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|f| f.unwrap())
+            .collect();
+        assert_eq!(files.len(), 1);
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(files[0].path()).unwrap()).unwrap();
+        assert_eq!(written["positions_exited"], 2);
+        assert_eq!(written["exit_reasons"]["stop loss"], 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_dry_run_exit() {
+        use crate::public::mock::{mock_client, mock_server};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+        use warp::Filter;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let any = warp::any().map(move || {
+            counter.fetch_add(1, AtomicOrdering::SeqCst);
+            warp::reply()
+        });
+        let client = mock_client(mock_server(any).await);
+        let recorded = Arc::new(std::sync::Mutex::new(vec![]));
+        let stopper = OptionsStopper::new(
+            client.clone(),
+            StopperConfig {
+                dry_run_exit: true,
+                execute: true,
+                ..StopperConfig::default()
+            },
+            vec![
+                Box::new(OrderHandler::new(client.clone())),
+                Box::new(RecordingHandler(recorded.clone())),
+            ],
+        );
+        let signal = Signal::Exit {
+            symbol: "QCOM260220P00138000".to_string(),
+            reason: ExitReason::StopLoss,
+            suggested_limit: 0.62,
+            side: OrderSide::Buy,
+            quantity: 1,
+            gain_percent: -212.5,
+            bid: 0.6,
+            ask: 0.64,
+        };
+
+        let placed = stopper.dispatch(std::slice::from_ref(&signal)).await;
+
+        assert_eq!(*recorded.lock().unwrap(), vec![signal.clone()]);
+        assert_eq!(requests.load(AtomicOrdering::SeqCst), 0);
+        assert!(placed.is_empty());
+
+        // Nor are orders placed without execute
+        let stopper = OptionsStopper::new(
+            client.clone(),
+            StopperConfig::default(),
+            vec![Box::new(OrderHandler::new(client))],
+        );
+        stopper.dispatch(std::slice::from_ref(&signal)).await;
+        assert_eq!(requests.load(AtomicOrdering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_should_exit_hold() {
+        let config = StopperConfig::default();
+        assert_eq!(
+            config.should_exit(&position_with_gain(8.58)),
+            ExitReason::Hold
+        );
+        assert_eq!(
+            config.should_exit(&position_with_gain(500.0)),
+            ExitReason::Hold
+        );
+    }
+
+    #[test]
+    fn test_find_max_pain() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let max_pain = OptionsAnalyze::find_max_pain(&chain);
+        assert!((45.0..=55.0).contains(&max_pain), "max pain {max_pain}");
+        assert_eq!(max_pain, 50.0);
+
+        let empty = OptionChain {
+            base_symbol: "LMND".to_string(),
+            calls: vec![],
+            puts: vec![],
+        };
+        assert!(OptionsAnalyze::find_max_pain(&empty).is_nan());
+    }
+
+    #[test]
+    fn test_unusual_options_activity() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let unusual = OptionsAnalyze::unusual_options_activity(&chain, 1.2);
+
+        let found: Vec<(&str, OptionType, u64, u64)> = unusual
+            .iter()
+            .map(|u| (u.symbol.as_str(), u.side.clone(), u.volume, u.open_interest))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("LMND251219C00095000", OptionType::Call, 132, 40),
+                ("LMND251219P00024000", OptionType::Put, 20, 12),
+                ("LMND251219C00100000", OptionType::Call, 195, 149),
+            ]
+        );
+        assert_eq!(unusual[0].ratio, 3.3);
+        assert_eq!((unusual[0].bid, unusual[0].ask), (2.15, 2.75));
+
+        assert!(OptionsAnalyze::unusual_options_activity(&chain, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_find_highest_open_interest_strikes() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let symbols = |quotes: &[Quote]| -> Vec<String> {
+            quotes.iter().map(|q| q.instrument.symbol.clone()).collect()
+        };
+
+        let (calls, puts) = OptionsAnalyze::find_highest_open_interest_strikes(&chain, 2);
+        assert_eq!(
+            symbols(&calls),
+            vec!["LMND251219C00060000", "LMND251219C00075000"]
+        );
+        assert_eq!(
+            symbols(&puts),
+            vec!["LMND251219P00037000", "LMND251219P00032000"]
+        );
+        assert_eq!(puts[1].open_interest, Some(3354));
+
+        let (calls, puts) = OptionsAnalyze::find_highest_volume_strikes(&chain, 2);
+        assert_eq!(
+            symbols(&calls),
+            vec!["LMND251219C00100000", "LMND251219C00080000"]
+        );
+        assert_eq!(
+            symbols(&puts),
+            vec!["LMND251219P00065000", "LMND251219P00032000"]
+        );
+        assert_eq!(puts[0].volume, 366);
+
+        let (calls, puts) = OptionsAnalyze::find_highest_open_interest_strikes(&chain, 1_000);
+        assert_eq!(
+            (calls.len(), puts.len()),
+            (chain.calls.len(), chain.puts.len())
+        );
+    }
+
+    #[test]
+    fn test_find_bull_call_spreads() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let spreads = OptionsAnalyze::find_bull_call_spreads(&chain, 5.0);
+        assert_eq!(spreads.len(), 47);
+        assert!(spreads.iter().all(|s| s.width() == 5.0));
+
+        // 95C at 2.75, 100C at 1.60
+        let spread = spreads
+            .iter()
+            .find(|s| s.long_call.instrument.symbol == "LMND251219C00095000")
+            .unwrap();
+        assert_eq!(spread.short_call.instrument.symbol, "LMND251219C00100000");
+        assert!((spread.net_debit() - 1.15).abs() < 1e-9);
+        assert!((spread.max_profit() - 3.85).abs() < 1e-9);
+        assert!((spread.break_even() - 96.15).abs() < 1e-9);
+
+        assert!(OptionsAnalyze::find_bull_call_spreads(&chain, 7.5).is_empty());
+    }
+
+    #[test]
+    fn test_find_iron_condors() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let condors = OptionsAnalyze::find_iron_condors(&chain, 40.3, 5.0, 0.25);
+        assert_eq!(condors.len(), 259);
+        assert!(condors.iter().all(|c| c.width() == 5.0
+            && c.net_credit() >= 0.25
+            && c.max_loss() > 0.0
+            && c.short_put.strike().unwrap() < 40.3
+            && c.short_call.strike().unwrap() > 40.3));
+        assert!(
+            condors
+                .windows(2)
+                .all(|w| w[0].credit_to_width() >= w[1].credit_to_width())
+        );
+
+        // 29P at 0.05 over 24P at 0.10, 50C at 25.70 over 55C at 22.00
+        let best = &condors[0];
+        assert_eq!(best.long_put.instrument.symbol, "LMND251219P00024000");
+        assert_eq!(best.short_put.instrument.symbol, "LMND251219P00029000");
+        assert_eq!(best.short_call.instrument.symbol, "LMND251219C00050000");
+        assert_eq!(best.long_call.instrument.symbol, "LMND251219C00055000");
+        assert!((best.net_credit() - 3.65).abs() < 1e-9);
+
+        assert!(OptionsAnalyze::find_iron_condors(&chain, 40.3, 5.0, 4.0).is_empty());
+    }
+
+    #[test]
+    fn test_expected_move() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        // ATM strike 40: call 35.20/38.00, put 0.00/0.45
+        let (dollars, pct) = OptionsAnalyze::expected_move(&chain, 40.3);
+        assert!((dollars - 36.825).abs() < 1e-9);
+        assert!((pct - 91.377171).abs() < 1e-6);
+
+        let calls_only = OptionChain {
+            puts: vec![],
+            ..chain
+        };
+        let (dollars, pct) = OptionsAnalyze::expected_move(&calls_only, 40.3);
+        assert!(dollars.is_nan() && pct.is_nan());
+    }
+
+    #[tokio::test]
+    async fn test_compare_expirations() {
+        use crate::public::mock::{mock_client, mock_greeks, mock_server};
+        use serde_json::json;
+        use warp::Filter;
+
+        let chains = warp::path!("userapigateway" / "marketdata" / "TEST" / "option-chain")
+            .and(warp::post())
+            .map(|| warp::reply::with_header(OPTION_CHAIN, "content-type", "application/json"));
+        let expirations = warp::path!(
+            "userapigateway" / "marketdata" / "TEST" / "option-expirations"
+        )
+        .map(|| {
+            warp::reply::json(&json!({
+                "baseSymbol": "LMND",
+                "expirations": ["2026-03-20", "2026-01-16", "2026-02-20"]
+            }))
+        });
+        let greeks = mock_greeks(|_| 0.5, 0.6);
+        let client = mock_client(mock_server(chains.or(expirations).or(greeks)).await);
+        let analyzer = OptionsAnalyze::new(client);
+
+        let summaries = analyzer
+            .compare_expirations(
+                "LMND",
+                &[
+                    "2026-02-20".to_string(),
+                    "2026-01-16".to_string(),
+                    "soon".to_string(),
+                ],
+                40.3,
+            )
+            .await
+            .unwrap();
+        let dates: Vec<NaiveDate> = summaries.iter().map(|s| s.expiration).collect();
+        assert_eq!(dates, vec![date(2026, 1, 16), date(2026, 2, 20)]);
+        assert!((summaries[0].atm_iv - 0.6).abs() < 1e-9);
+        assert_eq!(summaries[0].total_oi, 46016);
+        assert!((summaries[0].expected_move_pct - 36.825 / 40.3 * 100.0).abs() < 1e-9);
+
+        let all = analyzer
+            .compare_expirations("LMND", &[], 40.3)
+            .await
+            .unwrap();
+        let dates: Vec<NaiveDate> = all.iter().map(|s| s.expiration).collect();
+        assert_eq!(
+            dates,
+            vec![date(2026, 1, 16), date(2026, 2, 20), date(2026, 3, 20)]
+        );
+    }
+
+    /// Analyzer serving the fixture chain for every symbol, moved to expire a year
+    /// from now so the contracts have time left. Call deltas are 20 / strike, 0.3 at
+    /// $66.67, and put deltas -strike / 100.
+    async fn income_analyzer() -> (OptionsAnalyze, NaiveDate) {
+        use crate::public::mock::{mock_client, mock_greeks, mock_server};
+        use serde_json::{Value, json};
+        use warp::Filter;
+
+        let expiration = Local::now().date_naive() + Days::new(365);
+        let chain = OPTION_CHAIN.replace(
+            "LMND251219",
+            &format!("LMND{}", expiration.format("%y%m%d")),
+        );
+        let chains = warp::path!("userapigateway" / "marketdata" / "TEST" / "option-chain")
+            .and(warp::post())
+            .map(move || {
+                warp::reply::with_header(chain.clone(), "content-type", "application/json")
+            });
+        let greeks = mock_greeks(
+            |s| {
+                let parts = OsiSymbol::parse(s).unwrap();
+                match parts.option_type {
+                    OptionType::Call => (20.0 / parts.strike).min(1.0),
+                    OptionType::Put => -parts.strike / 100.0,
+                }
+            },
+            0.6,
+        );
+        // Every chain is the LMND one, the underlying prices tell the symbols apart
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(|body: Value| {
+                let instrument = &body["instruments"][0];
+                let last = match instrument["symbol"].as_str() {
+                    Some("LMND") => "70.0",
+                    _ => "20.0",
+                };
+                let now = chrono::Utc::now().to_rfc3339();
+                warp::reply::json(&json!({ "quotes": [{
+                    "instrument": instrument, "outcome": "SUCCESS", "last": last,
+                    "lastTimestamp": now, "bid": last, "bidTimestamp": now, "ask": last,
+                    "askTimestamp": now, "volume": 0
+                }] }))
+            });
+        let client = mock_client(mock_server(chains.or(greeks).or(quotes)).await);
+
+        (OptionsAnalyze::new(client), expiration)
+    }
+
+    #[tokio::test]
+    async fn test_analyze_portfolio() {
+        use crate::public::mock::{mock_client, mock_server};
+        use serde_json::{Value, json};
+        use warp::Filter;
+        use warp::http::StatusCode;
+
+        // No quotes or greeks, so the reports center on max pain and keep no IV history
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .map(|| warp::reply::json(&json!({ "quotes": [] })));
+        let chains = warp::path!("userapigateway" / "marketdata" / "TEST" / "option-chain")
+            .and(warp::body::json())
+            .map(|body: Value| {
+                let status = match body["instrument"]["symbol"].as_str() {
+                    Some("FAIL") => StatusCode::INTERNAL_SERVER_ERROR,
+                    _ => StatusCode::OK,
+                };
+                let reply =
+                    warp::reply::with_header(OPTION_CHAIN, "content-type", "application/json");
+                warp::reply::with_status(reply, status)
+            });
+        let greeks = warp::path!("userapigateway" / "option-details" / "TEST" / "greeks")
+            .map(|| warp::reply::json(&json!({ "greeks": [] })));
+        let analyzer =
+            OptionsAnalyze::new(mock_client(mock_server(quotes.or(chains).or(greeks)).await));
+
+        let config =
+            Config::from_str("options = ['LMND', 'FAIL', 'AMD251219C00200000', 'LMND']").unwrap();
+        let reports = analyzer
+            .analyze_portfolio(&config, date(2025, 12, 19), 3, None)
+            .await;
+        let symbols: Vec<&str> = reports.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["AMD", "LMND"]);
+        assert!(
+            reports
+                .iter()
+                .all(|r| r.calls.len() == 3 && r.puts.len() == 3)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_covered_calls() {
+        let (analyzer, expiration) = income_analyzer().await;
+        let portfolio: AccountPortfolio = serde_json::from_str(PORTFOLIO_WITH_OPTIONS).unwrap();
+        // LMND and SLDP hold enough shares, MU only 80, and the put is no equity.
+        let positions: Vec<Position> = portfolio
+            .positions
+            .into_iter()
+            .filter(|p| {
+                ["LMND", "SLDP", "MU", "QCOM260220P00138000"]
+                    .contains(&p.instrument.symbol.as_str())
+            })
+            .collect();
+        let suggestions = analyzer
+            .find_covered_calls(&positions, &expiration.to_string(), 0.3)
+            .await
+            .unwrap();
+
+        let years = 365.0 / DAYS_PER_YEAR;
+        assert_eq!(
+            suggestions,
+            vec![
+                CoveredCallSuggestion {
+                    ticker: "SLDP".to_string(),
+                    shares_held: 2950,
+                    recommended_strike: 65.0,
+                    mid_price: 13.8,
+                    annualized_yield: 13.8 / 3.51 / years * 100.0,
+                },
+                CoveredCallSuggestion {
+                    ticker: "LMND".to_string(),
+                    shares_held: 1653,
+                    recommended_strike: 65.0,
+                    mid_price: 13.8,
+                    annualized_yield: 13.8 / 64.8 / years * 100.0,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_cash_secured_puts() {
+        let (analyzer, expiration) = income_analyzer().await;
+        let expiration = expiration.to_string();
+
+        // Closest to 0.3 is the $30 put, above the max strike.
+        let symbols = ["DOWN".to_string(), "LMND".to_string()];
+        let suggestions = analyzer
+            .find_cash_secured_puts(&symbols, &expiration, 0.3, 25.0)
+            .await
+            .unwrap();
+        // Same put and yield for both, but it is deep in the money with DOWN at $20
+        let years = 365.0 / DAYS_PER_YEAR;
+        let payoff = |price: f64| {
+            crate::pricing::black_scholes_price(
+                price,
+                25.0,
+                DEFAULT_RISK_FREE_RATE,
+                years,
+                0.6,
+                OptionType::Put,
+            )
+        };
+        let suggestion = |ticker: &str, price: f64| CspSuggestion {
+            ticker: ticker.to_string(),
+            strike: 25.0,
+            mid_price: 0.175,
+            capital_required: 2500.0,
+            annualized_yield: 0.175 / 25.0 / years * 100.0,
+            delta: -0.25,
+            expected_value: 0.175 - payoff(price),
+        };
+        assert_eq!(
+            suggestions,
+            vec![suggestion("LMND", 70.0), suggestion("DOWN", 20.0)]
+        );
+        assert!(suggestions[1].expected_value < -4.0);
+
+        let none = analyzer
+            .find_cash_secured_puts(&["LMND".to_string()], &expiration, 0.3, 2.0)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_analysis_report() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let greeks = HashMap::from([(
+            "LMND251219C00041000".to_string(),
+            OptionGreeks {
+                symbol: "LMND251219C00041000".to_string(),
+                greeks: Greeks {
+                    delta: 0.81,
+                    gamma: 0.01,
+                    theta: -0.02,
+                    vega: 0.05,
+                    rho: 0.03,
+                    implied_volatility: 0.9,
+                },
+            },
+        )]);
+        let report = AnalysisReport::new(
+            "LMND".to_string(),
+            date(2025, 12, 19),
+            Some(40.3),
+            &chain,
+            3,
+            &greeks,
+            &AnalysisConfig::default(),
+        );
+
+        assert_eq!(report.total_oi, 46016);
+        assert_eq!(report.put_call_ratio, 26628.0 / 19388.0);
+        assert_eq!(report.max_pain, 50.0);
+        assert_eq!(report.unusual_activity.len(), 5);
+        assert_eq!(report.iron_condors.len(), 3);
+        assert!((report.expected_move_dollar - 36.825).abs() < 1e-9);
+        assert!((report.expected_move_pct - 36.825 / 40.3 * 100.0).abs() < 1e-9);
+
+        // 108 calls and 23 puts traded at $75, among the busiest strikes
+        assert_eq!(report.volume_profile[&OrderedFloat(75.0)], 131);
+        let busier = report.volume_profile.values().filter(|v| **v > 131).count();
+        assert!(busier < 10, "{busier} strikes above $75");
+        assert_eq!(
+            report.open_interest_profile[&OrderedFloat(75.0)],
+            1516 + 306
+        );
+        assert_eq!(
+            report.open_interest_profile.values().sum::<u64>(),
+            report.total_oi
+        );
+        assert!(serde_json::to_string(&report).is_ok());
+
+        let strikes = |rows: &[OptionRow]| -> Vec<f64> { rows.iter().map(|r| r.strike).collect() };
+        assert_eq!(strikes(&report.calls), vec![41.0, 42.0, 43.0]);
+        assert_eq!(strikes(&report.puts), vec![39.0, 38.0, 37.0]);
+        assert_eq!(strikes(&report.top_oi_calls), vec![60.0, 75.0, 65.0]);
+        assert_eq!(strikes(&report.top_oi_puts), vec![37.0, 32.0, 55.0]);
+
+        let call = &report.calls[0];
+        assert_eq!(call.symbol.as_str(), "LMND251219C00041000");
+        assert_eq!((call.bid, call.ask), (33.6, 37.1));
+        assert_eq!((call.volume, call.open_interest), (1, 31));
+        assert_eq!(call.greeks.as_ref().unwrap().greeks.delta, 0.81);
+        assert!(report.calls[1].greeks.is_none());
+
+        assert_eq!(report.atm_iv(), Some(0.9));
+        assert_eq!(report.iv_rank, None);
+
+        let mut history = IvHistory::new("LMND");
+        let today = Local::now().date_naive();
+        for (days, iv) in [(4, 0.7), (3, 1.1), (2, 0.8), (1, 1.0)] {
+            history.record(today - Days::new(days), iv);
+        }
+        let ranked = report.clone().with_iv_history(&history);
+        assert_eq!(ranked.iv_rank, Some(50.0));
+        assert_eq!(ranked.iv_52w, Some((0.7, 1.1)));
+
+        let best = AnalysisReport::closest_to_delta(&report.calls, TARGET_DELTA).unwrap();
+        assert_eq!(best.strike, 41.0);
+        assert!(AnalysisReport::closest_to_delta(&report.puts, TARGET_DELTA).is_none());
+    }
+
+    #[test]
+    fn test_analysis_report_without_underlying() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let report = AnalysisReport::new(
+            "LMND".to_string(),
+            date(2025, 12, 19),
+            None,
+            &chain,
+            2,
+            &HashMap::new(),
+            &AnalysisConfig::default(),
+        );
+
+        assert_eq!(report.underlying_price, None);
+        assert!(report.expected_move_dollar.is_nan());
+        assert!(report.iron_condors.is_empty());
+        assert_eq!(report.calls[0].strike, 55.0);
+        assert_eq!(report.puts[0].strike, 49.0);
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let greeks: HashMap<String, OptionGreeks> = chain
+            .calls
+            .iter()
+            .filter_map(|q| Some((q.strike()?, &q.instrument.symbol)))
+            .map(|(strike, symbol)| (symbol.clone(), test_greeks(symbol, 20.0 / strike)))
+            .collect();
+        let report = AnalysisReport::new(
+            "LMND".to_string(),
+            date(2025, 12, 19),
+            Some(40.3),
+            &chain,
+            3,
+            &greeks,
+            &AnalysisConfig::default(),
+        );
+        let min_volume = report.puts.iter().map(|r| r.volume).max().unwrap();
+
+        let unfiltered = report.clone().filter_rows(0);
+        assert_eq!(unfiltered.calls.len(), 3);
+        assert_eq!(unfiltered.puts.len(), 3);
+        let by_volume = report.clone().filter_rows(min_volume);
+        assert!(by_volume.puts.iter().all(|r| r.volume >= min_volume));
+        assert!(!by_volume.puts.is_empty());
+
+        // Calls at $41, $42 and $43, only the last two under 0.48
+        let by_delta = report.retain_contracts(&chain.filter_by_delta(&greeks, 0.2, 0.48));
+        let strikes: Vec<f64> = by_delta.calls.iter().map(|r| r.strike).collect();
+        assert_eq!(strikes, vec![42.0, 43.0]);
+        assert!(by_delta.puts.is_empty());
+    }
+
+    fn test_greeks(symbol: &str, delta: f64) -> OptionGreeks {
+        OptionGreeks {
+            symbol: symbol.to_string(),
+            greeks: Greeks {
+                delta,
+                gamma: 0.01,
+                theta: -0.1,
+                vega: 0.2,
+                rho: 0.01,
+                implied_volatility: 0.6,
+            },
+        }
+    }
+
+    #[cfg(feature = "progress")]
+    #[test]
+    fn test_greeks_progress_bar() {
+        let bar = greeks_progress_bar(60, false);
+        assert!(bar.is_hidden());
+        bar.inc(60);
+        assert_eq!(bar.position(), 60);
+    }
+
+    #[test]
+    fn test_skew_ratio() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        // 25 delta put at $40 and call at $70, with the put IV 1.3 times the call's
+        let greeks: HashMap<String, OptionGreeks> = chain
+            .puts
+            .iter()
+            .map(|q| (q, -q.strike().unwrap() / 160.0, 0.78))
+            .chain(
+                chain
+                    .calls
+                    .iter()
+                    .map(|q| (q, (10.0 / (q.strike().unwrap() - 30.0)).min(1.0), 0.6)),
+            )
+            .map(|(q, delta, iv)| {
+                let mut greeks = test_greeks(&q.instrument.symbol, delta);
+                greeks.greeks.implied_volatility = iv;
+                (q.instrument.symbol.clone(), greeks)
+            })
+            .collect();
+
+        let ratio = OptionsAnalyze::skew_ratio(&chain, &greeks, 41.0);
+        assert!((ratio - 1.3).abs() < 1e-12, "ratio {ratio}");
+        assert!(OptionsAnalyze::skew_ratio(&chain, &HashMap::new(), 41.0).is_nan());
+
+        let report = AnalysisReport::new(
+            "LMND".to_string(),
+            date(2025, 12, 19),
+            Some(40.3),
+            &chain,
+            2,
+            &greeks,
+            &AnalysisConfig::default(),
+        );
+        // Centered on the $40 strike, the same wings are picked
+        assert!((report.skew.skew_ratio - 1.3).abs() < 1e-12);
+        assert!((report.skew.atm_iv - 0.69).abs() < 1e-12);
+        assert_eq!(
+            report.skew.skew_interpretation,
+            "put skew, downside protection is bid"
+        );
+        assert_eq!(SkewSummary::new(0.6, 1.05).skew_interpretation, "flat");
+        assert_eq!(
+            SkewSummary::new(0.6, 0.8).skew_interpretation,
+            "call skew, upside is bid"
+        );
+    }
+
+    #[test]
+    fn test_next_monthly_expiration() {
+        // Third Friday of February 2026 is the 20th
+        assert_eq!(next_monthly_expiration(date(2026, 2, 2)), date(2026, 2, 20));
+        assert_eq!(
+            next_monthly_expiration(date(2026, 2, 20)),
+            date(2026, 2, 20)
+        );
+        assert_eq!(
+            next_monthly_expiration(date(2026, 2, 21)),
+            date(2026, 3, 20)
+        );
+        assert_eq!(
+            next_monthly_expiration(date(2026, 12, 19)),
+            date(2027, 1, 15)
+        );
+    }
+
+    #[test]
+    fn test_strikes_around_atm() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let (calls, puts) = strikes_around_atm(&chain, 40.3, 3);
+
+        let strikes = |quotes: &[&Quote]| -> Vec<f64> {
+            quotes.iter().map(|q| q.strike().unwrap()).collect()
+        };
+        assert_eq!(strikes(&calls), vec![41.0, 42.0, 43.0]);
+        assert_eq!(strikes(&puts), vec![39.0, 38.0, 37.0]);
+    }
+
+    #[test]
+    fn test_parse_option_name() {
+        let (ticker, strike, op_type, expiration) =
+            parse_option_name("QCOM $138 Put Feb 20, '26").unwrap();
+        assert_eq!(ticker, "QCOM");
+        assert_eq!(strike, 138.0);
+        assert_eq!(op_type, OptionType::Put);
+        assert_eq!(expiration, date(2026, 2, 20));
+
+        let (_, strike, op_type, expiration) =
+            parse_option_name("F $12.50 Call Jan 5, '26").unwrap();
+        assert_eq!(strike, 12.5);
         assert_eq!(op_type, OptionType::Call);
+        assert_eq!(expiration, date(2026, 1, 5));
+        assert_eq!(
+            parse_option_name("F $12.50 Call Jan 05, '26").unwrap().3,
+            date(2026, 1, 5)
+        );
+
+        let put = (
+            "QCOM".to_string(),
+            138.0,
+            OptionType::Put,
+            date(2026, 2, 20),
+        );
+        assert_eq!(parse_option_name("QCOM $138 Put Feb 20 '26").unwrap(), put);
+        assert_eq!(parse_option_name("QCOM 138 Put Feb 20 2026").unwrap(), put);
+        assert_eq!(parse_option_name("QCOM $138 Put Feb 20 2026").unwrap(), put);
+    }
+
+    #[test]
+    fn test_parse_option_name_errors() {
+        assert_eq!(
+            parse_option_name("QCOM $138 Put"),
+            Err(OptionParseError::UnexpectedTokenCount)
+        );
+        assert_eq!(
+            parse_option_name("QCOM 138 Put Feb 20, '26"),
+            Err(OptionParseError::InvalidStrike("138".to_string()))
+        );
+        assert_eq!(
+            parse_option_name("QCOM $138 Straddle Feb 20, '26"),
+            Err(OptionParseError::InvalidOptionType("Straddle".to_string()))
+        );
+        assert!(matches!(
+            parse_option_name("QCOM $138 Put Feb 30, '26"),
+            Err(OptionParseError::InvalidDate(_))
+        ));
+        assert_eq!(
+            parse_option_name("QCOM 138 Put Feb 20, 2026"),
+            Err(OptionParseError::UnrecognizedFormat(
+                "QCOM 138 Put Feb 20, 2026".to_string()
+            ))
+        );
+        assert!(matches!(
+            parse_option_name("QCOM $138 Put Feb 20 26"),
+            Err(OptionParseError::UnrecognizedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_option_position_name_fallback() {
+        let portfolio: AccountPortfolio = serde_json::from_str(PORTFOLIO_WITH_OPTIONS).unwrap();
+        let mut pos = portfolio
+            .positions
+            .into_iter()
+            .find(|p| p.is_option())
+            .unwrap();
+        pos.instrument.symbol = "QCOM-PUT".to_string();
+        let option = OptionPosition::new(&pos).unwrap();
+        assert_eq!(option.ticker, "QCOM");
+        assert_eq!(option.expiration, date(2026, 2, 20));
+
+        pos.instrument.name = None;
+        assert!(matches!(
+            OptionPosition::new(&pos),
+            Err(OptionParseError::InvalidSymbol(_))
+        ));
+
+        pos.instrument.symbol = "QCOM260220P00138000".to_string();
+        pos.cost_basis = None;
+        assert_eq!(
+            OptionPosition::new(&pos).unwrap_err(),
+            OptionParseError::InvalidField("costBasis")
+        );
+    }
+
+    #[test]
+    fn test_position_side() {
+        assert_eq!(position_side(177.0, 1), OrderSide::Buy);
+        assert_eq!(position_side(-120.07, -5), OrderSide::Sell);
+        assert_eq!(position_side(0.0, 1), OrderSide::Buy);
+        assert_eq!(position_side(0.0, -1), OrderSide::Sell);
+        assert_eq!(position_side(-0.0, -1), OrderSide::Sell);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_option_name_no_panic(name in "\\PC{0,40}") {
+            let _ = parse_option_name(&name);
+        }
+
+        #[test]
+        fn prop_parse_option_name_malformed(
+            ticker in "[A-Z]{1,5}",
+            strike in "[$0-9.,a-z-]{0,8}",
+            op_type in "(Call|Put|Cal|put|)",
+            month in "[A-Za-z]{0,4}",
+            day in "[0-9]{0,3},?",
+            year in "'?[0-9]{0,3}",
+        ) {
+            let name = format!("{ticker} {strike} {op_type} {month} {day} {year}");
+            let _ = parse_option_name(&name);
+        }
+
+        #[test]
+        fn prop_parse_option_name_formats(
+            ticker in "[A-Z]{1,5}",
+            strike_cents in 1u32..1_000_000,
+            call in any::<bool>(),
+            // chrono reads two digit years past '68 as 19xx
+            days in 0u64..25_000,
+            format in prop::sample::select(vec![
+                "%b %-d, '%y",
+                "%b %d '%y",
+                "%b %-d %Y",
+            ]),
+        ) {
+            let strike = strike_cents as f64 / 100.0;
+            let op_type = if call { OptionType::Call } else { OptionType::Put };
+            let expiration = date(2000, 1, 1) + Days::new(days);
+            let dollar = if format.ends_with("%Y") { "" } else { "$" };
+            let name = format!(
+                "{ticker} {dollar}{strike} {} {}",
+                if call { "Call" } else { "Put" },
+                expiration.format(format)
+            );
+            let parsed = parse_option_name(&name).unwrap();
+            prop_assert_eq!(parsed, (ticker, strike, op_type, expiration));
+        }
     }
 }
@@ -0,0 +1,246 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::public::OptionType;
+
+/// Length of the fixed part of an OSI symbol: YYMMDD + C/P + 8 strike digits.
+const OSI_SUFFIX_LEN: usize = 15;
+const MAX_TICKER_LEN: usize = 6;
+
+/// Option symbol in the OSI-normalized format, like "LMND251219C00060000":
+/// ticker, expiration as YYMMDD, C or P, and the strike times 1000 padded to 8 digits.
+/// Deserializes through `OsiSymbol::parse`, rejecting invalid symbols.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct OsiSymbol(String);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OsiParts {
+    pub ticker: String,
+    pub expiration: NaiveDate,
+    pub option_type: OptionType,
+    pub strike: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OsiError {
+    InvalidFormat(String),
+    InvalidDate(String),
+}
+
+impl fmt::Display for OsiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OsiError::InvalidFormat(s) => write!(f, "invalid OSI symbol <{s}>"),
+            OsiError::InvalidDate(s) => write!(f, "invalid expiration in OSI symbol <{s}>"),
+        }
+    }
+}
+
+impl std::error::Error for OsiError {}
+
+impl OsiSymbol {
+    /// Splits an OSI symbol into its parts.
+    /// Accepts the space-padded form ("AAPL  260417C00200000") as well.
+    pub fn parse(s: &str) -> Result<OsiParts, OsiError> {
+        let invalid = || OsiError::InvalidFormat(s.to_string());
+
+        if !s.is_ascii() || s.len() <= OSI_SUFFIX_LEN {
+            return Err(invalid());
+        }
+        let (ticker, suffix) = s.split_at(s.len() - OSI_SUFFIX_LEN);
+        let ticker = ticker.trim_end();
+        if ticker.is_empty()
+            || ticker.len() > MAX_TICKER_LEN
+            || !ticker
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let (date, rest) = suffix.split_at(6);
+        let (op_type, strike) = rest.split_at(1);
+        if !date.chars().all(|c| c.is_ascii_digit()) || !strike.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let option_type = match op_type {
+            "C" => OptionType::Call,
+            "P" => OptionType::Put,
+            _ => return Err(invalid()),
+        };
+        let expiration = NaiveDate::parse_from_str(date, "%y%m%d")
+            .map_err(|_| OsiError::InvalidDate(s.to_string()))?;
+        let strike = strike.parse::<u64>().map_err(|_| invalid())? as f64 / 1000.0;
+
+        Ok(OsiParts {
+            ticker: ticker.to_string(),
+            expiration,
+            option_type,
+            strike,
+        })
+    }
+
+    /// Builds the unpadded OSI symbol Public uses, like "LMND251219C00060000".
+    pub fn build(
+        ticker: &str,
+        expiration: NaiveDate,
+        option_type: OptionType,
+        strike: f64,
+    ) -> OsiSymbol {
+        let op_type = match option_type {
+            OptionType::Call => 'C',
+            OptionType::Put => 'P',
+        };
+        let strike = (strike * 1000.0).round() as u64;
+
+        OsiSymbol(format!(
+            "{ticker}{}{op_type}{strike:08}",
+            expiration.format("%y%m%d")
+        ))
+    }
+
+    pub fn parts(&self) -> OsiParts {
+        // Only constructed through validation or `build`, so this can't fail.
+        Self::parse(&self.0).expect("OsiSymbol holds a valid symbol")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&OsiParts> for OsiSymbol {
+    fn from(parts: &OsiParts) -> Self {
+        OsiSymbol::build(
+            &parts.ticker,
+            parts.expiration,
+            parts.option_type.clone(),
+            parts.strike,
+        )
+    }
+}
+
+impl FromStr for OsiSymbol {
+    type Err = OsiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)?;
+        Ok(OsiSymbol(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for OsiSymbol {
+    type Error = OsiError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)?;
+        Ok(OsiSymbol(s))
+    }
+}
+
+impl From<OsiSymbol> for String {
+    fn from(symbol: OsiSymbol) -> Self {
+        symbol.0
+    }
+}
+
+impl fmt::Display for OsiSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_put() {
+        let parts = OsiSymbol::parse("MU260417P00830000").unwrap();
+        assert_eq!(parts.ticker, "MU");
+        assert_eq!(parts.option_type, OptionType::Put);
+        assert_eq!(
+            parts.expiration,
+            NaiveDate::from_ymd_opt(2026, 4, 17).unwrap()
+        );
+        assert_eq!(parts.strike, 830.0);
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let parts = OsiSymbol::parse("LITE260417C01410000").unwrap();
+        assert_eq!(parts.ticker, "LITE");
+        assert_eq!(parts.option_type, OptionType::Call);
+        assert_eq!(parts.strike, 1410.0);
+    }
+
+    #[test]
+    fn test_parse_padded_fractional_strike() {
+        let parts = OsiSymbol::parse("F     260116C00012500").unwrap();
+        assert_eq!(parts.ticker, "F");
+        assert_eq!(parts.strike, 12.5);
+    }
+
+    #[test]
+    fn test_build() {
+        let symbol = OsiSymbol::build(
+            "LMND",
+            NaiveDate::from_ymd_opt(2025, 12, 19).unwrap(),
+            OptionType::Call,
+            60.0,
+        );
+        assert_eq!(symbol.as_str(), "LMND251219C00060000");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(
+            OsiSymbol::parse("LMND251219X00060000"),
+            Err(OsiError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            OsiSymbol::parse("LMND251319C00060000"),
+            Err(OsiError::InvalidDate(_))
+        ));
+        assert!("AAPL".parse::<OsiSymbol>().is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let symbol: OsiSymbol = serde_json::from_str(r#""LMND251219C00060000""#).unwrap();
+        assert_eq!(symbol.parts().ticker, "LMND");
+        assert_eq!(
+            serde_json::to_string(&symbol).unwrap(),
+            r#""LMND251219C00060000""#
+        );
+
+        assert!(serde_json::from_str::<OsiSymbol>(r#""AAPL""#).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_build_parse_round_trip(
+            ticker in "[A-Z]{1,6}",
+            date in (2000i32..2099, 1u32..=12, 1u32..=28),
+            is_call in any::<bool>(),
+            strike in 0u64..100_000_000,
+        ) {
+            let s = format!(
+                "{ticker}{:02}{:02}{:02}{}{strike:08}",
+                date.0 % 100, date.1, date.2, if is_call { 'C' } else { 'P' }
+            );
+            let parts = OsiSymbol::parse(&s).unwrap();
+            prop_assert_eq!(OsiSymbol::from(&parts).to_string(), s);
+        }
+
+        #[test]
+        fn prop_parse_garbage(s in "[a-z0-9 $,.']{0,30}") {
+            prop_assert!(matches!(OsiSymbol::parse(&s), Err(OsiError::InvalidFormat(_))));
+        }
+    }
+}
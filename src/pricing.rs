@@ -0,0 +1,386 @@
+use chrono::Local;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::osi::OsiSymbol;
+use crate::public::{Greeks, OptionGreeks, OptionType, Quote};
+
+const MAX_IV_ITERATIONS: usize = 100;
+const IV_PRICE_TOLERANCE: f64 = 1e-10;
+const MIN_VEGA: f64 = 1e-12;
+pub(crate) const DAYS_PER_YEAR: f64 = 365.0;
+/// Halvings of the search range in `norm_inv_cdf`, precise to about 1e-14.
+const MAX_QUANTILE_ITERATIONS: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IvError {
+    DidNotConverge,
+    InvalidInput(String),
+}
+
+impl fmt::Display for IvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IvError::DidNotConverge => write!(f, "implied volatility did not converge"),
+            IvError::InvalidInput(e) => write!(f, "invalid implied volatility input: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for IvError {}
+
+/// Standard normal density.
+fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+/// Standard normal CDF, West's double precision version of Hart's approximation.
+pub(crate) fn norm_cdf(x: f64) -> f64 {
+    let xabs = x.abs();
+    let c = if xabs > 37.0 {
+        0.0
+    } else if xabs < 7.07106781186547 {
+        let e = (-xabs * xabs / 2.0).exp();
+        let num = [
+            3.52624965998911e-02,
+            0.700383064443688,
+            6.37396220353165,
+            33.912866078383,
+            112.079291497871,
+            221.213596169931,
+            220.206867912376,
+        ]
+        .iter()
+        .fold(0.0, |acc, c| acc * xabs + c);
+        let den = [
+            8.83883476483184e-02,
+            1.75566716318264,
+            16.064177579207,
+            86.7807322029461,
+            296.564248779674,
+            637.333633378831,
+            793.826512519948,
+            440.413735824752,
+        ]
+        .iter()
+        .fold(0.0, |acc, c| acc * xabs + c);
+        e * num / den
+    } else {
+        let e = (-xabs * xabs / 2.0).exp();
+        let b = xabs + 0.65;
+        let b = xabs + 4.0 / b;
+        let b = xabs + 3.0 / b;
+        let b = xabs + 2.0 / b;
+        let b = xabs + 1.0 / b;
+        e / b / 2.506628274631
+    };
+
+    if x > 0.0 { 1.0 - c } else { c }
+}
+
+/// Inverse of `norm_cdf`, the `p` quantile of the standard normal, by bisection.
+pub(crate) fn norm_inv_cdf(p: f64) -> f64 {
+    let (mut low, mut high) = (-37.0, 37.0);
+    for _ in 0..MAX_QUANTILE_ITERATIONS {
+        let mid = (low + high) / 2.0;
+        if norm_cdf(mid) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+pub(crate) fn d1_d2(s: f64, k: f64, r: f64, t: f64, sigma: f64) -> (f64, f64) {
+    let vol_t = sigma * t.sqrt();
+    let d1 = ((s / k).ln() + (r + sigma * sigma / 2.0) * t) / vol_t;
+    (d1, d1 - vol_t)
+}
+
+/// Black-Scholes price of a European option on a non-dividend paying underlying.
+/// `s` spot, `k` strike, `r` risk-free rate and `sigma` volatility are annualized,
+/// `t` is the time to expiration in years.
+pub fn black_scholes_price(
+    s: f64,
+    k: f64,
+    r: f64,
+    t: f64,
+    sigma: f64,
+    option_type: OptionType,
+) -> f64 {
+    let (d1, d2) = d1_d2(s, k, r, t, sigma);
+    let discount = (-r * t).exp();
+    match option_type {
+        OptionType::Call => s * norm_cdf(d1) - k * discount * norm_cdf(d2),
+        OptionType::Put => k * discount * norm_cdf(-d2) - s * norm_cdf(-d1),
+    }
+}
+
+/// Risk-neutral probability that the option expires in the money, N(d2) for a call
+/// and N(-d2) for a put. Same inputs as `black_scholes_price`.
+pub fn probability_itm(s: f64, k: f64, r: f64, t: f64, sigma: f64, option_type: OptionType) -> f64 {
+    let (_, d2) = d1_d2(s, k, r, t, sigma);
+    match option_type {
+        OptionType::Call => norm_cdf(d2),
+        OptionType::Put => norm_cdf(-d2),
+    }
+}
+
+/// Analytic Black-Scholes greeks, quoted the way the API does: theta annualized,
+/// vega and rho per unit of volatility and rates.
+pub fn bs_greeks(s: f64, k: f64, r: f64, t: f64, sigma: f64, option_type: OptionType) -> Greeks {
+    let (d1, d2) = d1_d2(s, k, r, t, sigma);
+    let discount = (-r * t).exp();
+    let pdf = norm_pdf(d1);
+    let gamma = pdf / (s * sigma * t.sqrt());
+    let vega = s * pdf * t.sqrt();
+    let decay = -s * pdf * sigma / (2.0 * t.sqrt());
+
+    let (delta, theta, rho) = match option_type {
+        OptionType::Call => (
+            norm_cdf(d1),
+            decay - r * k * discount * norm_cdf(d2),
+            k * t * discount * norm_cdf(d2),
+        ),
+        OptionType::Put => (
+            norm_cdf(d1) - 1.0,
+            decay + r * k * discount * norm_cdf(-d2),
+            -k * t * discount * norm_cdf(-d2),
+        ),
+    };
+
+    Greeks {
+        delta,
+        gamma,
+        theta,
+        vega,
+        rho,
+        implied_volatility: sigma,
+    }
+}
+
+/// Expected payoff at expiration of holding the contract of `quote`, discounted
+/// at `risk_free_rate`, per share. The underlying is taken as lognormal with the IV
+/// of `greeks`, so this is the Black-Scholes price: S N(d1) - K e^(-rT) N(d2) for
+/// a call. The intrinsic value on the expiration day, NaN when `quote` isn't for an
+/// OSI symbol or expired, or without a usable IV.
+pub fn expected_value(
+    quote: &Quote,
+    underlying_price: f64,
+    greeks: &OptionGreeks,
+    risk_free_rate: f64,
+) -> f64 {
+    let Ok(parts) = OsiSymbol::parse(&quote.instrument.symbol) else {
+        return f64::NAN;
+    };
+    let (s, k) = (underlying_price, parts.strike);
+    let days = (parts.expiration - Local::now().date_naive()).num_days();
+    if days < 0 {
+        return f64::NAN;
+    }
+    if days == 0 {
+        return match parts.option_type {
+            OptionType::Call => (s - k).max(0.0),
+            OptionType::Put => (k - s).max(0.0),
+        };
+    }
+    let sigma = greeks.greeks.implied_volatility;
+    if !(sigma > 0.0 && s > 0.0) {
+        return f64::NAN;
+    }
+
+    black_scholes_price(
+        s,
+        k,
+        risk_free_rate,
+        days as f64 / DAYS_PER_YEAR,
+        sigma,
+        parts.option_type,
+    )
+}
+
+/// Solves for the volatility that prices the option at `market_price`,
+/// using Newton-Raphson on the Black-Scholes price.
+pub fn implied_volatility(
+    market_price: f64,
+    s: f64,
+    k: f64,
+    r: f64,
+    t: f64,
+    option_type: OptionType,
+) -> Result<f64, IvError> {
+    if !(s > 0.0 && k > 0.0 && t > 0.0 && r.is_finite()) {
+        return Err(IvError::InvalidInput(format!(
+            "spot {s}, strike {k} and time {t} must be positive, rate {r} finite"
+        )));
+    }
+    let discount = (-r * t).exp();
+    let (lower, upper) = match option_type {
+        OptionType::Call => ((s - k * discount).max(0.0), s),
+        OptionType::Put => ((k * discount - s).max(0.0), k * discount),
+    };
+    if !(market_price > lower && market_price < upper) {
+        return Err(IvError::InvalidInput(format!(
+            "price {market_price} outside of the no-arbitrage bounds ({lower}, {upper})"
+        )));
+    }
+
+    // Manaster-Koehler starting point, where vega peaks, so Newton converges
+    // monotonically instead of shooting off from a flat part of the curve.
+    let mut sigma = (2.0 * ((s / k).ln() + r * t).abs() / t).sqrt().max(0.1);
+    for _ in 0..MAX_IV_ITERATIONS {
+        let diff = black_scholes_price(s, k, r, t, sigma, option_type.clone()) - market_price;
+        if diff.abs() < IV_PRICE_TOLERANCE {
+            return Ok(sigma);
+        }
+        let (d1, _) = d1_d2(s, k, r, t, sigma);
+        let vega = s * norm_pdf(d1) * t.sqrt();
+        if vega < MIN_VEGA {
+            return Err(IvError::DidNotConverge);
+        }
+        let next = sigma - diff / vega;
+        // Newton can overshoot below zero far from the money, back off instead.
+        sigma = if next > 0.0 { next } else { sigma / 2.0 };
+    }
+
+    Err(IvError::DidNotConverge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_black_scholes_price() {
+        let call = black_scholes_price(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Call);
+        let put = black_scholes_price(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Put);
+        assert!((call - 10.450584).abs() < 1e-6);
+        assert!((put - 5.573526).abs() < 1e-6);
+        // Put-call parity
+        assert!((call - put - (100.0 - 100.0 * (-0.05f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expected_value() {
+        use crate::public::OptionChain;
+        use chrono::Days;
+
+        let chain: OptionChain =
+            serde_json::from_str(include_str!("fixtures/option_chain.json")).unwrap();
+        let quote = |symbol: String| {
+            let mut quote = chain.calls[0].clone();
+            quote.instrument.symbol = symbol;
+            quote
+        };
+        let expiration = Local::now().date_naive() + Days::new(365);
+        let greeks = OptionGreeks {
+            symbol: String::new(),
+            greeks: bs_greeks(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Call),
+        };
+
+        let call = quote(format!("AAPL{}C00100000", expiration.format("%y%m%d")));
+        let ev = expected_value(&call, 100.0, &greeks, 0.05);
+        let price = black_scholes_price(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Call);
+        assert!((ev - price).abs() < 1e-12);
+        assert!((ev - 10.450584).abs() < 1e-6);
+
+        let put = quote(format!("AAPL{}P00100000", expiration.format("%y%m%d")));
+        let ev = expected_value(&put, 100.0, &greeks, 0.05);
+        assert!((ev - 5.573526).abs() < 1e-6);
+
+        // Intrinsic value on the expiration day
+        let today = Local::now().date_naive().format("%y%m%d");
+        let call = quote(format!("AAPL{today}C00100000"));
+        assert_eq!(expected_value(&call, 104.5, &greeks, 0.05), 4.5);
+        assert_eq!(expected_value(&call, 95.0, &greeks, 0.05), 0.0);
+
+        assert!(expected_value(&quote("AAPL".to_string()), 100.0, &greeks, 0.05).is_nan());
+    }
+
+    #[test]
+    fn test_norm_inv_cdf() {
+        assert!(norm_inv_cdf(0.5).abs() < 1e-12);
+        assert!((norm_inv_cdf(0.95) - 1.644854).abs() < 1e-6);
+        assert!((norm_inv_cdf(0.01) + 2.326348).abs() < 1e-6);
+        for x in [-3.0, -0.7, 0.2, 2.5] {
+            assert!((norm_inv_cdf(norm_cdf(x)) - x).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_probability_itm() {
+        let call = probability_itm(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Call);
+        let put = probability_itm(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Put);
+        assert!((call - 0.559618).abs() < 1e-6);
+        assert!((call + put - 1.0).abs() < 1e-12);
+        assert!(probability_itm(100.0, 50.0, 0.05, 0.1, 0.2, OptionType::Call) > 0.99);
+    }
+
+    #[test]
+    fn test_bs_greeks() {
+        let call = bs_greeks(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Call);
+        let put = bs_greeks(100.0, 100.0, 0.05, 1.0, 0.2, OptionType::Put);
+        assert!((call.delta - 0.636831).abs() < 1e-6);
+        assert!((call.delta - put.delta - 1.0).abs() < 1e-12);
+        assert!((call.gamma - 0.018762).abs() < 1e-6);
+        assert_eq!(call.gamma, put.gamma);
+        assert!((call.vega - 37.524035).abs() < 1e-6);
+        assert!((call.theta + 6.414028).abs() < 1e-6);
+        assert!((put.theta + 1.657880).abs() < 1e-6);
+        assert!((call.rho - 53.232482).abs() < 1e-6);
+        assert!(put.rho < 0.0);
+        assert_eq!(call.implied_volatility, 0.2);
+    }
+
+    #[test]
+    fn test_implied_volatility_round_trip() {
+        for (k, option_type) in [
+            (80.0, OptionType::Put),
+            (100.0, OptionType::Call),
+            (100.0, OptionType::Put),
+            (125.0, OptionType::Call),
+        ] {
+            let price = black_scholes_price(100.0, k, 0.04, 0.5, 0.35, option_type.clone());
+            let iv = implied_volatility(price, 100.0, k, 0.04, 0.5, option_type).unwrap();
+            assert!((iv - 0.35).abs() < 1e-6, "strike {k}: {iv}");
+        }
+    }
+
+    #[test]
+    fn test_implied_volatility_invalid_input() {
+        assert!(matches!(
+            implied_volatility(5.0, 100.0, 100.0, 0.05, 0.0, OptionType::Call),
+            Err(IvError::InvalidInput(_))
+        ));
+        // Below intrinsic value
+        assert!(matches!(
+            implied_volatility(5.0, 120.0, 100.0, 0.05, 1.0, OptionType::Call),
+            Err(IvError::InvalidInput(_))
+        ));
+        // A call can't be worth more than the underlying
+        assert!(matches!(
+            implied_volatility(101.0, 100.0, 100.0, 0.05, 1.0, OptionType::Call),
+            Err(IvError::InvalidInput(_))
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn implied_volatility_recovers_sigma(
+            s in 80.0f64..120.0,
+            k in 90.0f64..110.0,
+            r in 0.0f64..0.08,
+            t in 0.25f64..2.0,
+            sigma in 0.2f64..1.0,
+            call in any::<bool>(),
+        ) {
+            let option_type = if call { OptionType::Call } else { OptionType::Put };
+            let price = black_scholes_price(s, k, r, t, sigma, option_type.clone());
+            let iv = implied_volatility(price, s, k, r, t, option_type).unwrap();
+            prop_assert!((iv - sigma).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use super::PublicError;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_RESET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether requests go out: `Closed` sends them, `Open` fails them since the
+/// given instant, and `HalfOpen` has a single trial request out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircuitState {
+    Closed,
+    Open(Instant),
+    HalfOpen,
+}
+
+/// Stops sending requests for `reset_timeout` once `failure_threshold` requests
+/// in a row failed from an outage, instead of hammering an API that is down.
+/// A trial request then decides whether to close the circuit or to open it again.
+/// Clones share their state.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    pub failure_threshold: u32,
+    pub reset_timeout: Duration,
+    state: Arc<Mutex<CircuitState>>,
+    /// Failures in a row while closed, only changed with `state` locked.
+    failures: Arc<AtomicU32>,
+}
+
+/// Permission to send one request, from `CircuitBreaker::acquire`.
+/// A trial dropped before its outcome is recorded counts as failed.
+pub struct CircuitPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    recorded: bool,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_RESET_TIMEOUT)
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+            state: Arc::new(Mutex::new(CircuitState::Closed)),
+            failures: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Lets a request through while closed, or as the trial once `reset_timeout`
+    /// has passed since opening. `PublicError::CircuitOpen` otherwise.
+    pub fn acquire(&self) -> Result<CircuitPermit<'_>, PublicError> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            CircuitState::Closed => {}
+            CircuitState::Open(since) if since.elapsed() >= self.reset_timeout => {
+                info!("Circuit breaker half open, sending a trial request");
+                *state = CircuitState::HalfOpen;
+            }
+            CircuitState::Open(_) | CircuitState::HalfOpen => {
+                return Err(PublicError::CircuitOpen);
+            }
+        }
+
+        Ok(CircuitPermit {
+            breaker: self,
+            recorded: false,
+        })
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if *state == CircuitState::HalfOpen {
+            info!("Trial request succeeded, closing the circuit breaker");
+        }
+        *state = CircuitState::Closed;
+        self.failures.store(0, Ordering::SeqCst);
+    }
+
+    fn on_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let open = match *state {
+            CircuitState::HalfOpen => true,
+            CircuitState::Closed => {
+                self.failures.fetch_add(1, Ordering::SeqCst) + 1 >= self.failure_threshold
+            }
+            CircuitState::Open(_) => false,
+        };
+        if open {
+            warn!(
+                "Circuit breaker open for {:?} after {} failures in a row",
+                self.reset_timeout,
+                self.failures.load(Ordering::SeqCst)
+            );
+            *state = CircuitState::Open(Instant::now());
+            self.failures.store(0, Ordering::SeqCst);
+        }
+    }
+}
+
+impl CircuitPermit<'_> {
+    /// Records the outcome of the request.
+    pub fn record(mut self, outage: bool) {
+        self.recorded = true;
+        if outage {
+            self.breaker.on_failure();
+        } else {
+            self.breaker.on_success();
+        }
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        // A cancelled trial would keep the circuit half open for good
+        if !self.recorded && self.breaker.state() == CircuitState::HalfOpen {
+            self.breaker.on_failure();
+        }
+    }
+}
+
+/// Errors that mean the API is unreachable or failing, rather than it
+/// refusing one request.
+pub fn is_outage(error: &PublicError) -> bool {
+    match error {
+        PublicError::HttpError(_) | PublicError::RetriesExhausted { .. } => true,
+        PublicError::StatusCode(status, _) => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail(breaker: &CircuitBreaker, times: u32) {
+        for _ in 0..times {
+            breaker.acquire().unwrap().record(true);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker_transitions() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // A success resets the count of failures in a row
+        fail(&breaker, 2);
+        breaker.acquire().unwrap().record(false);
+        fail(&breaker, 2);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        fail(&breaker, 1);
+        assert!(matches!(breaker.state(), CircuitState::Open(_)));
+        assert!(matches!(breaker.acquire(), Err(PublicError::CircuitOpen)));
+
+        tokio::time::advance(Duration::from_secs(9)).await;
+        assert!(breaker.acquire().is_err());
+
+        // One trial once the timeout is over, failing it opens the circuit again
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let trial = breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.acquire().is_err());
+        trial.record(true);
+        assert!(matches!(breaker.state(), CircuitState::Open(_)));
+        assert!(breaker.acquire().is_err());
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        fail(&breaker, 2);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker_dropped_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        fail(&breaker, 1);
+        tokio::time::advance(Duration::from_secs(10)).await;
+
+        drop(breaker.acquire().unwrap());
+        assert!(matches!(breaker.state(), CircuitState::Open(_)));
+
+        // Requests dropped while closed don't count
+        tokio::time::advance(Duration::from_secs(10)).await;
+        breaker.acquire().unwrap().record(false);
+        drop(breaker.acquire().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_is_outage() {
+        assert!(is_outage(&PublicError::HttpError("reset".to_string())));
+        assert!(is_outage(&PublicError::StatusCode(503, String::new())));
+        assert!(is_outage(&PublicError::StatusCode(429, String::new())));
+        assert!(!is_outage(&PublicError::StatusCode(404, String::new())));
+        assert!(!is_outage(&PublicError::ServiceError(
+            "Bad request".to_string(),
+            "no symbol".to_string(),
+            None
+        )));
+    }
+}
@@ -1,39 +1,147 @@
+//! Access tokens for the Public API, persisted in `~/.public/creds.json`
+//! and minted from a secret stored in a `SecretBackend`, Bitwarden or AWS SSM.
+//!
+//! Where files or secret backends aren't available, like CI or Docker:
+//! - `PUBLIC_TRADING_SECRET` is used as the public secret, the backend is never called.
+//! - `PUBLIC_TRADING_TOKEN` is taken as a valid, pre-minted access token.
+//!
+//! Tokens held in memory are overwritten with zeros when they are dropped or
+//! replaced, so they don't linger in freed memory or end up in a core dump.
+
 use super::PUBLIC_DIR;
-use crate::bitwarden::Bitwarden;
+use crate::secrets::{BackendConfig, SecretBackend};
 
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::env;
+use std::future::Future;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{fs, fs::OpenOptions};
+use tokio::sync::{OnceCell, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 const CREDS_FILE: &str = "creds.json";
+pub const DEFAULT_PROFILE: &str = "default";
 const BW_SECRET_NAME: &str = "public_trading_secret_token";
+const SECRET_VAR: &str = "PUBLIC_TRADING_SECRET";
+const TOKEN_VAR: &str = "PUBLIC_TRADING_TOKEN";
 const TOKEN_TTL: i64 = 60;
 const TOKEN_REFRESH: i64 = TOKEN_TTL - 1;
+/// How often the background task mints a token, ahead of `TOKEN_REFRESH`.
+pub const TOKEN_REFRESH_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs((TOKEN_REFRESH as u64 - 5) * 60);
 
 pub struct Creds {
     data: Option<CredsData>,
+    /// Where tokens are persisted, none to keep them in memory.
+    file: Option<PathBuf>,
+    /// Secret holding the public secret of the account.
+    secret_name: String,
+    /// Connected on the first mint unless injected, Bitwarden keeps its
+    /// secrets cached after that.
+    backend: OnceCell<Box<dyn SecretBackend>>,
+}
+
+/// Creds of one account setup, like a brokerage and an IRA each with their
+/// own secret. The default profile uses `creds.json` and the
+/// `public_trading_secret_token` secret, others `creds_{name}.json` and
+/// `public_trading_secret_token_{name}`.
+pub struct CredsProfile {
+    pub name: String,
+    pub creds: Creds,
+}
+
+impl CredsProfile {
+    pub fn load(name: &str) -> CredsProfile {
+        CredsProfile {
+            name: name.to_string(),
+            creds: Creds::from_file(public_creds_path(name), secret_name(name)),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 struct CredsData {
     token: String,
+    #[zeroize(skip)]
     token_ttl: DateTime<Utc>,
 }
 
 impl Creds {
-    pub fn new() -> Creds {
-        let mut creds = Creds { data: None };
-        if let Err(e) = creds.load_creds_from_file() {
+    fn from_file(file: PathBuf, secret_name: String) -> Creds {
+        let mut creds = Creds {
+            data: None,
+            file: Some(file),
+            secret_name,
+            backend: OnceCell::new(),
+        };
+        creds.load(env::var(TOKEN_VAR).ok());
+
+        creds
+    }
+
+    /// Takes `env_token` as a fresh token if set, otherwise loads the creds file.
+    fn load(&mut self, env_token: Option<String>) {
+        if let Some(token) = env_token {
+            info!("Using access token from {TOKEN_VAR}");
+            self.refresh(&token);
+            return;
+        }
+        if let Err(e) = self.load_creds_from_file() {
             warn!("Cannot load public creds from file: {e}");
         }
+    }
 
-        creds
+    /// Creds holding a valid token, without touching the creds file.
+    #[cfg(test)]
+    pub fn with_token(token: &str) -> Creds {
+        Creds {
+            data: Some(CredsData {
+                token: token.to_string(),
+                token_ttl: Utc::now() + Duration::minutes(TOKEN_REFRESH),
+            }),
+            file: None,
+            secret_name: BW_SECRET_NAME.to_string(),
+            backend: OnceCell::new(),
+        }
+    }
+
+    /// Uses `backend` for the public secret instead of picking one from the environment.
+    pub fn with_backend(self, backend: Box<dyn SecretBackend>) -> Creds {
+        Creds {
+            backend: OnceCell::new_with(Some(backend)),
+            ..self
+        }
+    }
+
+    /// Spawns a task minting a new token with `refresh_fn` every `interval`,
+    /// so API calls don't stall on an expired token. A failed refresh is logged
+    /// and tried again at the next tick, `PublicClient` still refreshes inline
+    /// if the token lapses.
+    pub fn start_refresh_task<F, Fut>(
+        creds: Arc<RwLock<Creds>>,
+        interval: std::time::Duration,
+        refresh_fn: F,
+    ) -> JoinHandle<()>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String>> + Send,
+    {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match refresh_fn().await {
+                    Ok(token) => creds.write().await.refresh(&token),
+                    Err(e) => error!("Background token refresh failed: {e}"),
+                }
+            }
+        })
     }
 
     pub fn access_token(&self) -> Option<&str> {
@@ -68,6 +176,9 @@ impl Creds {
     }
 
     fn save_creds_to_file(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
         let creds_data = if let Some(creds_data) = &self.data {
             creds_data
         } else {
@@ -80,30 +191,227 @@ impl Creds {
             .write(true)
             .truncate(true)
             .create(true)
-            .open(public_creds_path())?;
+            .open(file)?;
         let _ = creds_file.write(data.as_bytes())?;
 
         Ok(())
     }
 
     fn load_creds_from_file(&mut self) -> Result<()> {
-        let data = fs::read_to_string(public_creds_path())?;
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let data = fs::read_to_string(file)?;
         let creds: CredsData = serde_json::from_str(&data)?;
         self.data = Some(creds);
 
         Ok(())
     }
 
+    /// The public secret from `PUBLIC_TRADING_SECRET`, or else from the secret backend.
     pub async fn public_secret(&self) -> Result<String> {
-        let bw = Bitwarden::new().await?;
-        let (public_secret, _note) = bw.get_secret(BW_SECRET_NAME).await?;
+        resolve_secret(env::var(SECRET_VAR).ok(), || async {
+            let backend = self
+                .backend
+                .get_or_try_init(|| async { BackendConfig::from_env()?.connect().await })
+                .await?;
 
-        Ok(public_secret)
+            backend.get_secret(&self.secret_name).await
+        })
+        .await
     }
 }
 
-fn public_creds_path() -> PathBuf {
-    let home_dir = env::home_dir().unwrap_or(PathBuf::new());
+async fn resolve_secret<F, Fut>(env_secret: Option<String>, backend: F) -> Result<String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    if let Some(secret) = env_secret {
+        return Ok(secret);
+    }
+
+    backend().await
+}
+
+fn public_creds_path(profile: &str) -> PathBuf {
+    let home_dir = env::home_dir().unwrap_or_default();
 
-    home_dir.join(PathBuf::from(format!("{PUBLIC_DIR}/{CREDS_FILE}")))
+    profile_creds_path(&home_dir.join(PUBLIC_DIR), profile)
+}
+
+fn profile_creds_path(dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        return dir.join(CREDS_FILE);
+    }
+
+    dir.join(format!("creds_{profile}.json"))
+}
+
+fn secret_name(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        return BW_SECRET_NAME.to_string();
+    }
+
+    format!("{BW_SECRET_NAME}_{profile}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Lets the spawned refresh task run until it waits again.
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[test]
+    fn test_creds_data_zeroize() {
+        fn zeroized_on_drop<T: ZeroizeOnDrop>(_: &T) {}
+
+        let mut data = CredsData {
+            token: "secret-access-token".to_string(),
+            token_ttl: Utc::now(),
+        };
+        zeroized_on_drop(&data);
+        let (ptr, capacity) = (data.token.as_ptr(), data.token.capacity());
+
+        // What dropping does before the buffer is freed. Reading it after the
+        // drop would read freed memory, so the buffer is checked while still allocated.
+        data.zeroize();
+        assert_eq!(data.token.capacity(), capacity);
+        let buffer = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(buffer.iter().all(|b| *b == 0));
+        assert!(data.token.is_empty());
+    }
+
+    #[test]
+    fn test_profile_creds() {
+        let dir = env::temp_dir().join(format!("public_creds_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let ttl = (Utc::now() + Duration::minutes(30)).to_rfc3339();
+        for (profile, token) in [("brokerage", "brokerage-token"), ("ira", "ira-token")] {
+            let data = format!(r#"{{"token":"{token}","token_ttl":"{ttl}"}}"#);
+            fs::write(profile_creds_path(&dir, profile), data).unwrap();
+        }
+
+        let brokerage = Creds::from_file(
+            profile_creds_path(&dir, "brokerage"),
+            secret_name("brokerage"),
+        );
+        let ira = Creds::from_file(profile_creds_path(&dir, "ira"), secret_name("ira"));
+        assert_eq!(brokerage.access_token(), Some("brokerage-token"));
+        assert_eq!(ira.access_token(), Some("ira-token"));
+        assert_eq!(ira.secret_name, "public_trading_secret_token_ira");
+
+        let missing = Creds::from_file(profile_creds_path(&dir, "roth"), secret_name("roth"));
+        assert_eq!(missing.access_token(), None);
+
+        assert_eq!(
+            profile_creds_path(&dir, DEFAULT_PROFILE),
+            dir.join("creds.json")
+        );
+        assert_eq!(secret_name(DEFAULT_PROFILE), BW_SECRET_NAME);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_env_secret_skips_bitwarden() {
+        let bitwarden_calls = AtomicUsize::new(0);
+        let bitwarden = || async {
+            bitwarden_calls.fetch_add(1, Ordering::SeqCst);
+            Ok("bitwarden-secret".to_string())
+        };
+
+        let secret = resolve_secret(Some("env-secret".to_string()), bitwarden)
+            .await
+            .unwrap();
+        assert_eq!(secret, "env-secret");
+        assert_eq!(bitwarden_calls.load(Ordering::SeqCst), 0);
+
+        let secret = resolve_secret(None, bitwarden).await.unwrap();
+        assert_eq!(secret, "bitwarden-secret");
+        assert_eq!(bitwarden_calls.load(Ordering::SeqCst), 1);
+    }
+
+    struct MockBackend;
+
+    impl SecretBackend for MockBackend {
+        fn get_secret<'a>(
+            &'a self,
+            key: &'a str,
+        ) -> futures::future::BoxFuture<'a, Result<String>> {
+            Box::pin(async move { Ok(format!("{key}-secret")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_injected_backend() {
+        let creds = Creds::with_token("test-token").with_backend(Box::new(MockBackend));
+        assert_eq!(
+            creds.public_secret().await.unwrap(),
+            format!("{BW_SECRET_NAME}-secret")
+        );
+    }
+
+    #[test]
+    fn test_env_token() {
+        let data = Some(CredsData {
+            token: "file-token".to_string(),
+            token_ttl: Utc::now() + Duration::minutes(5),
+        });
+        let mut creds = Creds {
+            data,
+            file: None,
+            secret_name: BW_SECRET_NAME.to_string(),
+            backend: OnceCell::new(),
+        };
+        creds.load(Some("env-token".to_string()));
+        assert_eq!(creds.access_token(), Some("env-token"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_refresh_task() {
+        let creds = Arc::new(RwLock::new(Creds::with_token("initial")));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let interval = std::time::Duration::from_secs(50 * 60);
+        let task = Creds::start_refresh_task(creds.clone(), interval, move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if n == 2 {
+                    bail!("bitwarden unavailable");
+                }
+                Ok(format!("token-{n}"))
+            }
+        });
+        settle().await;
+
+        tokio::time::advance(interval / 2).await;
+        settle().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(creds.read().await.access_token(), Some("initial"));
+
+        tokio::time::advance(interval / 2).await;
+        settle().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(creds.read().await.access_token(), Some("token-1"));
+
+        // A failed refresh keeps the current token, the next tick tries again.
+        tokio::time::advance(interval).await;
+        settle().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(creds.read().await.access_token(), Some("token-1"));
+
+        tokio::time::advance(interval).await;
+        settle().await;
+        assert_eq!(creds.read().await.access_token(), Some("token-3"));
+
+        task.abort();
+    }
 }
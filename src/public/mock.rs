@@ -0,0 +1,51 @@
+use super::PublicClient;
+
+use reqwest::Url;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use warp::Filter;
+
+/// Serves `routes` on a random local port.
+pub(crate) async fn mock_server<F>(routes: F) -> Url
+where
+    F: warp::Filter + Clone + Send + Sync + 'static,
+    F::Extract: warp::Reply,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(warp::serve(routes).incoming(listener).run());
+
+    format!("http://{addr}").parse().unwrap()
+}
+
+/// Client for account "TEST" talking to `base_url`, with no retries.
+pub(crate) fn mock_client(base_url: Url) -> PublicClient {
+    PublicClient::mock(base_url)
+}
+
+/// Greeks route of account "TEST", answering every OSI symbol asked for with the
+/// `delta` of the symbol and an `implied_volatility` shared by all of them.
+pub(crate) fn mock_greeks<F>(
+    delta: F,
+    implied_volatility: f64,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Fn(&str) -> f64 + Clone + Send + Sync + 'static,
+{
+    warp::path!("userapigateway" / "option-details" / "TEST" / "greeks")
+        .and(warp::get())
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |params: HashMap<String, String>| {
+            let greeks: Vec<Value> = params["osiSymbols"]
+                .split(',')
+                .map(|s| {
+                    json!({ "symbol": s, "greeks": {
+                        "delta": delta(s).to_string(), "gamma": "0.01", "theta": "-0.1",
+                        "vega": "0.2", "rho": "0.01",
+                        "impliedVolatility": implied_volatility.to_string()
+                    } })
+                })
+                .collect();
+            warp::reply::json(&json!({ "greeks": greeks }))
+        })
+}
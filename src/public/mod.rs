@@ -1,8 +1,23 @@
+mod circuit_breaker;
 mod creds;
+#[cfg(test)]
+pub(crate) mod mock;
 mod model;
+#[cfg(feature = "news")]
+mod news;
+#[allow(clippy::module_inception)]
 mod public;
+mod rate_limiter;
+mod retry;
+mod stream;
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
 pub use model::*;
+#[cfg(feature = "news")]
+pub use news::{ALPACA_NEWS_API, NewsConfig, NewsItem};
 pub use public::*;
+pub use rate_limiter::{RateLimit, RateLimiter};
+pub use retry::RetryPolicy;
+pub use stream::QuoteStreamEvent;
 
 pub const PUBLIC_DIR: &str = ".public";
 const PUBLIC_API: &str = "https://api.public.com";
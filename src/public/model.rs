@@ -1,5 +1,13 @@
-use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use crate::osi::OsiSymbol;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
 use ts_rs::TS;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -25,15 +33,75 @@ pub enum AccountType {
     RothIra,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-#[allow(non_camel_case_types)]
+impl AccountType {
+    /// Name in the API, the same as the serde representation, for tags and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Brokerage => "BROKERAGE",
+            Self::HighYield => "HIGH_YIELD",
+            Self::BondAccount => "BOND_ACCOUNT",
+            Self::RiaAsset => "RIA_ASSET",
+            Self::Treasury => "TREASURY",
+            Self::TraditionalIra => "TRADITIONAL_IRA",
+            Self::RothIra => "ROTH_IRA",
+        }
+    }
+}
+
+impl fmt::Display for AccountType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Options approval of an account, higher levels allow riskier strategies.
+/// The API reports level 0 as "NONE".
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum OptionsLevel {
-    None,
-    Level_1,
-    Level_2,
-    Level_3,
-    Level_4,
+    #[serde(rename = "NONE")]
+    Level0,
+    #[serde(rename = "LEVEL_1")]
+    Level1,
+    #[serde(rename = "LEVEL_2")]
+    Level2,
+    #[serde(rename = "LEVEL_3")]
+    Level3,
+    #[serde(rename = "LEVEL_4")]
+    Level4,
+}
+
+impl OptionsLevel {
+    /// Spreads, like iron condors, need level 3.
+    pub fn can_trade_spreads(&self) -> bool {
+        *self >= OptionsLevel::Level3
+    }
+}
+
+impl FromStr for OptionsLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NONE" => Ok(Self::Level0),
+            "LEVEL_1" => Ok(Self::Level1),
+            "LEVEL_2" => Ok(Self::Level2),
+            "LEVEL_3" => Ok(Self::Level3),
+            "LEVEL_4" => Ok(Self::Level4),
+            _ => Err(format!("Unknown options level {s}")),
+        }
+    }
+}
+
+impl fmt::Display for OptionsLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionsLevel::Level0 => write!(f, "NONE"),
+            OptionsLevel::Level1 => write!(f, "LEVEL_1"),
+            OptionsLevel::Level2 => write!(f, "LEVEL_2"),
+            OptionsLevel::Level3 => write!(f, "LEVEL_3"),
+            OptionsLevel::Level4 => write!(f, "LEVEL_4"),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -170,6 +238,41 @@ pub struct BuyingPower {
     pub options_buying_power: String,
 }
 
+/// Cash and buying power of an account, parsed out of its portfolio.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalance {
+    pub cash: f64,
+    /// Sum of every equity bucket: stock, cash, options, bonds and crypto.
+    pub portfolio_value: f64,
+    /// Margin buying power, reported by Public as `buyingPower`.
+    pub day_trading_buying_power: f64,
+    pub options_buying_power: f64,
+}
+
+impl TryFrom<&AccountPortfolio> for AccountBalance {
+    type Error = std::num::ParseFloatError;
+
+    fn try_from(portfolio: &AccountPortfolio) -> Result<Self, Self::Error> {
+        let mut cash = 0.0;
+        let mut portfolio_value = 0.0;
+        for equity in &portfolio.equity {
+            let value = equity.value.parse::<f64>()?;
+            if let EquityType::Cash = equity.equity_type {
+                cash += value;
+            }
+            portfolio_value += value;
+        }
+
+        Ok(AccountBalance {
+            cash,
+            portfolio_value,
+            day_trading_buying_power: portfolio.buying_power.buying_power.parse()?,
+            options_buying_power: portfolio.buying_power.options_buying_power.parse()?,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SecurityType {
@@ -269,7 +372,7 @@ pub enum OrderType {
     StopLimit,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, TS)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "UPPERCASE")]
 #[ts(export)]
 pub enum OrderSide {
@@ -277,7 +380,30 @@ pub enum OrderSide {
     Sell,
 }
 
-#[derive(Debug, Deserialize, Serialize, TS)]
+impl fmt::Display for OrderSide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderSide::Buy => write!(f, "buy"),
+            OrderSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+impl FromStr for OrderSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("buy") {
+            Ok(OrderSide::Buy)
+        } else if s.eq_ignore_ascii_case("sell") {
+            Ok(OrderSide::Sell)
+        } else {
+            Err(format!("Unknown order side {s}"))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[ts(export)]
 pub enum OrderStatus {
@@ -350,6 +476,62 @@ pub struct Order {
     pub reject_reason: Option<String>,
 }
 
+/// Trading session of one day, without a window on holidays and weekends.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketHours {
+    pub date: NaiveDate,
+    pub is_open: bool,
+    pub open: Option<DateTime<Utc>>,
+    pub close: Option<DateTime<Utc>>,
+}
+
+impl MarketHours {
+    pub fn is_currently_open(&self) -> bool {
+        self.is_open_at(Utc::now())
+    }
+
+    /// Whether `time` falls in the session, the close excluded.
+    pub fn is_open_at(&self, time: DateTime<Utc>) -> bool {
+        match (self.is_open, self.open, self.close) {
+            (true, Some(open), Some(close)) => open <= time && time < close,
+            _ => false,
+        }
+    }
+}
+
+/// Result of placing or looking up an order.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderReceipt {
+    pub order_id: String,
+    pub status: OrderStatus,
+    pub filled_at: Option<DateTime<Utc>>,
+    /// When the order reached a final state: filled, cancelled, rejected or expired.
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Average price of the filled quantity.
+    pub fill_price: Option<f64>,
+}
+
+impl From<&Order> for OrderReceipt {
+    fn from(order: &Order) -> Self {
+        let completed_at: Option<DateTime<Utc>> =
+            order.closed_at.as_deref().and_then(|t| t.parse().ok());
+        let filled_at = match order.status {
+            OrderStatus::Filled => completed_at,
+            _ => None,
+        };
+
+        OrderReceipt {
+            order_id: order.order_id.clone(),
+            status: order.status.clone(),
+            filled_at,
+            completed_at,
+            fill_price: order.average_price.as_deref().and_then(|p| p.parse().ok()),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HistoryTransaction {
@@ -416,40 +598,54 @@ pub enum InstrumentType {
     Index,
 }
 
+impl InstrumentType {
+    pub const ALL: [InstrumentType; 8] = [
+        Self::Equity,
+        Self::Option,
+        Self::MultiLegInstrument,
+        Self::Crypto,
+        Self::Alt,
+        Self::Treasury,
+        Self::Bond,
+        Self::Index,
+    ];
+
+    /// Name in the API, the same as the serde representation, for URL paths and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Equity => "EQUITY",
+            Self::Option => "OPTION",
+            Self::MultiLegInstrument => "MULTI_LEG_INSTRUMENT",
+            Self::Crypto => "CRYPTO",
+            Self::Alt => "ALT",
+            Self::Treasury => "TREASURY",
+            Self::Bond => "BOND",
+            Self::Index => "INDEX",
+        }
+    }
+}
+
+impl From<InstrumentType> for &'static str {
+    fn from(instrument_type: InstrumentType) -> Self {
+        instrument_type.as_str()
+    }
+}
+
+/// Parses the API name, like "MULTI_LEG_INSTRUMENT", or the variant name, like "MultiLegInstrument".
 impl FromStr for InstrumentType {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Equity" => Ok(Self::Equity),
-            "EQUITY" => Ok(Self::Equity),
-            "Option" => Ok(Self::Option),
-            "OPTION" => Ok(Self::Option),
-            "MultiLegInstrument" => Ok(Self::MultiLegInstrument),
-            "Crypto" => Ok(Self::Crypto),
-            "CRYPTO" => Ok(Self::Crypto),
-            "Alt" => Ok(Self::Alt),
-            "Treasury" => Ok(Self::Treasury),
-            "Bond" => Ok(Self::Bond),
-            "Index" => Ok(Self::Index),
-            "INDEX" => Ok(Self::Index),
-            _ => Err(format!("Unknown type")),
-        }
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s || format!("{t:?}") == s)
+            .ok_or_else(|| "Unknown type".to_string())
     }
 }
 
 impl fmt::Display for InstrumentType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            InstrumentType::Equity => write!(f, "EQUITY"),
-            InstrumentType::Option => write!(f, "OPTION"),
-            InstrumentType::MultiLegInstrument => write!(f, "MULTI_LEG_INSTRUMENT"),
-            InstrumentType::Crypto => write!(f, "CRYPTO"),
-            InstrumentType::Alt => write!(f, "ALT"),
-            InstrumentType::Treasury => write!(f, "TREASURY"),
-            InstrumentType::Bond => write!(f, "BOND"),
-            InstrumentType::Index => write!(f, "INDEX"),
-        }
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -459,23 +655,55 @@ pub struct Instrument {
     pub symbol: String,
     #[serde(rename = "type")]
     pub instrument_type: InstrumentType,
+    /// Display name, like "QCOM $138 Put Feb 20, '26". Set by Public, leave empty in requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Instrument {
+    /// Option contract of `osi`, to request quotes or place orders with.
+    pub fn from_osi_symbol(osi: &OsiSymbol) -> Instrument {
+        Instrument {
+            symbol: osi.to_string(),
+            instrument_type: InstrumentType::Option,
+            name: None,
+        }
+    }
+
+    /// Crypto asset of `symbol`, like "BTC".
+    pub fn crypto(symbol: &str) -> Instrument {
+        Instrument {
+            symbol: symbol.to_string(),
+            instrument_type: InstrumentType::Crypto,
+            name: None,
+        }
+    }
+
+    /// Stock of `ticker`, like "QCOM".
+    pub fn equity(ticker: &str) -> Instrument {
+        Instrument {
+            symbol: ticker.to_string(),
+            instrument_type: InstrumentType::Equity,
+            name: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum QuoteOutcome {
     Success,
     Unknown,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OneDayChange {
     pub change: Option<String>,
     pub percent_change: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OptionDetails {
     pub greeks: Option<Greeks>,
@@ -483,17 +711,20 @@ pub struct OptionDetails {
     pub mid_price: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Quote {
     pub instrument: Instrument,
     pub outcome: QuoteOutcome,
-    pub last: String,
+    #[serde(deserialize_with = "de_f64")]
+    pub last: f64,
     pub last_timestamp: String,
-    pub bid: String,
+    #[serde(deserialize_with = "de_f64")]
+    pub bid: f64,
     pub bid_size: Option<u64>,
     pub bid_timestamp: String,
-    pub ask: String,
+    #[serde(deserialize_with = "de_f64")]
+    pub ask: f64,
     pub ask_size: Option<u64>,
     pub ask_timestamp: String,
     pub volume: u64,
@@ -503,6 +734,86 @@ pub struct Quote {
     pub option_details: Option<OptionDetails>,
 }
 
+impl Quote {
+    /// Strike of an option quote, read from its OSI symbol.
+    pub fn strike(&self) -> Option<f64> {
+        OsiSymbol::parse(&self.instrument.symbol)
+            .ok()
+            .map(|parts| parts.strike)
+    }
+
+    pub fn mid_price(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask - self.bid
+    }
+
+    /// Spread as a percentage of the mid price.
+    /// Infinite when there is no market (bid and ask both zero).
+    pub fn spread_pct(&self) -> f64 {
+        let mid = self.mid_price();
+        if mid <= 0.0 {
+            return f64::INFINITY;
+        }
+        self.spread() / mid * 100.0
+    }
+
+    /// Whether the bid is older than `max_age`. A missing or unreadable
+    /// timestamp counts as stale.
+    pub fn is_stale(&self, max_age: std::time::Duration) -> bool {
+        let Ok(bid_time) = self.bid_timestamp.parse::<DateTime<Utc>>() else {
+            return true;
+        };
+        let Ok(max_age) = chrono::Duration::from_std(max_age) else {
+            return false;
+        };
+
+        Utc::now() - bid_time > max_age
+    }
+
+    /// Whether the quote can be acted upon, see `validity_reason`.
+    pub fn is_valid_market(&self) -> bool {
+        self.validity_reason().is_none()
+    }
+
+    /// Why the quote isn't a market to trade on: no bid nor ask, a bid above
+    /// the ask, or a bid older than 5 minutes. None for a valid market.
+    pub fn validity_reason(&self) -> Option<&'static str> {
+        if self.bid <= 0.0 && self.ask <= 0.0 {
+            Some("no bid or ask")
+        } else if self.bid > self.ask {
+            Some("bid above ask")
+        } else if self.is_stale(MAX_MARKET_AGE) {
+            Some("stale quote")
+        } else {
+            None
+        }
+    }
+}
+
+/// Age from which a quote is not a valid market anymore.
+const MAX_MARKET_AGE: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Public sends most prices as quoted strings ("71.00"), but accept bare numbers too.
+fn de_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(f64),
+    }
+
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::Number(n) => Ok(n),
+        StringOrNumber::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OptionChain {
@@ -511,6 +822,156 @@ pub struct OptionChain {
     pub puts: Vec<Quote>,
 }
 
+impl OptionChain {
+    /// Strike closest to `underlying_price`, taken from the calls
+    /// (or the puts, for a chain without calls).
+    pub fn atm_strike(&self, underlying_price: f64) -> Option<f64> {
+        let quotes = if self.calls.is_empty() {
+            &self.puts
+        } else {
+            &self.calls
+        };
+
+        quotes.iter().filter_map(Quote::strike).min_by(|a, b| {
+            (a - underlying_price)
+                .abs()
+                .total_cmp(&(b - underlying_price).abs())
+        })
+    }
+
+    /// Quotes whose absolute delta in `greeks_map`, keyed by OSI symbol, is within
+    /// `[min_delta, max_delta]`. Quotes without greeks are dropped.
+    pub fn filter_by_delta(
+        self,
+        greeks_map: &HashMap<String, OptionGreeks>,
+        min_delta: f64,
+        max_delta: f64,
+    ) -> OptionChain {
+        let in_range = |q: &Quote| {
+            greeks_map
+                .get(&q.instrument.symbol)
+                .is_some_and(|g| (min_delta..=max_delta).contains(&g.greeks.delta.abs()))
+        };
+
+        OptionChain {
+            base_symbol: self.base_symbol,
+            calls: self.calls.into_iter().filter(in_range).collect(),
+            puts: self.puts.into_iter().filter(in_range).collect(),
+        }
+    }
+
+    /// Calls struck above `strike`, in chain order.
+    pub fn calls_above(&self, strike: f64) -> Vec<&Quote> {
+        self.calls
+            .iter()
+            .filter(|q| q.strike().is_some_and(|s| s > strike))
+            .collect()
+    }
+
+    /// Open interest summed over the calls and the puts.
+    pub fn total_open_interest(&self) -> u64 {
+        sum_open_interest(&self.calls) + sum_open_interest(&self.puts)
+    }
+
+    /// Put open interest over call open interest.
+    /// Infinite when no calls are open.
+    pub fn put_call_ratio(&self) -> f64 {
+        ratio(
+            sum_open_interest(&self.puts),
+            sum_open_interest(&self.calls),
+        )
+    }
+
+    /// Call and put volume summed at each strike, lowest strike first.
+    pub fn volume_profile(&self) -> BTreeMap<OrderedFloat<f64>, u64> {
+        self.strike_profile(|q| q.volume)
+    }
+
+    /// Call and put open interest summed at each strike, lowest strike first.
+    pub fn open_interest_profile(&self) -> BTreeMap<OrderedFloat<f64>, u64> {
+        self.strike_profile(|q| q.open_interest.unwrap_or(0))
+    }
+
+    fn strike_profile(&self, value: impl Fn(&Quote) -> u64) -> BTreeMap<OrderedFloat<f64>, u64> {
+        let mut profile = BTreeMap::new();
+        for quote in self.calls.iter().chain(&self.puts) {
+            if let Some(strike) = quote.strike() {
+                *profile.entry(OrderedFloat(strike)).or_default() += value(quote);
+            }
+        }
+
+        profile
+    }
+
+    /// Put volume over call volume.
+    /// Infinite when no calls traded.
+    pub fn put_call_volume_ratio(&self) -> f64 {
+        let volume = |quotes: &[Quote]| quotes.iter().map(|q| q.volume).sum::<u64>();
+        ratio(volume(&self.puts), volume(&self.calls))
+    }
+
+    /// Puts struck below `strike`, in chain order.
+    pub fn puts_below(&self, strike: f64) -> Vec<&Quote> {
+        self.puts
+            .iter()
+            .filter(|q| q.strike().is_some_and(|s| s < strike))
+            .collect()
+    }
+
+    /// Quotes of the `near` and `far` chains of one underlying lined up by the strike
+    /// of their OSI symbols, lowest strike first. A strike missing from a chain leaves
+    /// its quotes None.
+    pub fn merge_expirations(near: &OptionChain, far: &OptionChain) -> Vec<ExpirationPair> {
+        let mut pairs: BTreeMap<OrderedFloat<f64>, ExpirationPair> = BTreeMap::new();
+        let legs: [(&[Quote], PairLeg); 4] = [
+            (&near.calls, |p| &mut p.near_call),
+            (&far.calls, |p| &mut p.far_call),
+            (&near.puts, |p| &mut p.near_put),
+            (&far.puts, |p| &mut p.far_put),
+        ];
+        for (quotes, leg) in legs {
+            for quote in quotes {
+                let Some(strike) = quote.strike() else {
+                    continue;
+                };
+                let pair = pairs
+                    .entry(OrderedFloat(strike))
+                    .or_insert_with(|| ExpirationPair {
+                        strike,
+                        ..ExpirationPair::default()
+                    });
+                *leg(pair) = Some(quote.clone());
+            }
+        }
+
+        pairs.into_values().collect()
+    }
+}
+
+/// Field of an `ExpirationPair` holding one leg.
+type PairLeg = fn(&mut ExpirationPair) -> &mut Option<Quote>;
+
+/// Calls and puts of one strike in two expirations, for calendar spreads.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ExpirationPair {
+    pub strike: f64,
+    pub near_call: Option<Quote>,
+    pub far_call: Option<Quote>,
+    pub near_put: Option<Quote>,
+    pub far_put: Option<Quote>,
+}
+
+fn sum_open_interest(quotes: &[Quote]) -> u64 {
+    quotes.iter().map(|q| q.open_interest.unwrap_or(0)).sum()
+}
+
+fn ratio(puts: u64, calls: u64) -> f64 {
+    if calls == 0 {
+        return f64::INFINITY;
+    }
+    puts as f64 / calls as f64
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub enum MarketSession {
     #[default]
@@ -521,12 +982,33 @@ pub enum MarketSession {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Greeks {
-    pub delta: String,
-    pub gamma: String,
-    pub theta: String,
-    pub vega: String,
-    pub rho: String,
-    pub implied_volatility: String,
+    #[serde(deserialize_with = "de_f64")]
+    pub delta: f64,
+    #[serde(deserialize_with = "de_f64")]
+    pub gamma: f64,
+    /// Annualized, see `OptionGreeks::theta_daily`.
+    #[serde(deserialize_with = "de_f64")]
+    pub theta: f64,
+    /// Per unit of implied volatility, 100 IV points.
+    #[serde(deserialize_with = "de_f64")]
+    pub vega: f64,
+    /// Per unit of the risk-free rate.
+    #[serde(deserialize_with = "de_f64")]
+    pub rho: f64,
+    #[serde(deserialize_with = "de_f64")]
+    pub implied_volatility: f64,
+}
+
+/// Contract and market inputs the second-order greeks of `OptionGreeks` need,
+/// on top of the greeks and IV Public reports.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionGreeksContext {
+    pub underlying_price: f64,
+    pub strike: f64,
+    /// Time to expiration in years.
+    pub dte_years: f64,
+    /// Annualized, like `AnalysisConfig::risk_free_rate`.
+    pub risk_free_rate: f64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -535,6 +1017,72 @@ pub struct OptionGreeks {
     pub greeks: Greeks,
 }
 
+impl OptionGreeks {
+    /// Theta spread over calendar days, for an annualized theta.
+    pub fn theta_daily(&self) -> f64 {
+        self.greeks.theta / 365.0
+    }
+
+    /// Dollar change of `quantity` contracts, negative when short, for a 1% move
+    /// in the underlying: delta * price * quantity * contract size / 100.
+    pub fn dollar_delta(&self, underlying_price: f64, quantity: i32, contract_size: u32) -> f64 {
+        self.greeks.delta * underlying_price * quantity as f64 * contract_size as f64 / 100.0
+    }
+
+    /// Dollar change of `quantity` contracts for a 1 point move in IV, vega / 100
+    /// being per point.
+    pub fn dollar_vega(&self, quantity: i32, contract_size: u32) -> f64 {
+        self.greeks.vega / 100.0 * quantity as f64 * contract_size as f64
+    }
+
+    /// Dollar change of `quantity` contracts over a calendar day, from `theta_daily`.
+    pub fn dollar_theta(&self, quantity: i32, contract_size: u32) -> f64 {
+        self.theta_daily() * quantity as f64 * contract_size as f64
+    }
+
+    /// Implied volatility as an annualized fraction (0.25 is 25%), as Public reports it.
+    pub fn annualized_iv(&self) -> f64 {
+        self.greeks.implied_volatility
+    }
+
+    /// Change in delta for a 1% increase in IV, `vega / S * (1 - d1 / (sigma * sqrt(T)))`
+    /// with vega per point. Same for calls and puts.
+    pub fn vanna(&self, context: &OptionGreeksContext) -> f64 {
+        let (sigma, vol_t) = self.vol_t(context);
+        let (d1, _) = self.d1_d2(context, sigma);
+
+        self.greeks.vega / 100.0 / context.underlying_price * (1.0 - d1 / vol_t)
+    }
+
+    /// Change in delta as one calendar day passes, like theta is quoted:
+    /// `-gamma * S * (2rT - d2 * sigma * sqrt(T)) / 2T` per year.
+    /// Same for calls and puts, without dividends.
+    pub fn charm(&self, context: &OptionGreeksContext) -> f64 {
+        let (sigma, vol_t) = self.vol_t(context);
+        let (_, d2) = self.d1_d2(context, sigma);
+        let (r, t) = (context.risk_free_rate, context.dte_years);
+        let per_year =
+            -self.greeks.gamma * context.underlying_price * (2.0 * r * t - d2 * vol_t) / (2.0 * t);
+
+        per_year / 365.0
+    }
+
+    fn vol_t(&self, context: &OptionGreeksContext) -> (f64, f64) {
+        let sigma = self.greeks.implied_volatility;
+        (sigma, sigma * context.dte_years.sqrt())
+    }
+
+    fn d1_d2(&self, context: &OptionGreeksContext, sigma: f64) -> (f64, f64) {
+        crate::pricing::d1_d2(
+            context.underlying_price,
+            context.strike,
+            context.risk_free_rate,
+            context.dte_years,
+            sigma,
+        )
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum OptionType {
@@ -551,16 +1099,27 @@ impl std::fmt::Display for OptionType {
     }
 }
 
+impl OptionType {
+    pub fn is_call(&self) -> bool {
+        *self == OptionType::Call
+    }
+
+    pub fn is_put(&self) -> bool {
+        *self == OptionType::Put
+    }
+}
+
+/// Parses "Call" or "Put" in any case, as the API and the instrument names write them.
 impl FromStr for OptionType {
-    type Err = ();
+    type Err = String;
 
-    fn from_str(s: &str) -> Result<OptionType, ()> {
-        match s {
-            "Call" => Ok(OptionType::Call),
-            "CALL" => Ok(OptionType::Call),
-            "Put" => Ok(OptionType::Put),
-            "PUT" => Ok(OptionType::Put),
-            _ => Err(()),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("call") {
+            Ok(OptionType::Call)
+        } else if s.eq_ignore_ascii_case("put") {
+            Ok(OptionType::Put)
+        } else {
+            Err(format!("Unknown option type {s}"))
         }
     }
 }
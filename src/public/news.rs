@@ -0,0 +1,198 @@
+use super::public::{PublicError, handle_response};
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// News endpoint of the Alpaca market data API.
+pub const ALPACA_NEWS_API: &str = "https://data.alpaca.markets/v1beta1/news";
+const ALPACA_KEY_ID_HEADER: &str = "APCA-API-KEY-ID";
+const ALPACA_SECRET_KEY_HEADER: &str = "APCA-API-SECRET-KEY";
+
+/// Where `PublicClient::get_news` reads news from, the `[news]` config table.
+/// Public has no news endpoint, so news comes from Alpaca, with the keys of a
+/// (free) Alpaca account.
+#[derive(Clone, Deserialize, PartialEq, Zeroize, ZeroizeOnDrop)]
+pub struct NewsConfig {
+    #[serde(default = "default_news_url")]
+    #[zeroize(skip)]
+    pub url: String,
+    #[zeroize(skip)]
+    pub key_id: String,
+    pub secret_key: String,
+}
+
+// Manual so the secret key never ends up in the logs
+impl fmt::Debug for NewsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NewsConfig")
+            .field("url", &self.url)
+            .field("key_id", &self.key_id)
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+fn default_news_url() -> String {
+    ALPACA_NEWS_API.to_string()
+}
+
+/// One article about a symbol, latest first from `PublicClient::get_news`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NewsItem {
+    pub headline: String,
+    pub source: String,
+    pub published_at: DateTime<Utc>,
+    pub url: String,
+    /// From -1.0, negative, to 1.0, positive. None when the source doesn't score articles.
+    pub sentiment: Option<f64>,
+}
+
+impl fmt::Display for NewsItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.published_at.format("%Y-%m-%d %H:%M"),
+            self.source,
+            self.headline
+        )?;
+        if let Some(sentiment) = self.sentiment {
+            write!(f, " (sentiment {sentiment:.2})")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct AlpacaNewsResponse {
+    news: Vec<AlpacaArticle>,
+}
+
+#[derive(Deserialize)]
+struct AlpacaArticle {
+    headline: String,
+    source: String,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+impl From<AlpacaArticle> for NewsItem {
+    fn from(article: AlpacaArticle) -> Self {
+        NewsItem {
+            headline: article.headline,
+            source: article.source,
+            published_at: article.created_at,
+            url: article.url.unwrap_or_default(),
+            sentiment: None,
+        }
+    }
+}
+
+impl NewsConfig {
+    /// The `limit` latest articles about `symbol`. Not sent through the rate
+    /// limiter of the client, which is for the Public API.
+    pub(super) async fn fetch(
+        &self,
+        client: &Client,
+        symbol: &str,
+        limit: u32,
+    ) -> Result<Vec<NewsItem>, PublicError> {
+        let request = client
+            .get(&self.url)
+            .query(&[
+                ("symbols", symbol),
+                ("limit", &limit.to_string()),
+                ("sort", "desc"),
+            ])
+            .header(ACCEPT, "application/json")
+            .header(ALPACA_KEY_ID_HEADER, &self.key_id)
+            .header(ALPACA_SECRET_KEY_HEADER, &self.secret_key);
+        let res = handle_response(request.send().await).await?;
+
+        match res.json::<AlpacaNewsResponse>().await {
+            Ok(news) => Ok(news.news.into_iter().map(NewsItem::from).collect()),
+            Err(e) => Err(PublicError::ServiceError(
+                "MalformedJsonResponse".to_string(),
+                format!("Couldnt parse json response: {e}"),
+                None,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::mock::{mock_client, mock_server};
+    use std::collections::HashMap;
+    use warp::Filter;
+
+    const ALPACA_NEWS: &str = include_str!("../fixtures/alpaca_news.json");
+
+    #[test]
+    fn test_parse_news() {
+        let news: AlpacaNewsResponse = serde_json::from_str(ALPACA_NEWS).unwrap();
+        let news: Vec<NewsItem> = news.news.into_iter().map(NewsItem::from).collect();
+        assert_eq!(news.len(), 2);
+        assert_eq!(
+            news[0],
+            NewsItem {
+                headline: "Lemonade Shares Jump After Raising Full-Year Guidance".to_string(),
+                source: "benzinga".to_string(),
+                published_at: "2025-11-05T13:02:11Z".parse().unwrap(),
+                url: "https://www.benzinga.com/news/25/11/48612345/lemonade-shares-jump"
+                    .to_string(),
+                sentiment: None,
+            }
+        );
+        assert_eq!(news[1].url, "");
+        assert_eq!(
+            news[0].to_string(),
+            "2025-11-05 13:02 benzinga: Lemonade Shares Jump After Raising Full-Year Guidance"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_news() {
+        let news = warp::path!("v1beta1" / "news")
+            .and(warp::get())
+            .and(warp::header::<String>(ALPACA_KEY_ID_HEADER))
+            .and(warp::header::<String>(ALPACA_SECRET_KEY_HEADER))
+            .and(warp::query::<HashMap<String, String>>())
+            .map(
+                |key_id: String, secret: String, params: HashMap<String, String>| {
+                    assert_eq!((key_id.as_str(), secret.as_str()), ("key", "secret"));
+                    assert_eq!(params["symbols"], "LMND");
+                    assert_eq!(params["limit"], "2");
+                    warp::reply::with_header(ALPACA_NEWS, "content-type", "application/json")
+                },
+            );
+        let url = mock_server(news).await;
+        let config = NewsConfig {
+            url: url.join("v1beta1/news").unwrap().to_string(),
+            key_id: "key".to_string(),
+            secret_key: "secret".to_string(),
+        };
+
+        fn zeroized_on_drop<T: ZeroizeOnDrop>(_: &T) {}
+        zeroized_on_drop(&config);
+        let debug = format!("{config:?}");
+        assert!(!debug.contains("\"secret\""), "{debug}");
+
+        let client = mock_client(url);
+        assert!(matches!(
+            client.get_news("LMND", 2).await,
+            Err(PublicError::MissingNewsConfig)
+        ));
+        let client = client.with_news(config);
+        let news = client.get_news("LMND", 2).await.unwrap();
+        assert_eq!(news.len(), 2);
+        assert_eq!(news[1].source, "reuters");
+    }
+}
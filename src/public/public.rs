@@ -1,34 +1,265 @@
 use super::PUBLIC_API;
-use super::creds::Creds;
+use super::circuit_breaker::{CircuitBreaker, is_outage};
+use super::creds::{Creds, CredsProfile, DEFAULT_PROFILE, TOKEN_REFRESH_INTERVAL};
 use super::model::*;
-
+#[cfg(feature = "news")]
+use super::news::{NewsConfig, NewsItem};
+use super::rate_limiter::{RateLimit, RateLimiter};
+use super::retry::{Attempt, RetryPolicy, is_transient_error, is_transient_status};
+use super::stream::{QuoteStream, QuoteStreamEvent};
+use crate::osi::OsiSymbol;
+use crate::secrets::SecretBackend;
+
+use chrono::NaiveDate;
+use futures::future::{BoxFuture, FutureExt, Shared, join_all};
+use futures::{Stream, StreamExt};
 use reqwest::{
-    Client, Response, Url,
+    Client, Proxy, RequestBuilder, Response, Url,
     header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
-use tracing::{debug, error, info};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{Instrument as _, debug, error, info, info_span, warn};
+use uuid::Uuid;
+
+/// Header of the id `send` gives each request, to match log lines with Public's.
+const CORRELATION_ID_HEADER: &str = "X-Correlation-ID";
+/// Most contracts the greeks endpoint accepts in one request.
+const MAX_GREEKS_PER_REQUEST: usize = 250;
+const DEFAULT_QUOTE_CHUNK_SIZE: usize = 50;
+const DEFAULT_EXPIRATION_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Bytes of an unparsable error body kept in `PublicError::StatusCode`.
+const MAX_ERROR_BODY: usize = 1024;
+/// Shortest interval of `poll_quotes`, shorter ones are raised to it.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Option expirations by underlying symbol, with the time they were fetched.
+type ExpirationCache = HashMap<String, (Vec<String>, Instant)>;
+/// `get_quotes` requests in flight, by their sorted instruments, for callers
+/// asking for the same quotes to wait on.
+type InFlightQuotes = HashMap<String, InFlightQuote>;
+type SharedQuotes = Shared<BoxFuture<'static, Result<Vec<Quote>, PublicError>>>;
+
+/// One `get_quotes` request in flight, with the callers waiting on it.
+struct InFlightQuote {
+    id: Uuid,
+    request: SharedQuotes,
+    waiters: usize,
+}
+
+/// Leaves an `InFlightQuote` when its caller is done or cancelled. The last
+/// caller to leave removes it, dropping the request with it.
+struct InFlightGuard {
+    in_flight: Arc<Mutex<InFlightQuotes>>,
+    key: String,
+    id: Uuid,
+}
 
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(entry) = in_flight.get_mut(&self.key)
+            && entry.id == self.id
+        {
+            entry.waiters -= 1;
+            if entry.waiters == 0 {
+                in_flight.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Client for the Public API. Cloning is cheap: clones share the HTTP pool,
+/// credentials and rate limiter, so one client can serve many tasks.
 #[derive(Clone)]
 pub struct PublicClient {
+    inner: Arc<PublicClientInner>,
+}
+
+#[derive(Clone)]
+struct PublicClientInner {
     client: Client,
     base_url: Url,
     account_id: Option<String>,
-    creds: Arc<Mutex<Creds>>,
+    creds: Arc<RwLock<Creds>>,
+    rate_limiter: Arc<RateLimiter>,
+    retry_policy: RetryPolicy,
+    circuit_breaker: CircuitBreaker,
+    expiration_cache: Arc<RwLock<ExpirationCache>>,
+    expiration_cache_ttl: Duration,
+    in_flight_quotes: Arc<Mutex<InFlightQuotes>>,
+    #[cfg(feature = "news")]
+    news: Option<NewsConfig>,
+    /// Most instruments asked for in one quotes request.
+    quote_chunk_size: usize,
+    refresh_task: Option<Arc<RefreshTask>>,
+}
+
+/// Timeouts and connection pool of the HTTP client, so a server that stops
+/// responding fails requests instead of hanging the strategies.
+#[derive(Clone, PartialEq)]
+pub struct ClientConfig {
+    /// Time to establish the connection.
+    pub connect_timeout: Duration,
+    /// Time to wait between two reads of the response.
+    pub read_timeout: Duration,
+    /// Time for the whole request, from connecting to the end of the body.
+    pub request_timeout: Duration,
+    /// Idle connections kept open to the API for reuse. Requests beyond it
+    /// open new connections, they don't fail.
+    pub max_connections: usize,
+    /// Interval of the TCP keepalive probes on open connections.
+    pub keep_alive_timeout: Duration,
+    /// Proxy every request goes through, like "http://proxy.example.com:3128"
+    /// or "socks5://proxy.example.com:1080".
+    pub proxy_url: Option<String>,
+    /// Username and password of the proxy, if it asks for them.
+    pub proxy_auth: Option<(String, String)>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            request_timeout: Duration::from_secs(30),
+            max_connections: 10,
+            keep_alive_timeout: Duration::from_secs(15),
+            proxy_url: None,
+            proxy_auth: None,
+        }
+    }
+}
+
+// Manual so the proxy password never ends up in the logs
+impl std::fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("connect_timeout", &self.connect_timeout)
+            .field("read_timeout", &self.read_timeout)
+            .field("request_timeout", &self.request_timeout)
+            .field("max_connections", &self.max_connections)
+            .field("keep_alive_timeout", &self.keep_alive_timeout)
+            .field("proxy_url", &self.proxy_url)
+            .field(
+                "proxy_auth",
+                &self
+                    .proxy_auth
+                    .as_ref()
+                    .map(|(username, _)| (username, "<redacted>")),
+            )
+            .finish()
+    }
+}
+
+impl ClientConfig {
+    fn build_client(&self) -> Result<Client, PublicError> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout)
+            .timeout(self.request_timeout)
+            .pool_max_idle_per_host(self.max_connections)
+            .tcp_keepalive(self.keep_alive_timeout);
+        if let Some(url) = &self.proxy_url {
+            let mut proxy =
+                Proxy::all(url).map_err(|e| PublicError::InvalidProxy(format!("{url}: {e}")))?;
+            if let Some((username, password)) = &self.proxy_auth {
+                proxy = proxy.basic_auth(username, password);
+            }
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Whether the API answers the credentials of the client, from `PublicClient::health_check`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthStatus {
+    pub api_reachable: bool,
+    pub authenticated: bool,
+    pub account_count: u32,
+    /// Time to list the accounts, retries included.
+    pub latency_ms: u64,
+}
+
+/// Background token refresh, stopped once the last clone of the client is dropped.
+struct RefreshTask(JoinHandle<()>);
+
+impl Drop for RefreshTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, thiserror::Error)]
 pub enum PublicError {
-    AccountTypeNotFound,
+    /// No account with the requested account type or id.
+    #[error("account '{0}' not found")]
+    AccountNotFound(String),
+    #[error("missing credentials to mint an access token")]
     MissingCredentials,
+    #[error("no account id, the account type isn't set")]
     MissingAccountId,
-    ServiceError(String, String),
+    /// Error message from Public, with the correlation id of the request when it was sent.
+    #[error("service error, {0}: {1}{request}", request = request_suffix(.2))]
+    ServiceError(String, String, Option<String>),
+    /// HTTP status of a failed request whose body isn't a JSON error message,
+    /// with the start of the body.
+    #[error("http status {0}: {1}")]
+    StatusCode(u16, String),
+    #[error("http error: {0}")]
     HttpError(String),
+    #[error("invalid uri")]
     InvalidUri,
+    #[error("invalid proxy {0}")]
+    InvalidProxy(String),
+    #[error("cannot parse the response")]
     ParseError,
+    #[cfg(feature = "news")]
+    #[error("no [news] config to fetch news with")]
+    MissingNewsConfig,
+    /// Too many requests failed in a row, see `CircuitBreaker`.
+    #[error("circuit breaker open, not sending requests")]
+    CircuitOpen,
+    #[error("gave up after {attempts} attempts: {last}")]
+    RetriesExhausted {
+        attempts: u8,
+        #[source]
+        last: Box<PublicError>,
+    },
+}
+
+/// A chunk of a `get_quotes` request that failed, the others may have succeeded.
+#[derive(Debug, thiserror::Error)]
+#[error("quotes chunk {chunk_index} failed: {error}")]
+pub struct QuoteChunkError {
+    pub chunk_index: usize,
+    #[source]
+    pub error: PublicError,
+}
+
+/// Kept as the message, errors from `reqwest` aren't `Clone` and carry the
+/// whole request.
+impl From<reqwest::Error> for PublicError {
+    fn from(e: reqwest::Error) -> Self {
+        PublicError::HttpError(e.to_string())
+    }
+}
+
+/// " (request <id>)" for errors that carry the correlation id of their request.
+fn request_suffix(correlation_id: &Option<String>) -> String {
+    correlation_id
+        .as_ref()
+        .map(|id| format!(" (request {id})"))
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -59,6 +290,13 @@ pub struct GetHistoryResponse {
     page_size: Option<i64>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderHistoryResponse {
+    orders: Vec<Order>,
+    next_token: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct QuotesRequest {
     instruments: Vec<Instrument>,
@@ -116,6 +354,55 @@ struct GetOptionGreeksResponse {
     greeks: Vec<OptionGreeksResponse>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaceOrderRequest {
+    order_id: String,
+    instrument: Instrument,
+    order_side: OrderSide,
+    order_type: OrderType,
+    expiration: Expiration,
+    quantity: String,
+    limit_price: Option<String>,
+    open_close_indicator: Option<OPIndicator>,
+}
+
+impl PlaceOrderRequest {
+    /// Day limit order. `open_close` is only sent for options, other instruments don't take it.
+    fn limit(
+        instrument: Instrument,
+        side: OrderSide,
+        open_close: OPIndicator,
+        quantity: u32,
+        limit_price: f64,
+    ) -> Self {
+        let open_close_indicator = match instrument.instrument_type {
+            InstrumentType::Option => Some(open_close),
+            _ => None,
+        };
+
+        Self {
+            order_id: Uuid::new_v4().to_string(),
+            instrument,
+            order_side: side,
+            order_type: OrderType::Limit,
+            expiration: Expiration {
+                time_in_force: TimeInForce::Day,
+                expiration_time: None,
+            },
+            quantity: quantity.to_string(),
+            limit_price: Some(format!("{limit_price:.2}")),
+            open_close_indicator,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaceOrderResponse {
+    order_id: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PreflightSingleLegRequest {
@@ -156,7 +443,7 @@ pub struct PreflightSingleLegResponse {
 
 macro_rules! account_id {
     ($P:ident) => {
-        if let Some(a_id) = &$P.account_id {
+        if let Some(a_id) = &$P.inner.account_id {
             a_id
         } else {
             return Err(PublicError::MissingAccountId);
@@ -173,6 +460,7 @@ macro_rules! response {
                 return Err(PublicError::ServiceError(
                     "MalformedJsonResponse".to_string(),
                     format!("Couldnt parse json response: {e}"),
+                    None,
                 ));
             }
         }
@@ -186,42 +474,196 @@ macro_rules! _debug_res {
 }
 
 impl PublicClient {
+    /// Client for the Public API. Inside a tokio runtime it also starts
+    /// refreshing the access token in the background.
     pub fn new() -> Result<Self, PublicError> {
-        let client = Client::new();
+        Self::new_with_config(ClientConfig::default())
+    }
+
+    /// Client for the Public API with the timeouts, connection pool and proxy of `config`.
+    pub fn new_with_config(config: ClientConfig) -> Result<Self, PublicError> {
+        Self::with_profile(CredsProfile::load(DEFAULT_PROFILE), &config)
+    }
+
+    /// Client using the creds of `profile`, like "ira" for `~/.public/creds_ira.json`.
+    /// `set_account` then picks the account type within that profile.
+    pub fn new_with_profile(profile: &str) -> Result<Self, PublicError> {
+        Self::with_profile(CredsProfile::load(profile), &ClientConfig::default())
+    }
+
+    /// Client using the creds of `profile`, minting tokens from the secret in
+    /// `backend` instead of the one picked by `BackendConfig::from_env`.
+    pub fn new_with_backend(
+        profile: &str,
+        backend: Box<dyn SecretBackend>,
+    ) -> Result<Self, PublicError> {
+        let mut profile = CredsProfile::load(profile);
+        profile.creds = profile.creds.with_backend(backend);
+
+        Self::with_profile(profile, &ClientConfig::default())
+    }
+
+    fn with_profile(profile: CredsProfile, config: &ClientConfig) -> Result<Self, PublicError> {
+        info!("Using creds profile {}", profile.name);
+        let mut client = Self::without_refresh_task(profile.creds, config)?;
+        if tokio::runtime::Handle::try_current().is_err() {
+            warn!("No tokio runtime, access tokens will be refreshed inline");
+            return Ok(client);
+        }
+
+        // Shares the creds, but not the task, so the task doesn't keep itself alive.
+        let token_client = client.clone();
+        let task = Creds::start_refresh_task(
+            client.inner.creds.clone(),
+            TOKEN_REFRESH_INTERVAL,
+            move || {
+                let token_client = token_client.clone();
+                async move {
+                    token_client
+                        .new_access_token()
+                        .await
+                        .map_err(anyhow::Error::from)
+                }
+            },
+        );
+        client.inner_mut().refresh_task = Some(Arc::new(RefreshTask(task)));
+
+        Ok(client)
+    }
+
+    fn without_refresh_task(creds: Creds, config: &ClientConfig) -> Result<Self, PublicError> {
+        let client = config.build_client()?;
 
         Ok(Self {
-            client,
-            base_url: PUBLIC_API.parse().unwrap(),
-            account_id: None,
-            creds: Arc::new(Mutex::new(Creds::new())),
+            inner: Arc::new(PublicClientInner {
+                client,
+                base_url: PUBLIC_API.parse().unwrap(),
+                account_id: None,
+                creds: Arc::new(RwLock::new(creds)),
+                rate_limiter: Arc::new(RateLimiter::default()),
+                retry_policy: RetryPolicy::default(),
+                circuit_breaker: CircuitBreaker::default(),
+                expiration_cache: Arc::new(RwLock::new(HashMap::new())),
+                expiration_cache_ttl: DEFAULT_EXPIRATION_CACHE_TTL,
+                in_flight_quotes: Arc::new(Mutex::new(HashMap::new())),
+                #[cfg(feature = "news")]
+                news: None,
+                quote_chunk_size: DEFAULT_QUOTE_CHUNK_SIZE,
+                refresh_task: None,
+            }),
         })
     }
 
+    /// Client for account "TEST" talking to `base_url`, with no retries.
+    #[cfg(test)]
+    pub(crate) fn mock(base_url: Url) -> Self {
+        Self::mock_with_config(base_url, &ClientConfig::default())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn mock_with_config(base_url: Url, config: &ClientConfig) -> Self {
+        let mut client = Self::without_refresh_task(Creds::with_token("test-token"), config)
+            .expect("valid mock client config")
+            .with_retry_policy(RetryPolicy::none());
+        let inner = client.inner_mut();
+        inner.base_url = base_url;
+        inner.account_id = Some("TEST".to_string());
+        client
+    }
+
+    /// Settings of this client, copied first if other clones still share them.
+    fn inner_mut(&mut self) -> &mut PublicClientInner {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    /// Replaces the default request budget used to throttle calls to the API.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.inner_mut().rate_limiter = Arc::new(RateLimiter::new(&limit));
+        self
+    }
+
+    /// How long `get_option_expirations` reuses fetched expirations, 10 minutes by default.
+    pub fn with_expiration_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().expiration_cache_ttl = ttl;
+        self
+    }
+
+    /// Splits `get_quotes` requests into chunks of at most `size` instruments.
+    pub fn with_quote_chunk_size(mut self, size: usize) -> Self {
+        self.inner_mut().quote_chunk_size = size.max(1);
+        self
+    }
+
+    /// Fetches `get_news` from the source of `config`.
+    #[cfg(feature = "news")]
+    pub fn with_news(mut self, config: NewsConfig) -> Self {
+        self.inner_mut().news = Some(config);
+        self
+    }
+
+    /// Forgets the cached option expirations of every symbol.
+    pub async fn clear_expiration_cache(&self) {
+        self.inner.expiration_cache.write().await.clear();
+    }
+
+    /// Replaces the default circuit breaker, opening after 5 failures in a row for 30 seconds.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.inner_mut().circuit_breaker = breaker;
+        self
+    }
+
+    /// Replaces the default policy used to retry transient request failures.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.inner_mut().retry_policy = policy;
+        self
+    }
+
+    /// Uses the account of `account_type`, the last one listed if there are several.
     pub async fn set_account(&mut self, account_type: AccountType) -> Result<(), PublicError> {
+        let wanted = format!("{account_type:?}");
+        self.validate_and_set_account_id(&wanted, |account| account.account_type == account_type)
+            .await
+    }
+
+    /// Uses the account `account_id`, for profiles with several accounts of one type.
+    pub async fn set_account_by_id(&mut self, account_id: &str) -> Result<(), PublicError> {
+        self.validate_and_set_account_id(account_id, |account| account.account_id == account_id)
+            .await
+    }
+
+    /// Sets the id of the last listed account matching `select`, `wanted` names it in errors.
+    async fn validate_and_set_account_id<F>(
+        &mut self,
+        wanted: &str,
+        select: F,
+    ) -> Result<(), PublicError>
+    where
+        F: Fn(&Account) -> bool,
+    {
         let accounts = self.get_accounts().await?;
 
         let account_id = accounts
             .iter()
-            .filter(|account| account.account_type == account_type)
+            .filter(|account| select(account))
             .map(|account| &account.account_id)
-            .last();
+            .next_back();
 
         let account_id = if let Some(account_id) = account_id {
             account_id.to_string()
         } else {
-            error!("There was no valid account_id of type {account_type:?}");
-            return Err(PublicError::AccountTypeNotFound);
+            error!("There was no valid account_id for {wanted}");
+            return Err(PublicError::AccountNotFound(wanted.to_string()));
         };
 
-        self.account_id = Some(account_id);
+        self.inner_mut().account_id = Some(account_id);
 
         Ok(())
     }
 
     fn make_uri(&self, path: &str) -> Result<Url, PublicError> {
-        match self.base_url.join(path) {
+        match self.inner.base_url.join(path) {
             Ok(uri) => Ok(uri),
-            Err(_) => return Err(PublicError::InvalidUri),
+            Err(_) => Err(PublicError::InvalidUri),
         }
     }
 
@@ -232,33 +674,30 @@ impl PublicClient {
         K: AsRef<str>,
         V: AsRef<str>,
     {
-        let url = match self.base_url.join(path) {
+        let url = match self.inner.base_url.join(path) {
             Ok(uri) => uri,
             Err(_) => return Err(PublicError::InvalidUri),
         };
 
         match Url::parse_with_params(url.as_str(), params) {
             Ok(uri) => Ok(uri),
-            Err(_) => return Err(PublicError::InvalidUri),
+            Err(_) => Err(PublicError::InvalidUri),
         }
     }
 
     /// Makes a GET request to the specified endpoint
     async fn get(&self, path: &str) -> Result<Response, PublicError> {
         let uri = self.make_uri(path)?;
+        let token = self.access_token().await?;
 
-        let response = self
+        let request = self
+            .inner
             .client
             .get(uri)
-            .header(
-                AUTHORIZATION,
-                format!("Bearer {}", self.access_token().await?),
-            )
-            .header(ACCEPT, "*/*")
-            .send()
-            .await;
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .header(ACCEPT, "*/*");
 
-        handle_response(response).await
+        self.send(request).await
     }
 
     /// Makes a GET request to the specified endpoint, with URL parameters
@@ -270,18 +709,15 @@ impl PublicClient {
         V: AsRef<str>,
     {
         let uri = self.make_uri_with_params(path, params)?;
-        let response = self
+        let token = self.access_token().await?;
+        let request = self
+            .inner
             .client
             .get(uri)
-            .header(
-                AUTHORIZATION,
-                format!("Bearer {}", self.access_token().await?),
-            )
-            .header(ACCEPT, "*/*")
-            .send()
-            .await;
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .header(ACCEPT, "*/*");
 
-        handle_response(response).await
+        self.send(request).await
     }
 
     async fn post<P>(&self, path: &str, payload: &P) -> Result<Response, PublicError>
@@ -289,20 +725,91 @@ impl PublicClient {
         P: Serialize + ?Sized,
     {
         let uri = self.make_uri(path)?;
+        let token = self.access_token().await?;
 
-        let response = self
+        let request = self
+            .inner
             .client
             .post(uri)
-            .header(
-                AUTHORIZATION,
-                format!("Bearer {}", self.access_token().await?),
-            )
+            .header(AUTHORIZATION, format!("Bearer {token}"))
             .header(ACCEPT, "*/*")
-            .json(payload)
-            .send()
+            .json(payload);
+
+        self.send(request).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<Response, PublicError> {
+        let uri = self.make_uri(path)?;
+        let token = self.access_token().await?;
+
+        let request = self
+            .inner
+            .client
+            .delete(uri)
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .header(ACCEPT, "*/*");
+
+        self.send(request).await
+    }
+
+    /// Sends the request under the rate limiter, retrying transient failures
+    /// according to the client's `RetryPolicy`. Every attempt carries the same
+    /// correlation id, in the `X-Correlation-ID` header and the `http_request` span.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, PublicError> {
+        let correlation_id = Uuid::new_v4().to_string();
+        let request = request.header(CORRELATION_ID_HEADER, &correlation_id);
+        let target = request
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| format!("{} {}", r.method(), r.url().path()))
+            .unwrap_or_default();
+        let with_id = |e| match e {
+            PublicError::ServiceError(error, message, None) => {
+                PublicError::ServiceError(error, message, Some(correlation_id.clone()))
+            }
+            e => e,
+        };
+        let permit = self.inner.circuit_breaker.acquire()?;
+
+        let result = self
+            .inner
+            .retry_policy
+            .retry(|| async {
+                let Some(request) = request.try_clone() else {
+                    return Attempt::Done(Err(PublicError::HttpError(
+                        "Request cannot be cloned for sending".to_string(),
+                    )));
+                };
+                self.inner.rate_limiter.acquire().await;
+
+                let started = Instant::now();
+                let response = request.send().await;
+                match &response {
+                    Ok(res) => debug!(
+                        "{target} returned {} in {:?}",
+                        res.status(),
+                        started.elapsed()
+                    ),
+                    Err(e) => debug!("{target} failed in {:?}: {e}", started.elapsed()),
+                }
+
+                match response {
+                    Ok(res) if is_transient_status(res.status()) => {
+                        match handle_response(Ok(res)).await {
+                            Ok(res) => Attempt::Done(Ok(res)),
+                            Err(e) => Attempt::Transient(with_id(e)),
+                        }
+                    }
+                    Ok(res) => Attempt::Done(handle_response(Ok(res)).await.map_err(with_id)),
+                    Err(e) if is_transient_error(&e) => Attempt::Transient(e.into()),
+                    Err(e) => Attempt::Done(Err(e.into())),
+                }
+            })
+            .instrument(info_span!("http_request", correlation_id = %correlation_id))
             .await;
+        permit.record(result.as_ref().is_err_and(is_outage));
 
-        handle_response(response).await
+        result
     }
 
     async fn create_personal_token(
@@ -316,27 +823,45 @@ impl PublicClient {
             "secret": public_secret
         });
 
-        let response = self
+        let request = self
+            .inner
             .client
             .post(uri)
             .header(CONTENT_TYPE, "application/json")
-            .json(&payload)
-            .send()
-            .await;
-        let response = handle_response(response).await?;
+            .json(&payload);
+        let response = self.send(request).await?;
         let data = response!(PersonalTokenResponse, response);
 
         Ok(data.access_token)
     }
 
-    async fn access_token(&self) -> Result<String, PublicError> {
-        let (public_secret, ttl) = {
-            let creds = self.creds.lock().await;
-            if let Some(token) = creds.access_token() {
-                return Ok(token.to_string());
-            }
-            info!("Generating a new public token");
+    /// Current access token. Normally kept fresh by the background task,
+    /// a new one is minted inline if it lapsed anyway.
+    pub(super) async fn access_token(&self) -> Result<String, PublicError> {
+        if let Some(token) = self.inner.creds.read().await.access_token() {
+            return Ok(token.to_string());
+        }
+        if let Some(task) = &self.inner.refresh_task
+            && task.0.is_finished()
+        {
+            warn!("Token refresh task stopped, refreshing inline");
+        }
+        info!("Generating a new public token");
+
+        let public_token = self.new_access_token().await?;
+        self.inner
+            .creds
+            .write()
+            .await
+            .refresh(public_token.as_str());
+
+        Ok(public_token)
+    }
 
+    /// Mints an access token from the public secret, without storing it.
+    async fn new_access_token(&self) -> Result<String, PublicError> {
+        let (public_secret, ttl) = {
+            let creds = self.inner.creds.read().await;
             let public_secret = creds.public_secret().await.map_err(|e| {
                 error!("Missing public secret: {e}");
                 PublicError::MissingCredentials
@@ -345,10 +870,17 @@ impl PublicClient {
             (public_secret, creds.ttl())
         };
 
-        let public_token = self.create_personal_token(public_secret, ttl).await?;
-        self.creds.lock().await.refresh(public_token.as_str());
+        self.create_personal_token(public_secret, ttl).await
+    }
 
-        Ok(public_token)
+    /// Whether the options level of the current account allows spreads.
+    pub async fn can_trade_spreads(&self) -> Result<bool, PublicError> {
+        let account_id = account_id!(self);
+        let accounts = self.get_accounts().await?;
+        match accounts.iter().find(|a| &a.account_id == account_id) {
+            Some(account) => Ok(account.options_level.can_trade_spreads()),
+            None => Err(PublicError::AccountNotFound(account_id.clone())),
+        }
     }
 
     pub async fn get_accounts(&self) -> Result<Vec<Account>, PublicError> {
@@ -358,6 +890,35 @@ impl PublicClient {
         Ok(data.accounts)
     }
 
+    /// Lists the accounts to check that the API is reachable and the credentials are
+    /// accepted. Failures to reach the API or rejected credentials (401 and 403) are
+    /// reported in the status, other errors like missing credentials are returned.
+    pub async fn health_check(&self) -> Result<HealthStatus, PublicError> {
+        let start = Instant::now();
+        let accounts = self.get_accounts().await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let (api_reachable, authenticated, account_count) = match accounts {
+            Ok(accounts) => (true, true, accounts.len() as u32),
+            Err(e) if is_outage(&e) => {
+                warn!("Public API is unreachable: {e}");
+                (false, false, 0)
+            }
+            Err(e) if is_auth_rejection(&e) => {
+                warn!("Public API rejected the credentials: {e}");
+                (true, false, 0)
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(HealthStatus {
+            api_reachable,
+            authenticated,
+            account_count,
+            latency_ms,
+        })
+    }
+
     /// ## Get Account Portfolio
     /// Returns the current status of all assets under the given account.
     pub async fn get_account_portfolio(&self) -> Result<AccountPortfolio, PublicError> {
@@ -369,6 +930,35 @@ impl PublicClient {
         Ok(data)
     }
 
+    /// ## Get Positions
+    /// Returns the positions currently held in the account, from the portfolio endpoint.
+    pub async fn get_positions(&self) -> Result<Vec<Position>, PublicError> {
+        let portfolio = self.get_account_portfolio().await?;
+
+        Ok(portfolio.positions)
+    }
+
+    /// ## Get Account Balance
+    /// Returns the cash, total value and buying power of the account.
+    pub async fn get_account_balance(&self) -> Result<AccountBalance, PublicError> {
+        let _ = account_id!(self);
+        let portfolio = self.get_account_portfolio().await?;
+
+        AccountBalance::try_from(&portfolio).map_err(|e| {
+            error!("Cannot parse account balance: {e}");
+            PublicError::ParseError
+        })
+    }
+
+    /// ## Get Buying Power
+    /// Returns the buying power available for options trades.
+    pub async fn get_buying_power(&self) -> Result<f64, PublicError> {
+        let _ = account_id!(self);
+        let balance = self.get_account_balance().await?;
+
+        Ok(balance.options_buying_power)
+    }
+
     /// ## Get History
     /// Returns the transaction history of the account
     pub async fn get_history(
@@ -403,9 +993,135 @@ impl PublicClient {
         Ok(data.transactions)
     }
 
+    /// ## Get News
+    /// The `limit` latest articles about `symbol`, from the source set by `with_news`.
+    #[cfg(feature = "news")]
+    pub async fn get_news(&self, symbol: &str, limit: u32) -> Result<Vec<NewsItem>, PublicError> {
+        let Some(news) = &self.inner.news else {
+            return Err(PublicError::MissingNewsConfig);
+        };
+
+        news.fetch(&self.inner.client, symbol, limit).await
+    }
+
     /// ## Get Quotes
     /// Fetches the most up-to-date quotes for the given instruments.
+    /// Long lists are fetched concurrently in chunks, failing if any chunk fails.
+    /// Callers asking for the same instruments while a request is in flight share
+    /// its result, whatever the order of their instruments. Each gets the quotes
+    /// in the order of its own instruments.
     pub async fn get_quotes(&self, symbols: Vec<Instrument>) -> Result<Vec<Quote>, PublicError> {
+        let instrument_key = |i: &Instrument| (i.instrument_type.to_string(), i.symbol.clone());
+        let mut sorted: Vec<&Instrument> = symbols.iter().collect();
+        sorted.sort_by_key(|i| instrument_key(i));
+        let key = serde_json::to_string(&sorted).unwrap_or_default();
+
+        let (request, _guard) = {
+            let mut in_flight = self.inner.in_flight_quotes.lock().unwrap();
+            let entry = match in_flight.get_mut(&key) {
+                Some(entry) => {
+                    debug!("Joining the quotes request in flight for {key}");
+                    entry.waiters += 1;
+                    entry
+                }
+                None => {
+                    let id = Uuid::new_v4();
+                    let request = self.shared_quotes(key.clone(), id, symbols.clone());
+                    let entry = InFlightQuote {
+                        id,
+                        request,
+                        waiters: 1,
+                    };
+                    in_flight.entry(key.clone()).or_insert(entry)
+                }
+            };
+            let guard = InFlightGuard {
+                in_flight: self.inner.in_flight_quotes.clone(),
+                key,
+                id: entry.id,
+            };
+            (entry.request.clone(), guard)
+        };
+        let mut quotes = request.await?;
+
+        let order: HashMap<(String, String), usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(n, i)| (instrument_key(i), n))
+            .collect();
+        let position = |q: &Quote| order.get(&instrument_key(&q.instrument)).copied();
+        quotes.sort_by_key(|q| position(q).unwrap_or(usize::MAX));
+
+        Ok(quotes)
+    }
+
+    /// Quotes request shared by the callers of `get_quotes`, leaving the requests
+    /// in flight once done. Only holds the in flight requests weakly, as they hold it.
+    fn shared_quotes(&self, key: String, id: Uuid, symbols: Vec<Instrument>) -> SharedQuotes {
+        let client = self.clone();
+        let in_flight = Arc::downgrade(&self.inner.in_flight_quotes);
+
+        async move {
+            let quotes = client.get_quotes_uncached(symbols).await;
+            if let Some(in_flight) = in_flight.upgrade() {
+                let mut in_flight = in_flight.lock().unwrap();
+                if in_flight.get(&key).is_some_and(|entry| entry.id == id) {
+                    in_flight.remove(&key);
+                }
+            }
+
+            quotes
+        }
+        .boxed()
+        .shared()
+    }
+
+    async fn get_quotes_uncached(
+        &self,
+        symbols: Vec<Instrument>,
+    ) -> Result<Vec<Quote>, PublicError> {
+        let (quotes, errors) = self.get_quotes_partial(symbols).await;
+        for e in &errors {
+            error!("{e}");
+        }
+        match errors.into_iter().next() {
+            Some(e) => Err(e.error),
+            None => Ok(quotes),
+        }
+    }
+
+    /// ## Get Crypto Quotes
+    /// Quotes of crypto assets, like "BTC" or "ETH".
+    pub async fn get_crypto_quotes(&self, symbols: &[&str]) -> Result<Vec<Quote>, PublicError> {
+        let instruments = symbols.iter().map(|s| Instrument::crypto(s)).collect();
+
+        self.get_quotes(instruments).await
+    }
+
+    /// Like `get_quotes`, but keeps the quotes of the chunks that succeeded
+    /// along with the errors of the ones that failed.
+    pub async fn get_quotes_partial(
+        &self,
+        symbols: Vec<Instrument>,
+    ) -> (Vec<Quote>, Vec<QuoteChunkError>) {
+        let chunks = symbols
+            .chunks(self.inner.quote_chunk_size)
+            .map(|chunk| self.get_quotes_chunk(chunk.to_vec()));
+        let results = join_all(chunks).await;
+
+        let mut quotes = Vec::with_capacity(symbols.len());
+        let mut errors = Vec::new();
+        for (chunk_index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(chunk) => quotes.extend(chunk),
+                Err(error) => errors.push(QuoteChunkError { chunk_index, error }),
+            }
+        }
+
+        (quotes, errors)
+    }
+
+    async fn get_quotes_chunk(&self, symbols: Vec<Instrument>) -> Result<Vec<Quote>, PublicError> {
         let account_id = account_id!(self);
 
         let request = QuotesRequest {
@@ -419,6 +1135,80 @@ impl PublicClient {
         Ok(data.quotes)
     }
 
+    /// ## Poll Quotes
+    /// Quotes of `symbols` fetched with `get_quotes` right away, then every `interval`,
+    /// for when a WebSocket is too much. Ends after `max_polls` polls if given.
+    /// A failed poll is yielded as its error, the next tick polls again.
+    /// `interval` is at least a second, shorter ones poll every second.
+    pub fn poll_quotes(
+        &self,
+        symbols: Vec<Instrument>,
+        interval: Duration,
+        max_polls: Option<usize>,
+    ) -> impl Stream<Item = Result<Vec<Quote>, PublicError>> + use<> {
+        let mut ticks = tokio::time::interval(interval.max(MIN_POLL_INTERVAL));
+        // A slow poll delays the next ones instead of bursting to catch up
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        futures::stream::unfold(
+            (self.clone(), symbols, ticks, 0),
+            move |(client, symbols, mut ticks, polls)| async move {
+                if max_polls.is_some_and(|max| polls >= max) {
+                    return None;
+                }
+                ticks.tick().await;
+                let quotes = client.get_quotes(symbols.clone()).await;
+
+                Some((quotes, (client, symbols, ticks, polls + 1)))
+            },
+        )
+    }
+
+    /// ## Stream Quotes
+    /// Quotes of `symbols` pushed over a WebSocket as they change, see `stream_quote_events`.
+    pub async fn stream_quotes(
+        &self,
+        symbols: Vec<Instrument>,
+    ) -> Result<impl Stream<Item = Result<Quote, PublicError>> + use<>, PublicError> {
+        let events = self.stream_quote_events(symbols).await?;
+
+        Ok(events.filter_map(|event| async move {
+            match event {
+                Ok(QuoteStreamEvent::Quote(quote)) => Some(Ok(quote)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            }
+        }))
+    }
+
+    /// Quotes and heartbeats of `symbols` pushed over a WebSocket. A dropped
+    /// connection yields `Disconnect`, then reconnects with the backoff of the
+    /// client's `RetryPolicy` and subscribes to `symbols` again.
+    pub async fn stream_quote_events(
+        &self,
+        symbols: Vec<Instrument>,
+    ) -> Result<impl Stream<Item = Result<QuoteStreamEvent, PublicError>> + use<>, PublicError>
+    {
+        let account_id = account_id!(self);
+        let mut url = self.make_uri(&format!(
+            "/userapigateway/marketdata/{account_id}/quotes/stream"
+        ))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        if url.set_scheme(scheme).is_err() {
+            return Err(PublicError::InvalidUri);
+        }
+
+        let stream = QuoteStream::connect(
+            self.clone(),
+            url.to_string(),
+            symbols,
+            self.inner.retry_policy.clone(),
+        )
+        .await?;
+
+        Ok(stream.into_stream())
+    }
+
     /// ## Get Option Expirations
     /// Gets the tradeable expirations available for the instrument.
     pub async fn get_option_expirations(
@@ -426,12 +1216,25 @@ impl PublicClient {
         instrument: Instrument,
     ) -> Result<Vec<String>, PublicError> {
         let account_id = account_id!(self);
+        let symbol = instrument.symbol.clone();
+        if let Some((expirations, fetched)) = self.inner.expiration_cache.read().await.get(&symbol)
+            && fetched.elapsed() < self.inner.expiration_cache_ttl
+        {
+            debug!("Using cached expirations for {symbol}");
+            return Ok(expirations.clone());
+        }
         let request = GetOptionExpirationsRequest { instrument };
 
         let path = format!("/userapigateway/marketdata/{account_id}/option-expirations");
         let res = self.post(path.as_str(), &request).await?;
         let data = response!(GetOptionExpirationsResponse, res);
 
+        self.inner
+            .expiration_cache
+            .write()
+            .await
+            .insert(symbol, (data.expirations.clone(), Instant::now()));
+
         Ok(data.expirations)
     }
 
@@ -456,6 +1259,25 @@ impl PublicClient {
         Ok(option_chain)
     }
 
+    /// Chains of every expiration of the instrument, paired with their expiration date.
+    /// The chains are fetched concurrently, throttled by the client's rate limiter,
+    /// failing if any of them fails.
+    pub async fn get_option_chain_all_expirations(
+        &self,
+        instrument: Instrument,
+    ) -> Result<Vec<(String, OptionChain)>, PublicError> {
+        let expirations = self.get_option_expirations(instrument.clone()).await?;
+        let chains = expirations
+            .iter()
+            .map(|expiration| self.get_option_chain(instrument.clone(), expiration.clone()));
+        let chains = join_all(chains)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(expirations.into_iter().zip(chains).collect())
+    }
+
     /// ## Get Bars V2
     /// Fetch bar data for a given symbol and period
     pub async fn get_bars_v2(
@@ -494,15 +1316,123 @@ impl PublicClient {
         Ok(data)
     }
 
+    /// ## Place Order
+    /// Submits a day limit order, opening or closing an option position as `open_close`
+    /// says. Orders are processed asynchronously, so the receipt only confirms Public
+    /// accepted the order, poll the order for its fill status.
+    pub async fn place_limit_order(
+        &self,
+        instrument: Instrument,
+        side: OrderSide,
+        open_close: OPIndicator,
+        quantity: u32,
+        limit_price: f64,
+    ) -> Result<OrderReceipt, PublicError> {
+        let account_id = account_id!(self);
+        let path = format!("/userapigateway/trading/{account_id}/order");
+
+        let request = PlaceOrderRequest::limit(instrument, side, open_close, quantity, limit_price);
+        info!(
+            "Placing order {}: {:?} {} {} @ ${}",
+            request.order_id,
+            request.order_side,
+            request.quantity,
+            request.instrument.symbol,
+            limit_price
+        );
+        let res = self.post(path.as_str(), &request).await?;
+        let data = response!(PlaceOrderResponse, res);
+
+        Ok(OrderReceipt {
+            order_id: data.order_id,
+            status: OrderStatus::New,
+            filled_at: None,
+            completed_at: None,
+            fill_price: None,
+        })
+    }
+
+    /// ## Get Order
+    /// Looks up the current status of an order placed on the account.
+    pub async fn get_order_status(&self, order_id: &str) -> Result<OrderReceipt, PublicError> {
+        let account_id = account_id!(self);
+        let path = format!("/userapigateway/trading/{account_id}/order/{order_id}");
+        let res = self.get(path.as_str()).await?;
+        let order = response!(Order, res);
+
+        Ok(OrderReceipt::from(&order))
+    }
+
+    /// ## Get Market Hours
+    /// Returns the regular trading session of `date`, closed on weekends and holidays.
+    pub async fn get_market_hours(&self, date: NaiveDate) -> Result<MarketHours, PublicError> {
+        let path = format!("/userapigateway/marketdata/hours/{date}");
+        let res = self.get(path.as_str()).await?;
+        let data = response!(MarketHours, res);
+
+        Ok(data)
+    }
+
+    /// ## Get Order History
+    /// Returns every order placed between `from` and `to` (inclusive), whatever its
+    /// final status. Follows `nextToken` until all pages are fetched.
+    pub async fn get_order_history(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<OrderReceipt>, PublicError> {
+        let account_id = account_id!(self);
+        let path = format!("/userapigateway/trading/{account_id}/orders");
+        let mut orders = Vec::new();
+        let mut next_token: Option<String> = None;
+
+        loop {
+            let mut params = vec![
+                ("from", from.format("%Y-%m-%d").to_string()),
+                ("to", to.format("%Y-%m-%d").to_string()),
+            ];
+            if let Some(token) = next_token.take() {
+                params.push(("nextToken", token));
+            }
+            let res = self.get_with_params(path.as_str(), &params).await?;
+            let data = response!(OrderHistoryResponse, res);
+            orders.extend(data.orders.iter().map(OrderReceipt::from));
+
+            match data.next_token {
+                Some(token) if !token.is_empty() => next_token = Some(token),
+                _ => break,
+            }
+        }
+        debug!("Fetched {} orders from {from} to {to}", orders.len());
+
+        Ok(orders)
+    }
+
+    /// ## Cancel Order
+    /// Requests cancellation of an open order. Cancellation is asynchronous,
+    /// poll `get_order_status` to confirm the order was cancelled.
+    pub async fn cancel_order(&self, order_id: &str) -> Result<(), PublicError> {
+        let account_id = account_id!(self);
+        let path = format!("/userapigateway/trading/{account_id}/order/{order_id}");
+        let _ = self.delete(path.as_str()).await?;
+        info!("Requested cancel of order {order_id}");
+
+        Ok(())
+    }
+
     /// ## GetOptionGreeks
     /// Get the greeks for a list of option symbol in the OSI-normalized format. Max 250 contracts per request.
     pub async fn get_option_greeks(
         &self,
-        osi_option_symbols: &Vec<String>,
+        osi_option_symbols: &[OsiSymbol],
     ) -> Result<Vec<OptionGreeks>, PublicError> {
         let account_id = account_id!(self);
         let path = format!("/userapigateway/option-details/{account_id}/greeks");
-        let symbols = osi_option_symbols.join(",");
+        let symbols = osi_option_symbols
+            .iter()
+            .map(OsiSymbol::as_str)
+            .collect::<Vec<&str>>()
+            .join(",");
         let res = self
             .get_with_params(path.as_str(), &[("osiSymbols", symbols)])
             .await?;
@@ -515,6 +1445,47 @@ impl PublicClient {
 
         Ok(greeks)
     }
+
+    /// Greeks for any number of OSI symbols, keyed by symbol. Requests are split
+    /// into batches the endpoint accepts and sent one after another under the
+    /// rate limiter. Symbols Public has no greeks for are left out.
+    pub async fn get_option_greeks_batch(
+        &self,
+        osi_option_symbols: &[OsiSymbol],
+    ) -> Result<HashMap<String, OptionGreeks>, PublicError> {
+        self.get_option_greeks_batch_with_progress(osi_option_symbols, |_| {})
+            .await
+    }
+
+    /// Like `get_option_greeks_batch`, calling `on_chunk` with the number of
+    /// contracts of each request once it completes.
+    pub async fn get_option_greeks_batch_with_progress(
+        &self,
+        osi_option_symbols: &[OsiSymbol],
+        on_chunk: impl Fn(usize),
+    ) -> Result<HashMap<String, OptionGreeks>, PublicError> {
+        let mut greeks = HashMap::with_capacity(osi_option_symbols.len());
+        for batch in osi_option_symbols.chunks(MAX_GREEKS_PER_REQUEST) {
+            for g in self.get_option_greeks(batch).await? {
+                greeks.insert(g.symbol.clone(), g);
+            }
+            on_chunk(batch.len());
+        }
+
+        Ok(greeks)
+    }
+}
+
+/// Whether the API answered 401 or 403, by the status or by the error of its
+/// JSON message, like "Unauthorized".
+fn is_auth_rejection(error: &PublicError) -> bool {
+    match error {
+        PublicError::StatusCode(status, _) => matches!(status, 401 | 403),
+        PublicError::ServiceError(error, ..) => {
+            matches!(error.as_str(), "Unauthorized" | "Forbidden")
+        }
+        _ => false,
+    }
 }
 
 pub async fn handle_response(
@@ -524,24 +1495,28 @@ pub async fn handle_response(
 
     let response = match response {
         Ok(response) => response,
-        Err(e) => return Err(PublicError::HttpError(e.to_string())),
+        Err(e) => return Err(e.into()),
     };
 
     if !response.status().is_success() {
-        match response.text().await {
-            Ok(msg) => {
-                return Err(PublicError::ServiceError(
-                    "Error message from Public".to_string(),
-                    msg,
-                ));
-            }
+        let status = response.status().as_u16();
+        let mut body = match response.text().await {
+            Ok(body) => body,
             Err(e) => {
-                return Err(PublicError::ServiceError(
-                    "Cannot extract text from Response".to_string(),
-                    format!("{e}"),
+                return Err(PublicError::StatusCode(
+                    status,
+                    format!("cannot extract text from response: {e}"),
                 ));
             }
-        }
+        };
+
+        return match serde_json::from_str::<ServiceErrorMsg>(&body) {
+            Ok(msg) => Err(PublicError::ServiceError(msg.error, msg.message, None)),
+            Err(_) => {
+                body.truncate(body.floor_char_boundary(MAX_ERROR_BODY));
+                Err(PublicError::StatusCode(status, body))
+            }
+        };
     }
 
     Ok(response)
@@ -550,12 +1525,70 @@ pub async fn handle_response(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::public::mock::{mock_client, mock_greeks, mock_server};
     use std::include_str;
+    use warp::Filter;
+    use warp::http::StatusCode;
 
     const ACCOUNT_PORTFOLIO: &str = include_str!("../fixtures/account_portfolio.json");
     const OPTION_CHAIN: &str = include_str!("../fixtures/option_chain.json");
+    const CRYPTO_QUOTES: &str = include_str!("../fixtures/crypto_quotes.json");
     const ACCOUNTS: &str = include_str!("../fixtures/accounts.json");
+    const MARKET_HOURS_OPEN: &str = include_str!("../fixtures/market_hours_open.json");
+    const MARKET_HOURS_HOLIDAY: &str = include_str!("../fixtures/market_hours_holiday.json");
     const ACC_WITH_ORDERS: &str = include_str!("../fixtures/acc_portfolio_with_orders.json");
+    const ACC_WITH_OPTIONS: &str = include_str!("../fixtures/account_portfolio_with_options.json");
+    const ORDER_HISTORY_PAGE_1: &str = include_str!("../fixtures/order_history_page1.json");
+    const ORDER_HISTORY_PAGE_2: &str = include_str!("../fixtures/order_history_page2.json");
+
+    #[test]
+    fn test_public_error_display() {
+        let errors = [
+            (
+                PublicError::AccountNotFound("RothIra".to_string()),
+                "'RothIra'",
+            ),
+            (PublicError::MissingCredentials, "credentials"),
+            (PublicError::MissingAccountId, "account id"),
+            (
+                PublicError::ServiceError("Bad request".to_string(), "no symbol".to_string(), None),
+                "Bad request: no symbol",
+            ),
+            (
+                PublicError::ServiceError(
+                    "Bad request".to_string(),
+                    "no symbol".to_string(),
+                    Some("b1c2".to_string()),
+                ),
+                "no symbol (request b1c2)",
+            ),
+            (
+                PublicError::HttpError("connection reset".to_string()),
+                "connection reset",
+            ),
+            (
+                PublicError::StatusCode(502, "Bad Gateway".to_string()),
+                "502: Bad Gateway",
+            ),
+            (PublicError::InvalidUri, "invalid uri"),
+            (PublicError::ParseError, "parse"),
+            (PublicError::CircuitOpen, "circuit breaker open"),
+            (
+                PublicError::RetriesExhausted {
+                    attempts: 3,
+                    last: Box::new(PublicError::HttpError("timed out".to_string())),
+                },
+                "3 attempts: http error: timed out",
+            ),
+        ];
+        for (err, detail) in errors {
+            let msg = format!("{err}");
+            assert!(msg.contains(detail), "{msg} doesnt contain {detail}");
+        }
+
+        let err = anyhow::Error::from(PublicError::InvalidUri);
+        assert_eq!(err.to_string(), "invalid uri");
+    }
 
     #[test]
     fn test_parse_option_chain() {
@@ -569,6 +1602,256 @@ mod tests {
         assert!(option_chain.is_ok());
     }
 
+    #[test]
+    fn test_option_chain_strikes() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        assert_eq!(chain.atm_strike(40.3), Some(40.0));
+        assert_eq!(chain.atm_strike(52.0), Some(50.0));
+        assert_eq!(chain.atm_strike(1.0), Some(3.0));
+
+        let calls = chain.calls_above(40.0);
+        assert_eq!(calls.len(), 23);
+        assert!(calls.iter().all(|q| q.strike().unwrap() > 40.0));
+
+        let puts = chain.puts_below(40.0);
+        assert_eq!(puts.len(), 29);
+        assert!(puts.iter().all(|q| q.strike().unwrap() < 40.0));
+
+        let empty = OptionChain {
+            base_symbol: "LMND".to_string(),
+            calls: vec![],
+            puts: vec![],
+        };
+        assert_eq!(empty.atm_strike(40.0), None);
+    }
+
+    #[test]
+    fn test_option_chain_filter_by_delta() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        // Calls lose delta with the strike, puts gain it
+        let greeks: HashMap<String, OptionGreeks> = chain
+            .calls
+            .iter()
+            .map(|q| (q, 20.0 / q.strike().unwrap()))
+            .chain(chain.puts.iter().map(|q| (q, -q.strike().unwrap() / 100.0)))
+            .filter(|(q, _)| q.instrument.symbol != "LMND251219C00050000")
+            .map(|(q, delta)| {
+                let greeks = Greeks {
+                    delta,
+                    gamma: 0.01,
+                    theta: -0.1,
+                    vega: 0.2,
+                    rho: 0.01,
+                    implied_volatility: 0.6,
+                };
+                let symbol = q.instrument.symbol.clone();
+                (symbol.clone(), OptionGreeks { symbol, greeks })
+            })
+            .collect();
+
+        let filtered = chain.filter_by_delta(&greeks, 0.3, 0.4);
+        assert_eq!(filtered.base_symbol, "LMND");
+        let strikes =
+            |quotes: &[Quote]| -> Vec<f64> { quotes.iter().filter_map(Quote::strike).collect() };
+        // $50 has no greeks
+        assert_eq!(strikes(&filtered.calls), vec![55.0, 60.0, 65.0]);
+        assert!(
+            filtered
+                .calls
+                .iter()
+                .all(|q| q.instrument.symbol.contains('C'))
+        );
+        assert_eq!(strikes(&filtered.puts)[0], 30.0);
+        assert_eq!(*strikes(&filtered.puts).last().unwrap(), 40.0);
+        assert!(
+            filtered
+                .puts
+                .iter()
+                .all(|q| q.instrument.symbol.contains('P'))
+        );
+
+        let none = filtered.filter_by_delta(&HashMap::new(), 0.0, 1.0);
+        assert!(none.calls.is_empty() && none.puts.is_empty());
+    }
+
+    #[test]
+    fn test_instrument_constructors() {
+        let osi: OsiSymbol = "QCOM260220P00138000".parse().unwrap();
+        assert_eq!(
+            serde_json::to_value(Instrument::from_osi_symbol(&osi)).unwrap(),
+            json!({ "symbol": "QCOM260220P00138000", "type": "OPTION" })
+        );
+        assert_eq!(
+            serde_json::to_value(Instrument::equity("QCOM")).unwrap(),
+            json!({ "symbol": "QCOM", "type": "EQUITY" })
+        );
+    }
+
+    #[test]
+    fn test_option_chain_sentiment() {
+        let chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        assert_eq!(chain.total_open_interest(), 19388 + 26628);
+        assert_eq!(chain.put_call_ratio(), 26628.0 / 19388.0);
+        assert_eq!(chain.put_call_volume_ratio(), 946.0 / 1087.0);
+
+        let no_calls = OptionChain {
+            base_symbol: chain.base_symbol,
+            calls: vec![],
+            puts: chain.puts,
+        };
+        assert_eq!(no_calls.total_open_interest(), 26628);
+        assert_eq!(no_calls.put_call_ratio(), f64::INFINITY);
+        assert_eq!(no_calls.put_call_volume_ratio(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_merge_expirations() {
+        let option = |symbol: &str, bid: f64, ask: f64| -> Quote {
+            let mut q = quote(bid, ask, "2026-02-17T20:59:59Z");
+            q.instrument.symbol = symbol.to_string();
+            q
+        };
+        let near = OptionChain {
+            base_symbol: "QCOM".to_string(),
+            calls: vec![
+                option("QCOM260320C00140000", 4.8, 5.0),
+                option("QCOM260320C00145000", 2.5, 2.7),
+            ],
+            puts: vec![option("QCOM260320P00135000", 1.75, 1.9)],
+        };
+        let far = OptionChain {
+            base_symbol: "QCOM".to_string(),
+            calls: vec![
+                option("QCOM260417C00145000", 4.0, 4.2),
+                option("QCOM260417C00150000", 2.2, 2.4),
+            ],
+            puts: vec![option("QCOM260417P00135000", 3.0, 3.25)],
+        };
+
+        let pairs = OptionChain::merge_expirations(&near, &far);
+        let strikes: Vec<f64> = pairs.iter().map(|p| p.strike).collect();
+        assert_eq!(strikes, vec![135.0, 140.0, 145.0, 150.0]);
+
+        let symbol = |q: &Option<Quote>| q.as_ref().map(|q| q.instrument.symbol.clone());
+        let (puts, only_near, both, only_far) = (&pairs[0], &pairs[1], &pairs[2], &pairs[3]);
+        assert_eq!(symbol(&puts.near_put).unwrap(), "QCOM260320P00135000");
+        assert_eq!(symbol(&puts.far_put).unwrap(), "QCOM260417P00135000");
+        assert_eq!(symbol(&only_near.near_call).unwrap(), "QCOM260320C00140000");
+        assert!(only_near.far_call.is_none() && only_near.near_put.is_none());
+        assert_eq!(symbol(&both.near_call).unwrap(), "QCOM260320C00145000");
+        assert_eq!(symbol(&both.far_call).unwrap(), "QCOM260417C00145000");
+        assert!(only_far.near_call.is_none());
+        assert_eq!(symbol(&only_far.far_call).unwrap(), "QCOM260417C00150000");
+    }
+
+    #[test]
+    fn test_parse_option_chain_prices() {
+        let option_chain: OptionChain = serde_json::from_str(OPTION_CHAIN).unwrap();
+        let call = &option_chain.calls[0];
+        assert_eq!(call.instrument.symbol, "LMND251219C00003000");
+        assert_eq!(call.last, 30.5);
+        assert_eq!(call.bid, 71.0);
+        assert_eq!(call.ask, 74.7);
+
+        let put = &option_chain.puts[0];
+        assert_eq!(put.bid, 0.0);
+        assert_eq!(put.bid_size, None);
+    }
+
+    #[test]
+    fn test_parse_quote_bare_numbers() {
+        let quote: Quote = serde_json::from_value(json!({
+            "instrument": { "symbol": "AAPL", "type": "EQUITY" },
+            "outcome": "SUCCESS",
+            "last": 230.1,
+            "lastTimestamp": "2026-02-17T20:59:59Z",
+            "bid": "230.05",
+            "bidTimestamp": "2026-02-17T20:59:59Z",
+            "ask": 230.15,
+            "askTimestamp": "2026-02-17T20:59:59Z",
+            "volume": 100
+        }))
+        .unwrap();
+        assert_eq!(quote.last, 230.1);
+        assert_eq!(quote.bid, 230.05);
+        assert_eq!(quote.ask, 230.15);
+
+        let bad = serde_json::from_value::<Quote>(json!({
+            "instrument": { "symbol": "AAPL", "type": "EQUITY" },
+            "outcome": "SUCCESS",
+            "last": "n/a",
+            "lastTimestamp": "",
+            "bid": "1",
+            "bidTimestamp": "",
+            "ask": "1",
+            "askTimestamp": "",
+            "volume": 0
+        }));
+        assert!(bad.is_err());
+    }
+
+    fn quote(bid: f64, ask: f64, bid_timestamp: &str) -> Quote {
+        serde_json::from_value(json!({
+            "instrument": { "symbol": "QCOM260220P00138000", "type": "OPTION" },
+            "outcome": "SUCCESS",
+            "last": ask,
+            "lastTimestamp": bid_timestamp,
+            "bid": bid,
+            "bidTimestamp": bid_timestamp,
+            "ask": ask,
+            "askTimestamp": bid_timestamp,
+            "volume": 10
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quote_spread_helpers() {
+        let q = quote(1.0, 1.5, "2026-02-17T20:59:59Z");
+        assert_eq!(q.mid_price(), 1.25);
+        assert_eq!(q.spread(), 0.5);
+        assert_eq!(q.spread_pct(), 40.0);
+
+        let zero_bid = quote(0.0, 0.1, "2026-02-17T20:59:59Z");
+        assert_eq!(zero_bid.mid_price(), 0.05);
+        assert_eq!(zero_bid.spread_pct(), 200.0);
+
+        let no_market = quote(0.0, 0.0, "2026-02-17T20:59:59Z");
+        assert_eq!(no_market.mid_price(), 0.0);
+        assert_eq!(no_market.spread_pct(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_quote_is_stale() {
+        let max_age = std::time::Duration::from_secs(60);
+        let fresh = chrono::Utc::now().to_rfc3339();
+        assert!(!quote(1.0, 1.1, &fresh).is_stale(max_age));
+        assert!(quote(1.0, 1.1, "2026-02-17T20:59:59Z").is_stale(max_age));
+        assert!(quote(1.0, 1.1, "").is_stale(max_age));
+    }
+
+    #[test]
+    fn test_quote_validity() {
+        let fresh = chrono::Utc::now().to_rfc3339();
+        assert!(quote(1.0, 1.1, &fresh).is_valid_market());
+        assert_eq!(quote(1.0, 1.1, &fresh).validity_reason(), None);
+        // One sided markets are fine
+        assert!(quote(0.0, 0.05, &fresh).is_valid_market());
+
+        let cases = [
+            (quote(0.0, 0.0, &fresh), "no bid or ask"),
+            (quote(1.2, 1.1, &fresh), "bid above ask"),
+            (quote(1.0, 1.1, "2026-02-17T20:59:59Z"), "stale quote"),
+        ];
+        for (q, reason) in cases {
+            assert!(!q.is_valid_market());
+            assert_eq!(q.validity_reason(), Some(reason));
+        }
+
+        let four_minutes_ago = (chrono::Utc::now() - chrono::Duration::minutes(4)).to_rfc3339();
+        assert!(quote(1.0, 1.1, &four_minutes_ago).is_valid_market());
+    }
+
     #[test]
     fn test_parse_account_portfolio() {
         let portfolio: Result<AccountPortfolio, serde_json::Error> =
@@ -581,6 +1864,499 @@ mod tests {
         assert!(portfolio.is_ok());
     }
 
+    #[test]
+    fn test_account_balance() {
+        let portfolio: AccountPortfolio = serde_json::from_str(ACCOUNT_PORTFOLIO).unwrap();
+        let balance = AccountBalance::try_from(&portfolio).unwrap();
+
+        assert_eq!(balance.cash, 14176.48);
+        assert!((balance.portfolio_value - 384789.62).abs() < 1e-6);
+        assert_eq!(balance.day_trading_buying_power, 459879.64);
+        assert_eq!(balance.options_buying_power, 229939.82);
+    }
+
+    #[tokio::test]
+    async fn test_get_quotes_dedup() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::sync::Barrier;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .and_then(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async {
+                    // Slow enough for the second call to arrive while in flight
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok::<_, warp::Rejection>(warp::reply::with_header(
+                        CRYPTO_QUOTES,
+                        "content-type",
+                        "application/json",
+                    ))
+                }
+            });
+        let client = mock_client(mock_server(quotes).await);
+
+        let barrier = Arc::new(Barrier::new(2));
+        let calls: Vec<_> = [["BTC", "ETH"], ["ETH", "BTC"]]
+            .into_iter()
+            .map(|symbols| {
+                let (client, barrier) = (client.clone(), barrier.clone());
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    client.get_crypto_quotes(&symbols).await
+                })
+            })
+            .collect();
+        let results: Vec<Vec<Quote>> = join_all(calls)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().unwrap())
+            .collect();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        // In the order each caller asked for
+        let symbols = |quotes: &[Quote]| -> Vec<String> {
+            quotes.iter().map(|q| q.instrument.symbol.clone()).collect()
+        };
+        assert_eq!(symbols(&results[0]), vec!["BTC", "ETH"]);
+        assert_eq!(symbols(&results[1]), vec!["ETH", "BTC"]);
+
+        // Done requests aren't reused
+        client.get_crypto_quotes(&["BTC", "ETH"]).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert!(client.inner.in_flight_quotes.lock().unwrap().is_empty());
+
+        // Cancelled requests are dropped, and don't keep the client alive
+        let cancelled = client.get_crypto_quotes(&["BTC", "ETH"]);
+        let timeout = tokio::time::timeout(Duration::from_millis(10), cancelled).await;
+        assert!(timeout.is_err());
+        assert!(client.inner.in_flight_quotes.lock().unwrap().is_empty());
+        assert_eq!(Arc::strong_count(&client.inner), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_quotes() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .map(move || {
+                if counter.fetch_add(1, Ordering::SeqCst) == 1 {
+                    return warp::reply::with_status(CRYPTO_QUOTES, StatusCode::BAD_REQUEST);
+                }
+                warp::reply::with_status(CRYPTO_QUOTES, StatusCode::OK)
+            });
+        let client = mock_client(mock_server(quotes).await);
+        let symbols = vec![Instrument::crypto("BTC"), Instrument::crypto("ETH")];
+        let interval = Duration::from_secs(60);
+
+        // Polls at 0, 60 and 120 seconds, the failed poll included
+        let start = tokio::time::Instant::now();
+        let polls = client.poll_quotes(symbols.clone(), interval, Some(3));
+        let results: Vec<_> = polls.collect().await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().len(), 2);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().len(), 2);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= 2 * interval);
+
+        // Without max_polls, polls until dropped
+        let start = tokio::time::Instant::now();
+        let mut polls = std::pin::pin!(client.poll_quotes(symbols.clone(), interval, None));
+        let mut emitted = 0;
+        while let Some(quotes) = polls.next().await {
+            assert!(quotes.is_ok());
+            emitted += 1;
+            if emitted == 5 {
+                break;
+            }
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 8);
+        assert!(start.elapsed() >= 4 * interval && start.elapsed() < 5 * interval);
+
+        // A zero interval polls every second
+        let start = tokio::time::Instant::now();
+        let polls = client.poll_quotes(symbols, Duration::ZERO, Some(3));
+        assert_eq!(polls.collect::<Vec<_>>().await.len(), 3);
+        assert!(start.elapsed() >= 2 * MIN_POLL_INTERVAL);
+    }
+
+    #[tokio::test]
+    async fn test_get_crypto_quotes() {
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(|body: Value| {
+                assert_eq!(
+                    body["instruments"],
+                    json!([
+                        { "symbol": "BTC", "type": "CRYPTO" },
+                        { "symbol": "ETH", "type": "CRYPTO" }
+                    ])
+                );
+                warp::reply::with_header(CRYPTO_QUOTES, "content-type", "application/json")
+            });
+        let client = mock_client(mock_server(quotes).await);
+
+        let quotes = client.get_crypto_quotes(&["BTC", "ETH"]).await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert!(
+            quotes
+                .iter()
+                .all(|q| q.instrument.instrument_type == InstrumentType::Crypto)
+        );
+        assert_eq!(quotes[0].instrument.symbol, "BTC");
+        assert_eq!((quotes[0].bid, quotes[0].ask), (67405.10, 67419.60));
+        assert_eq!(quotes[1].open_interest, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_quotes_chunks() {
+        use std::sync::Mutex;
+
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let requests = sizes.clone();
+        let quotes = warp::path!("userapigateway" / "marketdata" / "TEST" / "quotes")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                let instruments = body["instruments"].as_array().unwrap().clone();
+                requests.lock().unwrap().push(instruments.len());
+                if instruments.iter().any(|i| i["symbol"] == "FAIL") {
+                    return warp::reply::with_status(
+                        warp::reply::json(&json!({})),
+                        StatusCode::BAD_REQUEST,
+                    );
+                }
+                let quotes: Vec<Value> = instruments
+                    .iter()
+                    .map(|instrument| {
+                        json!({
+                            "instrument": instrument,
+                            "outcome": "SUCCESS",
+                            "last": "1.00",
+                            "lastTimestamp": "2026-02-17T20:59:59Z",
+                            "bid": "0.99",
+                            "bidTimestamp": "2026-02-17T20:59:59Z",
+                            "ask": "1.01",
+                            "askTimestamp": "2026-02-17T20:59:59Z",
+                            "volume": 10
+                        })
+                    })
+                    .collect();
+                warp::reply::with_status(
+                    warp::reply::json(&json!({ "quotes": quotes })),
+                    StatusCode::OK,
+                )
+            });
+        let client = mock_client(mock_server(quotes).await);
+        let instrument = |symbol: String| Instrument::equity(&symbol);
+
+        let symbols: Vec<Instrument> = (0..120).map(|i| instrument(format!("S{i}"))).collect();
+        let quotes = client.get_quotes(symbols).await.unwrap();
+        assert_eq!(quotes.len(), 120);
+        assert_eq!(quotes[0].instrument.symbol, "S0");
+        assert_eq!(quotes[119].instrument.symbol, "S119");
+        let mut chunks = sizes.lock().unwrap().clone();
+        chunks.sort();
+        assert_eq!(chunks, vec![20, 50, 50]);
+
+        let mut symbols: Vec<Instrument> = (0..6).map(|i| instrument(format!("S{i}"))).collect();
+        symbols[3] = instrument("FAIL".to_string());
+        let client = client.with_quote_chunk_size(2);
+        let (quotes, errors) = client.get_quotes_partial(symbols.clone()).await;
+        assert_eq!(quotes.len(), 4);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].chunk_index, 1);
+        assert!(client.get_quotes(symbols).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        use crate::public::CircuitState;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let (hits, down) = (
+            Arc::new(AtomicUsize::new(0)),
+            Arc::new(AtomicBool::new(true)),
+        );
+        let (counter, is_down) = (hits.clone(), down.clone());
+        let accounts = warp::path!("userapigateway" / "trading" / "account").map(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let status = match is_down.load(Ordering::SeqCst) {
+                true => StatusCode::SERVICE_UNAVAILABLE,
+                false => StatusCode::OK,
+            };
+            warp::reply::with_status(ACCOUNTS, status)
+        });
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(100));
+        let client = mock_client(mock_server(accounts).await).with_circuit_breaker(breaker.clone());
+
+        for _ in 0..2 {
+            let err = client.get_accounts().await.unwrap_err();
+            assert!(matches!(err, PublicError::StatusCode(503, _)), "{err:?}");
+        }
+        assert!(matches!(
+            client.get_accounts().await,
+            Err(PublicError::CircuitOpen)
+        ));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // The trial after the reset timeout closes the circuit once the API is back
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        down.store(false, Ordering::SeqCst);
+        assert!(client.get_accounts().await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_error_status_code() {
+        let accounts = warp::path!("userapigateway" / "trading" / "account").map(|| {
+            warp::reply::with_status("upstream exploded", StatusCode::INTERNAL_SERVER_ERROR)
+        });
+        let client = mock_client(mock_server(accounts).await);
+
+        let err = client.get_accounts().await.unwrap_err();
+        assert!(
+            matches!(&err, PublicError::StatusCode(500, body) if body == "upstream exploded"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_body() {
+        let long = warp::path!("long")
+            .map(|| warp::reply::with_status("é".repeat(MAX_ERROR_BODY), StatusCode::BAD_REQUEST));
+        let json = warp::path!("json").map(|| {
+            warp::reply::with_status(
+                r#"{"error": "BadRequest", "message": "no symbol"}"#,
+                StatusCode::BAD_REQUEST,
+            )
+        });
+        let client = mock_client(mock_server(long.or(json)).await);
+
+        match client.get("/long").await.unwrap_err() {
+            PublicError::StatusCode(400, body) => {
+                assert_eq!(body.len(), MAX_ERROR_BODY);
+                assert!(body.chars().all(|c| c == 'é'));
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+        assert!(matches!(
+            client.get("/json").await.unwrap_err(),
+            PublicError::ServiceError(error, message, _) if error == "BadRequest" && message == "no symbol"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let route = warp::path("slow").then(|| async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            warp::reply::json(&json!({}))
+        });
+        let config = ClientConfig {
+            request_timeout: Duration::from_secs(1),
+            ..ClientConfig::default()
+        };
+        let client = PublicClient::mock_with_config(mock_server(route).await, &config);
+
+        let started = Instant::now();
+        let err = client.get("/slow").await.unwrap_err();
+        assert!(matches!(err, PublicError::HttpError(_)), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_transport_errors() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        };
+
+        // Nothing listens on the port of a dropped listener
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let client = mock_client(closed.parse().unwrap()).with_retry_policy(policy.clone());
+        let err = client.get("/json").await.unwrap_err();
+        assert!(
+            matches!(err, PublicError::RetriesExhausted { attempts: 2, .. }),
+            "{err}"
+        );
+
+        // A redirect loop fails the same way on every attempt
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let redirect = warp::path("loop").map(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            warp::redirect::found(warp::http::Uri::from_static("/loop"))
+        });
+        let client = mock_client(mock_server(redirect).await).with_retry_policy(policy);
+        let err = client.get("/loop").await.unwrap_err();
+        assert!(matches!(err, PublicError::HttpError(_)), "{err}");
+        let one_attempt = hits.load(Ordering::SeqCst);
+        assert!(one_attempt > 1);
+        assert!(one_attempt <= 11, "{one_attempt} requests");
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests = seen.clone();
+        // Answers in place of the API, which is never resolved
+        let proxy = warp::path("json")
+            .and(warp::header::<String>("host"))
+            .and(warp::header::<String>("proxy-authorization"))
+            .map(move |host: String, auth: String| {
+                requests.lock().unwrap().push((host, auth));
+                warp::reply::json(&json!({}))
+            });
+        let config = ClientConfig {
+            proxy_url: Some(mock_server(proxy).await.to_string()),
+            proxy_auth: Some(("trader".to_string(), "hunter2".to_string())),
+            ..ClientConfig::default()
+        };
+        let api = "http://api.public.invalid".parse().unwrap();
+        let client = PublicClient::mock_with_config(api, &config);
+
+        client.get("/json").await.unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(
+                "api.public.invalid".to_string(),
+                "Basic dHJhZGVyOmh1bnRlcjI=".to_string()
+            )]
+        );
+
+        let debug = format!("{config:?}");
+        assert!(
+            debug.contains("trader") && !debug.contains("hunter2"),
+            "{debug}"
+        );
+
+        let socks = ClientConfig {
+            proxy_url: Some("socks5://127.0.0.1:1080".to_string()),
+            ..ClientConfig::default()
+        };
+        assert!(socks.build_client().is_ok());
+
+        let config = ClientConfig {
+            proxy_url: Some("not a proxy".to_string()),
+            ..ClientConfig::default()
+        };
+        assert!(matches!(
+            config.build_client(),
+            Err(PublicError::InvalidProxy(url)) if url.starts_with("not a proxy")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_requests_above_pool_size() {
+        use std::collections::HashSet;
+        use std::net::SocketAddr;
+
+        // Client address of every request, one per connection
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let addrs = seen.clone();
+        let route =
+            warp::path("slow")
+                .and(warp::addr::remote())
+                .then(move |addr: Option<SocketAddr>| {
+                    addrs.lock().unwrap().push(addr.unwrap());
+                    async {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        warp::reply::json(&json!({}))
+                    }
+                });
+        let config = ClientConfig {
+            max_connections: 2,
+            ..ClientConfig::default()
+        };
+        // Without the default 100ms spacing, which would serialize the requests
+        let client = PublicClient::mock_with_config(mock_server(route).await, &config)
+            .with_rate_limit(RateLimit {
+                requests_per_second: 1000,
+                requests_per_minute: 1000,
+            });
+        let burst = |n: usize| {
+            join_all((0..n).map(|_| async {
+                let res = client.get("/slow").await?;
+                res.bytes().await.map_err(PublicError::from)
+            }))
+        };
+
+        // Requests above the pool size open more connections instead of failing
+        assert!(burst(8).await.iter().all(Result::is_ok));
+        let first: HashSet<SocketAddr> = seen.lock().unwrap().drain(..).collect();
+        assert_eq!(first.len(), 8);
+
+        // Only 2 of them are kept for reuse
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(burst(4).await.iter().all(Result::is_ok));
+        let second: HashSet<SocketAddr> = seen.lock().unwrap().drain(..).collect();
+        assert_eq!(second.len(), 4);
+        assert_eq!(second.intersection(&first).count(), 2);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_correlation_id() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ids = seen.clone();
+        let route = warp::path("fails")
+            .and(warp::header::<String>(CORRELATION_ID_HEADER))
+            .map(move |id: String| {
+                ids.lock().unwrap().push(id);
+                warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "BadRequest", "message": "no symbol" })),
+                    StatusCode::BAD_REQUEST,
+                )
+            });
+        let client = mock_client(mock_server(route).await);
+
+        let err = client.get("/fails").await.unwrap_err();
+        client.get("/fails").await.unwrap_err();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_ne!(seen[0], seen[1]);
+        assert!(matches!(
+            &err,
+            PublicError::ServiceError(_, _, Some(id)) if *id == seen[0]
+        ));
+        assert!(logs_contain(&format!(
+            "http_request{{correlation_id={}}}",
+            seen[0]
+        )));
+        assert!(logs_contain("GET /fails returned 400 Bad Request"));
+    }
+
+    #[tokio::test]
+    async fn test_get_buying_power() {
+        let portfolio = warp::path!("userapigateway" / "trading" / "TEST" / "portfolio" / "v2")
+            .and(warp::get())
+            .map(|| {
+                warp::reply::with_header(ACCOUNT_PORTFOLIO, "content-type", "application/json")
+            });
+
+        let client = mock_client(mock_server(portfolio).await);
+        assert_eq!(client.get_buying_power().await.unwrap(), 229939.82);
+
+        let mut no_account = client.clone();
+        no_account.inner_mut().account_id = None;
+        assert!(matches!(
+            no_account.get_buying_power().await,
+            Err(PublicError::MissingAccountId)
+        ));
+    }
+
     #[test]
     fn test_parse_account_portfolio_with_orders() {
         let accounts: Result<AccountPortfolio, serde_json::Error> =
@@ -591,12 +2367,584 @@ mod tests {
         assert!(accounts.is_ok());
     }
 
+    /// ATM call, S=K=100, r=0, T=1y, sigma=20%: delta=N(0.1), annual theta=-S*phi(0.1)*sigma/2
+    fn atm_call_greeks() -> OptionGreeks {
+        let res: GetOptionGreeksResponse = serde_json::from_value(json!({
+            "greeks": [{
+                "symbol": "TEST270115C00100000",
+                "greeks": {
+                    "delta": "0.539828",
+                    "gamma": 0.019841,
+                    "theta": "-3.969525",
+                    "vega": "39.695255",
+                    "rho": "49.997",
+                    "impliedVolatility": "0.20"
+                }
+            }, {
+                "symbol": "TEST270115P00100000",
+                "greeks": null
+            }]
+        }))
+        .unwrap();
+        let greeks: Vec<OptionGreeks> = res
+            .greeks
+            .iter()
+            .filter_map(|g| OptionGreeks::try_from(g).ok())
+            .collect();
+        assert_eq!(greeks.len(), 1);
+        greeks[0].clone()
+    }
+
+    #[tokio::test]
+    async fn test_get_option_greeks_batch() {
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        let greeks = warp::query::<HashMap<String, String>>()
+            .map(move |params: HashMap<String, String>| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                assert!(params["osiSymbols"].split(',').count() <= MAX_GREEKS_PER_REQUEST);
+            })
+            .untuple_one()
+            .and(mock_greeks(|_| 0.5, 0.3));
+        let client = mock_client(mock_server(greeks).await);
+
+        let expiration = chrono::NaiveDate::from_ymd_opt(2026, 12, 18).unwrap();
+        let symbols: Vec<OsiSymbol> = (1..=300)
+            .map(|strike| OsiSymbol::build("TEST", expiration, OptionType::Call, strike as f64))
+            .collect();
+        let batch = client.get_option_greeks_batch(&symbols).await.unwrap();
+
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(batch.len(), 300);
+        assert_eq!(batch["TEST261218C00042000"].greeks.delta, 0.5);
+    }
+
+    #[test]
+    fn test_option_greeks_helpers() {
+        let greeks = atm_call_greeks();
+        assert_eq!(greeks.greeks.gamma, 0.019841);
+        assert!((greeks.theta_daily() - -0.010875).abs() < 1e-6);
+        // 0.539828 * $100 * 2 contracts * 100 shares, for a $1 move of 1%
+        assert!((greeks.dollar_delta(100.0, 2, 100) - 107.9656).abs() < 1e-9);
+        assert!((greeks.dollar_delta(100.0, -2, 100) + 107.9656).abs() < 1e-9);
+        // $0.40 per IV point, and $0.011 a day, of each share
+        assert!((greeks.dollar_vega(2, 100) - 79.39051).abs() < 1e-9);
+        assert!((greeks.dollar_theta(-1, 100) - 1.087541).abs() < 1e-6);
+        assert_eq!(greeks.annualized_iv(), 0.20);
+    }
+
+    #[test]
+    fn test_second_order_greeks() {
+        use crate::pricing::bs_greeks;
+
+        // S = K = 100, r = 5%, T = 1, sigma = 20%: d1 = 0.35, d2 = 0.15
+        let context = OptionGreeksContext {
+            underlying_price: 100.0,
+            strike: 100.0,
+            dte_years: 1.0,
+            risk_free_rate: 0.05,
+        };
+        let option = |option_type| OptionGreeks {
+            symbol: "TEST270115C00100000".to_string(),
+            greeks: bs_greeks(100.0, 100.0, 0.05, 1.0, 0.2, option_type),
+        };
+        let (call, put) = (option(OptionType::Call), option(OptionType::Put));
+
+        // -N'(d1) * d2 / sigma, per 1% of IV
+        assert!((call.vanna(&context) - -0.00281430).abs() < 1e-7);
+        assert!((put.vanna(&context) - call.vanna(&context)).abs() < 1e-12);
+        // -N'(d1) * (2rT - d2 * sigma * sqrt(T)) / (2T * sigma * sqrt(T)), per day
+        assert!((call.charm(&context) * 365.0 - -0.0656670).abs() < 1e-6);
+        assert!((put.charm(&context) - call.charm(&context)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parse_positions() {
+        let portfolio: AccountPortfolio = serde_json::from_str(ACC_WITH_OPTIONS).unwrap();
+        let positions = portfolio.positions;
+        assert_eq!(positions.len(), 17);
+        assert_eq!(positions.iter().filter(|p| p.is_option()).count(), 4);
+
+        let qcom = positions
+            .iter()
+            .find(|p| p.instrument.symbol == "QCOM260220P00138000")
+            .unwrap();
+        assert!(qcom.is_option());
+        assert_eq!(qcom.quantity, "-1");
+        let cost_basis = qcom.cost_basis.as_ref().unwrap();
+        assert_eq!(cost_basis.total_cost, "-70.01");
+        assert_eq!(cost_basis.gain_value, "6.01");
+        assert_eq!(cost_basis.gain_percentage, "8.58");
+    }
+
+    #[test]
+    fn test_place_limit_order_request() {
+        let instrument = Instrument::from_osi_symbol(&"QCOM260220P00138000".parse().unwrap());
+        let request =
+            PlaceOrderRequest::limit(instrument, OrderSide::Buy, OPIndicator::Close, 1, 1.5);
+        let body = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(body["orderId"].as_str().unwrap().len(), 36);
+        assert_eq!(
+            body["instrument"],
+            json!({ "symbol": "QCOM260220P00138000", "type": "OPTION" })
+        );
+        assert_eq!(body["orderSide"], "BUY");
+        assert_eq!(body["orderType"], "LIMIT");
+        assert_eq!(body["expiration"]["timeInForce"], "DAY");
+        assert_eq!(body["quantity"], "1");
+        assert_eq!(body["limitPrice"], "1.50");
+        assert_eq!(body["openCloseIndicator"], "CLOSE");
+
+        let instrument = Instrument::from_osi_symbol(&"QCOM260220P00138000".parse().unwrap());
+        let request =
+            PlaceOrderRequest::limit(instrument, OrderSide::Sell, OPIndicator::Open, 1, 1.5);
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["openCloseIndicator"], "OPEN");
+
+        let request = PlaceOrderRequest::limit(
+            Instrument::equity("QCOM"),
+            OrderSide::Buy,
+            OPIndicator::Open,
+            10,
+            150.0,
+        );
+        let body = serde_json::to_value(&request).unwrap();
+        assert!(body["openCloseIndicator"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_order_round_trip() {
+        let order_path = warp::path!("userapigateway" / "trading" / "TEST" / "order");
+        let order_id_path = warp::path!("userapigateway" / "trading" / "TEST" / "order" / String);
+
+        let place = order_path
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(|body: Value| {
+                assert_eq!(body["orderType"], "LIMIT");
+                warp::reply::json(&json!({ "orderId": body["orderId"] }))
+            });
+        let status = order_id_path.and(warp::get()).map(|order_id: String| {
+            warp::reply::json(&json!({
+                "orderId": order_id,
+                "instrument": { "symbol": "QCOM260220P00138000", "type": "OPTION" },
+                "type": "LIMIT",
+                "side": "BUY",
+                "status": "FILLED",
+                "closedAt": "2026-02-18T15:01:02Z",
+                "legs": []
+            }))
+        });
+        let cancel = order_id_path
+            .and(warp::delete())
+            .map(|_order_id: String| warp::reply());
+
+        let client = mock_client(mock_server(place.or(status).or(cancel)).await);
+        let instrument = Instrument::from_osi_symbol(&"QCOM260220P00138000".parse().unwrap());
+
+        let receipt = client
+            .place_limit_order(instrument, OrderSide::Buy, OPIndicator::Open, 1, 0.64)
+            .await
+            .unwrap();
+        assert_eq!(receipt.status, OrderStatus::New);
+
+        let polled = client.get_order_status(&receipt.order_id).await.unwrap();
+        assert_eq!(polled.order_id, receipt.order_id);
+        assert_eq!(polled.status, OrderStatus::Filled);
+        assert_eq!(
+            polled.filled_at,
+            Some("2026-02-18T15:01:02Z".parse().unwrap())
+        );
+
+        assert!(client.cancel_order(&receipt.order_id).await.is_ok());
+    }
+
+    #[test]
+    fn test_parse_order_history_page() {
+        let page: OrderHistoryResponse = serde_json::from_str(ORDER_HISTORY_PAGE_1).unwrap();
+        assert_eq!(page.orders.len(), 2);
+        assert_eq!(page.next_token.as_deref(), Some("page-2"));
+
+        let filled = OrderReceipt::from(&page.orders[0]);
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.fill_price, Some(0.62));
+        assert!(filled.filled_at.is_some());
+
+        let cancelled = OrderReceipt::from(&page.orders[1]);
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+        assert!(cancelled.filled_at.is_none());
+        assert!(cancelled.completed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_order_history_pagination() {
+        let history = warp::path!("userapigateway" / "trading" / "TEST" / "orders")
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|params: HashMap<String, String>| {
+                assert_eq!(params["from"], "2026-02-01");
+                assert_eq!(params["to"], "2026-02-28");
+                let page = match params.get("nextToken").map(String::as_str) {
+                    None => ORDER_HISTORY_PAGE_1,
+                    Some("page-2") => ORDER_HISTORY_PAGE_2,
+                    Some(other) => panic!("unexpected token {other}"),
+                };
+                warp::reply::with_header(page, "content-type", "application/json")
+            });
+
+        let client = mock_client(mock_server(history).await);
+        let orders = client
+            .get_order_history(
+                NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 2, 28).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(orders.len(), 3);
+        let statuses: Vec<OrderStatus> = orders.iter().map(|o| o.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                OrderStatus::Filled,
+                OrderStatus::Cancelled,
+                OrderStatus::Rejected
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_option_expirations_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let expirations =
+            warp::path!("userapigateway" / "marketdata" / "TEST" / "option-expirations")
+                .and(warp::post())
+                .and(warp::body::json())
+                .map(move |body: Value| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    warp::reply::json(&json!({
+                        "baseSymbol": body["instrument"]["symbol"],
+                        "expirations": ["2026-02-20", "2026-03-20"]
+                    }))
+                });
+        let base_url = mock_server(expirations).await;
+        let equity = Instrument::equity;
+
+        let client = mock_client(base_url.clone());
+        let first = client.get_option_expirations(equity("QCOM")).await.unwrap();
+        let second = client.get_option_expirations(equity("QCOM")).await.unwrap();
+        assert_eq!(first, vec!["2026-02-20", "2026-03-20"]);
+        assert_eq!(first, second);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        client.get_option_expirations(equity("LMND")).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        client.clear_expiration_cache().await;
+        client.get_option_expirations(equity("QCOM")).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let uncached = mock_client(base_url).with_expiration_cache_ttl(Duration::ZERO);
+        uncached
+            .get_option_expirations(equity("QCOM"))
+            .await
+            .unwrap();
+        uncached
+            .get_option_expirations(equity("QCOM"))
+            .await
+            .unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_get_option_chain_all_expirations() {
+        let expirations =
+            warp::path!("userapigateway" / "marketdata" / "TEST" / "option-expirations")
+                .and(warp::post())
+                .map(|| {
+                    warp::reply::json(&json!({
+                        "baseSymbol": "LMND",
+                        "expirations": ["2026-02-20", "2026-03-20", "2026-04-17"]
+                    }))
+                });
+        // Each chain is tagged with the expiration it was requested for
+        let chains = warp::path!("userapigateway" / "marketdata" / "TEST" / "option-chain")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(|body: Value| {
+                warp::reply::json(&json!({
+                    "baseSymbol": body["expirationDate"],
+                    "calls": [],
+                    "puts": []
+                }))
+            });
+        let client = mock_client(mock_server(expirations.or(chains)).await);
+
+        let chains = client
+            .get_option_chain_all_expirations(Instrument::equity("LMND"))
+            .await
+            .unwrap();
+        assert_eq!(chains.len(), 3);
+        for (expiration, chain) in &chains {
+            assert_eq!(expiration, &chain.base_symbol);
+        }
+        let dates: Vec<&str> = chains.iter().map(|(e, _)| e.as_str()).collect();
+        assert_eq!(dates, vec!["2026-02-20", "2026-03-20", "2026-04-17"]);
+    }
+
+    #[tokio::test]
+    async fn test_cloned_client_concurrent_tasks() {
+        let portfolio = warp::path!("userapigateway" / "trading" / "TEST" / "portfolio" / "v2")
+            .and(warp::get())
+            .map(|| {
+                warp::reply::with_header(ACCOUNT_PORTFOLIO, "content-type", "application/json")
+            });
+        let client = mock_client(mock_server(portfolio).await);
+
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.get_buying_power().await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), 229939.82);
+        }
+        assert!(Arc::ptr_eq(&client.inner, &client.clone().inner));
+    }
+
     #[test]
     fn test_parse_accounts() {
         let accounts: Result<AccountsResponse, serde_json::Error> = serde_json::from_str(ACCOUNTS);
         if let Err(e) = &accounts {
             println!("Error {e:?}");
         }
-        assert!(accounts.is_ok());
+        let levels: Vec<OptionsLevel> = accounts
+            .unwrap()
+            .accounts
+            .iter()
+            .map(|a| a.options_level)
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                OptionsLevel::Level0,
+                OptionsLevel::Level3,
+                OptionsLevel::Level2
+            ]
+        );
+    }
+
+    #[test]
+    fn test_options_level() {
+        for level in ["NONE", "LEVEL_1", "LEVEL_2", "LEVEL_3", "LEVEL_4"] {
+            let parsed: OptionsLevel = level.parse().unwrap();
+            assert_eq!(parsed.to_string(), level);
+            assert_eq!(
+                serde_json::to_string(&parsed).unwrap(),
+                format!("\"{level}\"")
+            );
+        }
+        assert!("LEVEL_5".parse::<OptionsLevel>().is_err());
+        assert!(OptionsLevel::Level2 < OptionsLevel::Level3);
+        assert!(!OptionsLevel::Level2.can_trade_spreads());
+        assert!(OptionsLevel::Level4.can_trade_spreads());
+    }
+
+    #[tokio::test]
+    async fn test_get_market_hours() {
+        let hours =
+            warp::path!("userapigateway" / "marketdata" / "hours" / String).map(|date: String| {
+                let body = if date == "2026-12-25" {
+                    MARKET_HOURS_HOLIDAY
+                } else {
+                    MARKET_HOURS_OPEN
+                };
+                warp::reply::with_header(body, "content-type", "application/json")
+            });
+        let client = mock_client(mock_server(hours).await);
+
+        let holiday = client
+            .get_market_hours(NaiveDate::from_ymd_opt(2026, 12, 25).unwrap())
+            .await
+            .unwrap();
+        assert!(!holiday.is_open);
+        assert_eq!(holiday.open, None);
+        assert!(!holiday.is_currently_open());
+
+        let day = client
+            .get_market_hours(NaiveDate::from_ymd_opt(2026, 10, 14).unwrap())
+            .await
+            .unwrap();
+        assert!(day.is_open);
+        let at = |time: &str| day.is_open_at(time.parse().unwrap());
+        assert!(at("2026-10-14T13:30:00Z"));
+        assert!(at("2026-10-14T17:00:00Z"));
+        assert!(!at("2026-10-14T20:00:00Z"));
+        assert!(!at("2026-10-14T13:00:00Z"));
+        assert!(!holiday.is_open_at("2026-12-25T17:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_option_type() {
+        for (s, option_type) in [
+            ("Call", OptionType::Call),
+            ("CALL", OptionType::Call),
+            ("call", OptionType::Call),
+            ("Put", OptionType::Put),
+            ("PUT", OptionType::Put),
+            ("put", OptionType::Put),
+        ] {
+            assert_eq!(s.parse::<OptionType>(), Ok(option_type));
+        }
+        assert!("Straddle".parse::<OptionType>().is_err());
+        assert!("".parse::<OptionType>().is_err());
+
+        assert_eq!(OptionType::Call.to_string(), "Call");
+        assert_eq!(OptionType::Put.to_string(), "Put");
+        assert!(OptionType::Call.is_call() && !OptionType::Call.is_put());
+        assert!(OptionType::Put.is_put() && !OptionType::Put.is_call());
+    }
+
+    #[test]
+    fn test_account_type() {
+        use clap::ValueEnum;
+
+        for account_type in AccountType::value_variants() {
+            let json = serde_json::to_string(account_type).unwrap();
+            assert_eq!(json, format!("\"{account_type}\""));
+        }
+        assert_eq!(AccountType::RothIra.as_str(), "ROTH_IRA");
+    }
+
+    #[test]
+    fn test_instrument_type() {
+        for instrument_type in InstrumentType::ALL {
+            let name: &'static str = instrument_type.clone().into();
+            let json = serde_json::to_string(&instrument_type).unwrap();
+            assert_eq!(json, format!("\"{name}\""));
+            assert_eq!(
+                serde_json::from_str::<InstrumentType>(&json).unwrap(),
+                instrument_type
+            );
+            assert_eq!(instrument_type.to_string(), name);
+            assert_eq!(name.parse(), Ok(instrument_type.clone()));
+            assert_eq!(format!("{instrument_type:?}").parse(), Ok(instrument_type));
+        }
+        assert_eq!(
+            InstrumentType::MultiLegInstrument.as_str(),
+            "MULTI_LEG_INSTRUMENT"
+        );
+        assert!("equity".parse::<InstrumentType>().is_err());
+    }
+
+    #[test]
+    fn test_order_side() {
+        for (s, side) in [
+            ("buy", OrderSide::Buy),
+            ("BUY", OrderSide::Buy),
+            ("sell", OrderSide::Sell),
+            ("Sell", OrderSide::Sell),
+        ] {
+            assert_eq!(s.parse::<OrderSide>(), Ok(side.clone()));
+            assert_eq!(side.to_string(), s.to_lowercase());
+        }
+        assert!("short".parse::<OrderSide>().is_err());
+        assert_eq!(serde_json::to_string(&OrderSide::Sell).unwrap(), "\"SELL\"");
+    }
+
+    #[tokio::test]
+    async fn test_set_account() {
+        let accounts = warp::path!("userapigateway" / "trading" / "account")
+            .map(|| warp::reply::with_header(ACCOUNTS, "content-type", "application/json"));
+        let mut client = mock_client(mock_server(accounts).await);
+
+        client.set_account(AccountType::RothIra).await.unwrap();
+        assert_eq!(client.inner.account_id.as_deref(), Some("5OD27341"));
+        client.set_account_by_id("5OT13772").await.unwrap();
+        assert_eq!(client.inner.account_id.as_deref(), Some("5OT13772"));
+
+        assert!(matches!(
+            client.set_account(AccountType::Treasury).await,
+            Err(PublicError::AccountNotFound(wanted)) if wanted == "Treasury"
+        ));
+        assert!(matches!(
+            client.set_account_by_id("MISSING").await,
+            Err(PublicError::AccountNotFound(wanted)) if wanted == "MISSING"
+        ));
+        assert_eq!(client.inner.account_id.as_deref(), Some("5OT13772"));
+    }
+
+    #[tokio::test]
+    async fn test_can_trade_spreads() {
+        let accounts = warp::path!("userapigateway" / "trading" / "account")
+            .map(|| warp::reply::with_header(ACCOUNTS, "content-type", "application/json"));
+        let mut client = mock_client(mock_server(accounts).await);
+
+        for (account_id, can_trade) in [("5LI70019", true), ("5OD27341", false)] {
+            client.inner_mut().account_id = Some(account_id.to_string());
+            assert_eq!(client.can_trade_spreads().await.unwrap(), can_trade);
+        }
+        // Selected, but not among the accounts of the fixture
+        client.inner_mut().account_id = Some("UNKNOWN".to_string());
+        assert!(matches!(
+            client.can_trade_spreads().await,
+            Err(PublicError::AccountNotFound(wanted)) if wanted == "UNKNOWN"
+        ));
+
+        client.inner_mut().account_id = None;
+        assert!(matches!(
+            client.can_trade_spreads().await,
+            Err(PublicError::MissingAccountId)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let accounts = warp::path!("userapigateway" / "trading" / "account")
+            .map(|| warp::reply::with_header(ACCOUNTS, "content-type", "application/json"));
+        let client = mock_client(mock_server(accounts).await);
+        let status = client.health_check().await.unwrap();
+        assert!(status.api_reachable && status.authenticated);
+        assert_eq!(status.account_count, 3);
+
+        let unauthorized = warp::path!("userapigateway" / "trading" / "account").map(|| {
+            warp::reply::with_status(
+                warp::reply::json(&json!({ "error": "Unauthorized", "message": "bad token" })),
+                StatusCode::UNAUTHORIZED,
+            )
+        });
+        let client = mock_client(mock_server(unauthorized).await);
+        let status = client.health_check().await.unwrap();
+        assert!(status.api_reachable);
+        assert!(!status.authenticated);
+        assert_eq!(status.account_count, 0);
+
+        let unavailable = warp::path!("userapigateway" / "trading" / "account")
+            .map(|| warp::reply::with_status("down", StatusCode::SERVICE_UNAVAILABLE));
+        let client = mock_client(mock_server(unavailable).await);
+        let status = client.health_check().await.unwrap();
+        assert!(!status.api_reachable && !status.authenticated);
+
+        // Other rejected requests are errors, not rejected credentials
+        let not_found = warp::path!("userapigateway" / "trading" / "account").map(|| {
+            warp::reply::with_status(
+                warp::reply::json(&json!({ "error": "NotFound", "message": "no route" })),
+                StatusCode::NOT_FOUND,
+            )
+        });
+        let client = mock_client(mock_server(not_found).await);
+        assert!(matches!(
+            client.health_check().await,
+            Err(PublicError::ServiceError(..))
+        ));
+        let forbidden = warp::path!("userapigateway" / "trading" / "account")
+            .map(|| warp::reply::with_status("forbidden", StatusCode::FORBIDDEN));
+        let client = mock_client(mock_server(forbidden).await);
+        assert!(!client.health_check().await.unwrap().authenticated);
     }
 }
@@ -0,0 +1,136 @@
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, sleep};
+use tracing::trace;
+
+const DEFAULT_REQUESTS_PER_SECOND: u32 = 10;
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 300;
+
+/// Request budget for the Public API, read from the `[rate_limit]` config table
+/// or passed to `PublicClient::with_rate_limit`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: u32,
+    pub requests_per_minute: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: DEFAULT_REQUESTS_PER_SECOND,
+            requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+        }
+    }
+}
+
+/// Throttles requests so consecutive calls are spaced at least `1 / requests_per_second`
+/// apart, and no more than `requests_per_minute` go out in any sliding minute (token bucket).
+pub struct RateLimiter {
+    interval: Duration,
+    per_minute: f64,
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    next_slot: Instant,
+    minute_tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(limit: &RateLimit) -> RateLimiter {
+        let per_second = limit.requests_per_second.max(1);
+        let per_minute = limit.requests_per_minute.max(1) as f64;
+        let now = Instant::now();
+
+        RateLimiter {
+            interval: Duration::from_secs(1) / per_second,
+            per_minute,
+            state: Mutex::new(LimiterState {
+                next_slot: now,
+                minute_tokens: per_minute,
+                last_refill: now,
+            }),
+        }
+    }
+
+    /// Waits until the budget allows another request, then consumes one slot.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+
+                let refill = now.duration_since(state.last_refill).as_secs_f64() / 60.0;
+                state.minute_tokens =
+                    (state.minute_tokens + refill * self.per_minute).min(self.per_minute);
+                state.last_refill = now;
+
+                let slot_wait = state.next_slot.saturating_duration_since(now);
+                let token_wait = if state.minute_tokens >= 1.0 {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs_f64((1.0 - state.minute_tokens) * 60.0 / self.per_minute)
+                };
+
+                let wait = slot_wait.max(token_wait);
+                if wait.is_zero() {
+                    state.minute_tokens -= 1.0;
+                    state.next_slot = now + self.interval;
+                    return;
+                }
+
+                wait
+            };
+
+            trace!("rate limited, waiting {wait:?}");
+            sleep(wait).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(&RateLimit::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_per_second_spacing() {
+        let limiter = RateLimiter::new(&RateLimit {
+            requests_per_second: 4,
+            requests_per_minute: 1000,
+        });
+
+        let n = 9;
+        let start = Instant::now();
+        for _ in 0..n {
+            limiter.acquire().await;
+        }
+
+        let min_elapsed = Duration::from_secs_f64((n - 1) as f64 / 4.0);
+        assert!(start.elapsed() >= min_elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_per_minute_budget() {
+        let limiter = RateLimiter::new(&RateLimit {
+            requests_per_second: 100,
+            requests_per_minute: 5,
+        });
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_secs(11));
+    }
+}
@@ -0,0 +1,184 @@
+use super::PublicError;
+
+use reqwest::StatusCode;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::warn;
+
+const DEFAULT_MAX_ATTEMPTS: u8 = 3;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(250);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// How a failed request is retried: up to `max_attempts` tries in total, waiting an
+/// exponentially growing, jittered delay between `base_delay` and `max_delay`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u8,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+/// Outcome of a single attempt made under a `RetryPolicy`.
+pub enum Attempt<T> {
+    /// Final result, returned to the caller as is.
+    Done(Result<T, PublicError>),
+    /// Failure worth retrying, like a network reset or a 503.
+    Transient(PublicError),
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Runs `attempt` until it is `Done` or `max_attempts` is reached.
+    pub async fn retry<T, F, Fut>(&self, mut attempt: F) -> Result<T, PublicError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Attempt<T>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            let error = match attempt().await {
+                Attempt::Done(result) => return result,
+                Attempt::Transient(e) => e,
+            };
+
+            if attempts >= max_attempts {
+                if attempts == 1 {
+                    return Err(error);
+                }
+                return Err(PublicError::RetriesExhausted {
+                    attempts,
+                    last: Box::new(error),
+                });
+            }
+
+            let delay = self.delay(attempts);
+            warn!("Attempt {attempts}/{max_attempts} failed, retrying in {delay:?}: {error:?}");
+            sleep(delay).await;
+        }
+    }
+
+    /// Delay before the next try, after `attempts` tries have failed.
+    /// Uses "equal jitter": half the backoff is fixed, the other half is random.
+    pub(crate) fn delay(&self, attempts: u8) -> Duration {
+        let exp = 2u32.saturating_pow(u32::from(attempts.saturating_sub(1)));
+        let backoff = self.base_delay.saturating_mul(exp).min(self.max_delay);
+        let half = backoff / 2;
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+        let jitter = half.mul_f64(f64::from(nanos) / 1e9);
+
+        half + jitter
+    }
+}
+
+/// Statuses that signal a temporary condition on the server side.
+pub fn is_transient_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Errors sending a request that a new attempt may not hit: failing to connect,
+/// timing out, or losing the connection while sending. Errors like a redirect
+/// loop or an unreadable body fail the same way every time.
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    fn policy(max_attempts: u8) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Fails with a transient error `failures` times, then succeeds.
+    async fn flaky(calls: &AtomicU8, failures: u8) -> Attempt<&'static str> {
+        if calls.fetch_add(1, Ordering::SeqCst) < failures {
+            Attempt::Transient(PublicError::HttpError("connection reset".to_string()))
+        } else {
+            Attempt::Done(Ok("ok"))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_recovers() {
+        let calls = AtomicU8::new(0);
+        let res = policy(4).retry(|| flaky(&calls, 3)).await;
+        assert_eq!(res.unwrap(), "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_exhausted() {
+        let calls = AtomicU8::new(0);
+        let res = policy(3).retry(|| flaky(&calls, 5)).await;
+        match res {
+            Err(PublicError::RetriesExhausted { attempts, last }) => {
+                assert_eq!(attempts, 3);
+                assert!(matches!(*last, PublicError::HttpError(_)));
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_done_error_not_retried() {
+        let calls = AtomicU8::new(0);
+        let res: Result<(), PublicError> = policy(3)
+            .retry(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Attempt::Done(Err(PublicError::InvalidUri))
+            })
+            .await;
+        assert!(matches!(res, Err(PublicError::InvalidUri)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_delay_bounds() {
+        let policy = policy(10);
+        assert!(policy.delay(1) >= Duration::from_millis(50));
+        assert!(policy.delay(1) <= Duration::from_millis(100));
+        assert!(policy.delay(3) >= Duration::from_millis(200));
+        assert!(policy.delay(9) >= Duration::from_millis(500));
+        assert!(policy.delay(9) <= policy.max_delay);
+    }
+
+    #[test]
+    fn test_transient_status() {
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::NOT_IMPLEMENTED));
+    }
+}
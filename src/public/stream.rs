@@ -0,0 +1,305 @@
+use super::model::{Instrument, Quote};
+use super::public::{PublicClient, PublicError};
+use super::retry::RetryPolicy;
+
+use futures::{SinkExt, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{debug, info, warn};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// What a quote stream yields, `Disconnect` is followed by a reconnect.
+/// Quotes are yielded one at a time, so they aren't boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum QuoteStreamEvent {
+    Quote(Quote),
+    Heartbeat,
+    Disconnect,
+}
+
+/// Messages sent by the server, like `{"type": "quote", "quote": {...}}`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum StreamMessage {
+    Quote { quote: Box<Quote> },
+    Heartbeat,
+}
+
+#[derive(Serialize)]
+struct SubscribeRequest<'a> {
+    action: &'static str,
+    instruments: &'a [Instrument],
+}
+
+/// WebSocket subscribed to quotes of `instruments`. When the connection
+/// drops it reconnects and subscribes again, giving up after
+/// `retry_policy.max_attempts` failed connections in a row.
+pub(super) struct QuoteStream {
+    client: PublicClient,
+    url: String,
+    instruments: Vec<Instrument>,
+    retry_policy: RetryPolicy,
+    socket: Option<Socket>,
+    failures: u8,
+    done: bool,
+}
+
+impl QuoteStream {
+    pub(super) async fn connect(
+        client: PublicClient,
+        url: String,
+        instruments: Vec<Instrument>,
+        retry_policy: RetryPolicy,
+    ) -> Result<QuoteStream, PublicError> {
+        let socket = connect(&client, &url, &instruments).await?;
+
+        Ok(QuoteStream {
+            client,
+            url,
+            instruments,
+            retry_policy,
+            socket: Some(socket),
+            failures: 0,
+            done: false,
+        })
+    }
+
+    pub(super) fn into_stream(self) -> impl Stream<Item = Result<QuoteStreamEvent, PublicError>> {
+        futures::stream::unfold(self, |mut stream| async move {
+            let event = stream.next_event().await?;
+            Some((event, stream))
+        })
+    }
+
+    async fn next_event(&mut self) -> Option<Result<QuoteStreamEvent, PublicError>> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let Some(socket) = &mut self.socket else {
+                if let Err(e) = self.reconnect().await {
+                    return Some(Err(e));
+                }
+                continue;
+            };
+
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(text.as_str()) {
+                    Ok(StreamMessage::Quote { quote }) => {
+                        return Some(Ok(QuoteStreamEvent::Quote(*quote)));
+                    }
+                    Ok(StreamMessage::Heartbeat) => return Some(Ok(QuoteStreamEvent::Heartbeat)),
+                    Err(e) => warn!("Ignoring quote stream message <{text}>: {e}"),
+                },
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => {
+                    return Some(Ok(QuoteStreamEvent::Heartbeat));
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    info!("Quote stream closed");
+                    self.socket = None;
+                    return Some(Ok(QuoteStreamEvent::Disconnect));
+                }
+                Some(Err(e)) => {
+                    warn!("Quote stream dropped: {e}");
+                    self.socket = None;
+                    return Some(Ok(QuoteStreamEvent::Disconnect));
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    /// Waits out the backoff, then connects again. Errors once the policy
+    /// runs out of attempts, which ends the stream.
+    async fn reconnect(&mut self) -> Result<(), PublicError> {
+        loop {
+            let delay = self.retry_policy.delay(self.failures.saturating_add(1));
+            debug!("Reconnecting the quote stream in {delay:?}");
+            sleep(delay).await;
+
+            match connect(&self.client, &self.url, &self.instruments).await {
+                Ok(socket) => {
+                    self.socket = Some(socket);
+                    self.failures = 0;
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.failures = self.failures.saturating_add(1);
+                    if self.failures >= self.retry_policy.max_attempts.max(1) {
+                        self.done = true;
+                        return Err(PublicError::RetriesExhausted {
+                            attempts: self.failures,
+                            last: Box::new(e),
+                        });
+                    }
+                    warn!("Quote stream reconnect failed: {e}");
+                }
+            }
+        }
+    }
+}
+
+fn ws_error(e: tungstenite::Error) -> PublicError {
+    PublicError::HttpError(e.to_string())
+}
+
+/// Opens the socket with the access token on the upgrade request, then
+/// subscribes to `instruments`.
+async fn connect(
+    client: &PublicClient,
+    url: &str,
+    instruments: &[Instrument],
+) -> Result<Socket, PublicError> {
+    let token = client.access_token().await?;
+    let mut request = url.into_client_request().map_err(ws_error)?;
+    let auth = HeaderValue::from_str(&format!("Bearer {token}"))
+        .map_err(|_| PublicError::MissingCredentials)?;
+    request.headers_mut().insert(AUTHORIZATION, auth);
+
+    let (mut socket, _) = connect_async(request).await.map_err(ws_error)?;
+    let subscribe = SubscribeRequest {
+        action: "subscribe",
+        instruments,
+    };
+    let subscribe = serde_json::to_string(&subscribe).map_err(|_| PublicError::ParseError)?;
+    socket
+        .send(Message::text(subscribe))
+        .await
+        .map_err(ws_error)?;
+    info!("Subscribed to quotes of {} instruments", instruments.len());
+
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::mock::mock_client;
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+    fn quote_message(symbol: &str) -> Message {
+        let quote = json!({
+            "type": "quote",
+            "quote": {
+                "instrument": { "symbol": symbol, "type": "EQUITY" },
+                "outcome": "SUCCESS",
+                "last": "1.00",
+                "lastTimestamp": "2026-02-17T20:59:59Z",
+                "bid": "0.99",
+                "bidTimestamp": "2026-02-17T20:59:59Z",
+                "ask": "1.01",
+                "askTimestamp": "2026-02-17T20:59:59Z",
+                "volume": 10
+            }
+        });
+        Message::text(quote.to_string())
+    }
+
+    /// Accepts two connections: sends two quotes then closes the first,
+    /// sends one quote on the second and keeps it open.
+    #[allow(clippy::result_large_err)] // the handshake callback's error type
+    async fn mock_ws_server() -> reqwest::Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for symbols in [vec!["AAPL", "QCOM"], vec!["AMD"]] {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let check_auth = |req: &Request, res: Response| {
+                    assert_eq!(
+                        req.uri().path(),
+                        "/userapigateway/marketdata/TEST/quotes/stream"
+                    );
+                    assert_eq!(req.headers()[AUTHORIZATION], "Bearer test-token");
+                    Ok(res)
+                };
+                let mut socket = tokio_tungstenite::accept_hdr_async(tcp, check_auth)
+                    .await
+                    .unwrap();
+
+                let subscribe = socket.next().await.unwrap().unwrap();
+                let subscribe: Value = serde_json::from_str(subscribe.to_text().unwrap()).unwrap();
+                assert_eq!(subscribe["action"], "subscribe");
+                assert_eq!(subscribe["instruments"][0]["symbol"], "AAPL");
+
+                for symbol in &symbols {
+                    socket.send(quote_message(symbol)).await.unwrap();
+                }
+                socket
+                    .send(Message::text(r#"{"type":"heartbeat"}"#))
+                    .await
+                    .unwrap();
+                if symbols.len() == 2 {
+                    socket.close(None).await.unwrap();
+                } else {
+                    tokio::spawn(async move { while socket.next().await.is_some() {} });
+                }
+            }
+        });
+
+        format!("http://{addr}").parse().unwrap()
+    }
+
+    fn instruments() -> Vec<Instrument> {
+        vec![Instrument::equity("AAPL")]
+    }
+
+    #[tokio::test]
+    async fn test_stream_quote_events_reconnect() {
+        let client = mock_client(mock_ws_server().await);
+        let events = client.stream_quote_events(instruments()).await.unwrap();
+        let events: Vec<QuoteStreamEvent> = events.take(6).map(Result::unwrap).collect().await;
+
+        let symbol = |event: &QuoteStreamEvent| match event {
+            QuoteStreamEvent::Quote(quote) => quote.instrument.symbol.clone(),
+            QuoteStreamEvent::Heartbeat => "heartbeat".to_string(),
+            QuoteStreamEvent::Disconnect => "disconnect".to_string(),
+        };
+        let events: Vec<String> = events.iter().map(symbol).collect();
+        assert_eq!(
+            events,
+            vec![
+                "AAPL",
+                "QCOM",
+                "heartbeat",
+                "disconnect",
+                "AMD",
+                "heartbeat"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_quotes() {
+        let client = mock_client(mock_ws_server().await);
+        let quotes = client.stream_quotes(instruments()).await.unwrap();
+        let symbols: Vec<String> = quotes
+            .take(3)
+            .map(|quote| quote.unwrap().instrument.symbol)
+            .collect()
+            .await;
+        assert_eq!(symbols, vec!["AAPL", "QCOM", "AMD"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_quotes_connect_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client = mock_client(format!("http://{addr}").parse().unwrap());
+        assert!(matches!(
+            client.stream_quotes(instruments()).await,
+            Err(PublicError::HttpError(_))
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::options::PortfolioGreeks;
+use crate::pricing::norm_inv_cdf;
+
+/// Confidence level of the VaR `OptionsStopper` prints.
+pub const DEFAULT_VAR_CONFIDENCE: f64 = 0.95;
+/// Trading days the VaR `OptionsStopper` prints is over.
+pub const DEFAULT_VAR_HORIZON_DAYS: u32 = 1;
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Parametric (delta-gamma) Value at Risk of positions on one underlying, in dollars:
+/// the loss of a `confidence` percentile move of the underlying over
+/// `time_horizon_days` trading days, at the annualized `portfolio_iv`.
+/// Moves both ways are tried, the larger loss is the VaR, zero if both gain.
+pub fn compute_var(
+    greeks: &PortfolioGreeks,
+    underlying_price: f64,
+    portfolio_iv: f64,
+    confidence: f64,
+    time_horizon_days: u32,
+) -> f64 {
+    let horizon = (time_horizon_days as f64 / TRADING_DAYS_PER_YEAR).sqrt();
+    let move_dollar = underlying_price * portfolio_iv * horizon * norm_inv_cdf(confidence);
+    let pnl = |ds: f64| greeks.delta * ds + 0.5 * greeks.gamma * ds * ds;
+
+    (-pnl(move_dollar)).max(-pnl(-move_dollar)).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn greeks(delta: f64, gamma: f64) -> PortfolioGreeks {
+        PortfolioGreeks {
+            delta,
+            gamma,
+            ..PortfolioGreeks::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_var() {
+        let var = |delta, gamma| compute_var(&greeks(delta, gamma), 100.0, 0.2, 0.95, 1);
+        // $100 at 20% IV moves $2.07 in a day at 95%
+        let move_dollar = 100.0 * 0.2 * (1.0f64 / 252.0).sqrt() * 1.6448536;
+
+        assert_eq!(var(0.0, 0.0), 0.0);
+        assert!((var(100.0, 0.0) - 100.0 * move_dollar).abs() < 1e-4);
+        assert_eq!(var(100.0, 0.0), var(-100.0, 0.0));
+
+        // Short gamma loses on a move either way, long gamma gains
+        let short_gamma = var(0.0, -10.0);
+        assert!((short_gamma - 5.0 * move_dollar * move_dollar).abs() < 1e-4);
+        assert_eq!(var(0.0, 10.0), 0.0);
+        assert!(var(100.0, -10.0) > var(100.0, 0.0));
+
+        // Grows with the square root of the horizon
+        let week = compute_var(&greeks(100.0, 0.0), 100.0, 0.2, 0.95, 4);
+        assert!((week - 2.0 * var(100.0, 0.0)).abs() < 1e-9);
+    }
+}
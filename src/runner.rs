@@ -0,0 +1,196 @@
+use futures::future::BoxFuture;
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::public::PublicError;
+
+/// A trading job run against the API, like `OptionsStopper`.
+/// `run` returns a boxed future so strategies can be stored as trait objects.
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn run(&self) -> BoxFuture<'_, Result<(), PublicError>>;
+
+    /// Picks up the settings of an edited config, before the next `run`.
+    fn reload(&mut self, _config: &Config) {}
+}
+
+/// Strategies that failed during `StrategyRunner::run_all`, by name.
+#[derive(Debug)]
+pub struct RunnerError {
+    pub failures: Vec<(String, PublicError)>,
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} strategies failed:", self.failures.len())?;
+        for (name, e) in &self.failures {
+            write!(f, " [{name}: {e}]")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RunnerError {}
+
+#[derive(Default)]
+pub struct StrategyRunner {
+    strategies: Vec<Box<dyn Strategy>>,
+    /// Latest config, from `Config::watch`.
+    config: Option<watch::Receiver<Config>>,
+}
+
+impl StrategyRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Strategy + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// Reloads the strategies whenever `config` changes.
+    pub fn with_config(mut self, config: watch::Receiver<Config>) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    fn reload(&mut self) {
+        let Some(config) = &mut self.config else {
+            return;
+        };
+        if !config.has_changed().unwrap_or(false) {
+            return;
+        }
+
+        let config = config.borrow_and_update().clone();
+        for strategy in &mut self.strategies {
+            info!("Reloading strategy {}", strategy.name());
+            strategy.reload(&config);
+        }
+    }
+
+    /// Runs the strategies one after the other. A failing strategy doesn't
+    /// stop the ones after it, every failure is returned at the end.
+    pub async fn run_all(&mut self) -> Result<(), RunnerError> {
+        self.reload();
+        let mut failures = Vec::new();
+        for strategy in &self.strategies {
+            info!("Running strategy {}", strategy.name());
+            if let Err(e) = strategy.run().await {
+                error!("Strategy {} failed: {e}", strategy.name());
+                failures.push((strategy.name().to_string(), e));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(RunnerError { failures })
+        }
+    }
+
+    /// Runs the strategies every `interval`, until the process is stopped.
+    pub async fn run_every(&mut self, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.run_all().await {
+                error!("Run error: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockStrategy {
+        name: String,
+        fails: bool,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Strategy for MockStrategy {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn reload(&mut self, config: &Config) {
+            self.fails = config.get("fails").is_some();
+        }
+
+        fn run(&self) -> BoxFuture<'_, Result<(), PublicError>> {
+            Box::pin(async move {
+                self.runs.fetch_add(1, Ordering::SeqCst);
+                if self.fails {
+                    Err(PublicError::HttpError(format!("{} failed", self.name)))
+                } else {
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_all_reports_every_failure() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let strategy = |name: &str, fails: bool| MockStrategy {
+            name: name.to_string(),
+            fails,
+            runs: runs.clone(),
+        };
+        let mut runner = StrategyRunner::new()
+            .with_strategy(strategy("first", true))
+            .with_strategy(strategy("second", false))
+            .with_strategy(strategy("third", true));
+
+        let err = runner.run_all().await.unwrap_err();
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let failed: Vec<&str> = err.failures.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(failed, vec!["first", "third"]);
+        assert!(err.to_string().starts_with("2 strategies failed:"));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_ok() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut runner = StrategyRunner::new().with_strategy(MockStrategy {
+            name: "only".to_string(),
+            fails: false,
+            runs: runs.clone(),
+        });
+
+        assert!(runner.run_all().await.is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(StrategyRunner::new().run_all().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_all_reloads_config() {
+        let config = Config::from_str;
+        let (tx, rx) = watch::channel(config("").unwrap());
+        let mut runner = StrategyRunner::new()
+            .with_strategy(MockStrategy {
+                name: "only".to_string(),
+                fails: false,
+                runs: Arc::new(AtomicUsize::new(0)),
+            })
+            .with_config(rx);
+        assert!(runner.run_all().await.is_ok());
+
+        tx.send(config("fails = ['AAPL']").unwrap()).unwrap();
+        assert!(runner.run_all().await.is_err());
+        // Not reloaded again without a change
+        runner.strategies[0].reload(&config("").unwrap());
+        assert!(runner.run_all().await.is_ok());
+    }
+}
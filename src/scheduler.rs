@@ -0,0 +1,99 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::runner::Strategy;
+
+/// Runs a strategy every `interval`, like `OptionsStopper` in a long running
+/// deployment. A run that fails or panics is logged, and doesn't stop the next.
+pub struct Scheduler {
+    interval: Duration,
+    /// Shared with the task each run is spawned in, so a panic stays in the task.
+    strategy: Arc<dyn Strategy>,
+}
+
+impl Scheduler {
+    pub fn new(strategy: impl Strategy + 'static, interval: Duration) -> Self {
+        Self {
+            interval,
+            strategy: Arc::new(strategy),
+        }
+    }
+
+    /// Runs the strategy right away, then every `interval`, until the process is stopped.
+    pub async fn run_forever(&self) {
+        let mut ticks = tokio::time::interval(self.interval);
+        loop {
+            ticks.tick().await;
+            self.run_once().await;
+        }
+    }
+
+    async fn run_once(&self) {
+        let name = self.strategy.name().to_string();
+        info!("Starting run of {name}");
+        let start = Instant::now();
+
+        let strategy = self.strategy.clone();
+        let run = tokio::spawn(async move { strategy.run().await });
+        match run.await {
+            Ok(Ok(())) => info!("Run of {name} finished in {:?}", start.elapsed()),
+            Ok(Err(e)) => error!("Run of {name} failed after {:?}: {e}", start.elapsed()),
+            Err(e) => error!("Run of {name} panicked after {:?}: {e}", start.elapsed()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::PublicError;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingStrategy {
+        runs: Arc<AtomicUsize>,
+        panics: bool,
+    }
+
+    impl Strategy for CountingStrategy {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn run(&self) -> BoxFuture<'_, Result<(), PublicError>> {
+            Box::pin(async move {
+                self.runs.fetch_add(1, Ordering::SeqCst);
+                if self.panics {
+                    panic!("strategy panicked");
+                }
+                Ok(())
+            })
+        }
+    }
+
+    /// Runs `scheduler` makes over `elapsed`.
+    async fn count_runs(scheduler: Scheduler, runs: Arc<AtomicUsize>, elapsed: Duration) -> usize {
+        let task = tokio::spawn(async move { scheduler.run_forever().await });
+        tokio::time::sleep(elapsed).await;
+        task.abort();
+
+        runs.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_forever() {
+        let interval = Duration::from_secs(60);
+        for panics in [false, true] {
+            let runs = Arc::new(AtomicUsize::new(0));
+            let strategy = CountingStrategy {
+                runs: runs.clone(),
+                panics,
+            };
+            // Runs at 0, 60 and 120 seconds, panics included
+            let scheduler = Scheduler::new(strategy, interval);
+            let elapsed = Duration::from_secs(150);
+            assert_eq!(count_runs(scheduler, runs, elapsed).await, 3);
+        }
+    }
+}
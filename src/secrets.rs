@@ -0,0 +1,155 @@
+use anyhow::{Result, bail};
+use futures::future::BoxFuture;
+use std::env;
+use tracing::{debug, info};
+
+use crate::bitwarden::{Bitwarden, bw_config_path};
+
+const AWS_REGION_VAR: &str = "AWS_REGION";
+const SSM_PREFIX_VAR: &str = "PUBLIC_TRADING_SSM_PREFIX";
+
+/// Where the public secret is stored, like Bitwarden or AWS SSM.
+pub trait SecretBackend: Send + Sync {
+    fn get_secret<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<String>>;
+}
+
+impl SecretBackend for Bitwarden {
+    fn get_secret<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let (secret, _note) = Bitwarden::get_secret(self, key).await?;
+
+            Ok(secret)
+        })
+    }
+}
+
+/// Secrets stored as SSM parameters, named `{prefix}/{key}` with a prefix.
+pub struct SsmBackend {
+    client: aws_sdk_ssm::Client,
+    prefix: Option<String>,
+}
+
+impl SsmBackend {
+    /// Backend using the AWS config of the environment, like `AWS_REGION`.
+    pub async fn new(prefix: Option<String>) -> SsmBackend {
+        let aws_config = aws_config::load_from_env().await;
+
+        SsmBackend {
+            client: aws_sdk_ssm::Client::new(&aws_config),
+            prefix,
+        }
+    }
+
+    fn parameter_name(&self, key: &str) -> String {
+        parameter_name(self.prefix.as_deref(), key)
+    }
+}
+
+fn parameter_name(prefix: Option<&str>, key: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}/{key}", prefix.trim_end_matches('/')),
+        None => key.to_string(),
+    }
+}
+
+impl SecretBackend for SsmBackend {
+    fn get_secret<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let name = self.parameter_name(key);
+            let res = self
+                .client
+                .get_parameter()
+                .name(&name)
+                .with_decryption(true)
+                .send()
+                .await?;
+            debug!("Get Parameter: {name}");
+
+            match res.parameter.and_then(|parameter| parameter.value) {
+                Some(value) => Ok(value),
+                None => bail!("SSM parameter <{name}> has no value"),
+            }
+        })
+    }
+}
+
+/// Which `SecretBackend` to use.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackendConfig {
+    Bitwarden,
+    Ssm { prefix: Option<String> },
+}
+
+impl BackendConfig {
+    /// Bitwarden when `~/.bw.json` exists, otherwise SSM when `AWS_REGION` is set,
+    /// with the parameter prefix from `PUBLIC_TRADING_SSM_PREFIX`.
+    pub fn from_env() -> Result<BackendConfig> {
+        select_backend(
+            bw_config_path().exists(),
+            env::var(AWS_REGION_VAR).ok(),
+            env::var(SSM_PREFIX_VAR).ok(),
+        )
+    }
+
+    pub async fn connect(&self) -> Result<Box<dyn SecretBackend>> {
+        info!("Using secret backend {self:?}");
+        let backend: Box<dyn SecretBackend> = match self {
+            BackendConfig::Bitwarden => Box::new(Bitwarden::new().await?),
+            BackendConfig::Ssm { prefix } => Box::new(SsmBackend::new(prefix.clone()).await),
+        };
+
+        Ok(backend)
+    }
+}
+
+fn select_backend(
+    bw_config_exists: bool,
+    aws_region: Option<String>,
+    ssm_prefix: Option<String>,
+) -> Result<BackendConfig> {
+    if bw_config_exists {
+        return Ok(BackendConfig::Bitwarden);
+    }
+    if aws_region.is_some() {
+        return Ok(BackendConfig::Ssm { prefix: ssm_prefix });
+    }
+
+    bail!("No secret backend, neither ~/.bw.json nor {AWS_REGION_VAR} are set")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_backend() {
+        let region = Some("us-east-1".to_string());
+        let prefix = Some("/public_trading".to_string());
+        assert_eq!(
+            select_backend(true, region.clone(), prefix.clone()).unwrap(),
+            BackendConfig::Bitwarden
+        );
+        assert_eq!(
+            select_backend(false, region.clone(), prefix.clone()).unwrap(),
+            BackendConfig::Ssm { prefix }
+        );
+        assert_eq!(
+            select_backend(false, region, None).unwrap(),
+            BackendConfig::Ssm { prefix: None }
+        );
+        assert!(select_backend(false, None, None).is_err());
+    }
+
+    #[test]
+    fn test_parameter_name() {
+        assert_eq!(parameter_name(None, "secret"), "secret");
+        assert_eq!(
+            parameter_name(Some("/trading"), "secret"),
+            "/trading/secret"
+        );
+        assert_eq!(
+            parameter_name(Some("/trading/"), "secret"),
+            "/trading/secret"
+        );
+    }
+}
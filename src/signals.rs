@@ -0,0 +1,255 @@
+use futures::future::BoxFuture;
+use std::fmt;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::journal::Journal;
+use crate::options::ExitReason;
+use crate::public::{
+    Instrument, InstrumentType, OPIndicator, OrderSide, PublicClient, PublicError,
+};
+
+/// What `OptionsStopper` decided for one option position, acted upon by its
+/// `SignalHandler`s.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Signal {
+    /// Close `quantity` contracts of `symbol` with a `side` order.
+    Exit {
+        symbol: String,
+        reason: ExitReason,
+        /// Mid of a valid quote, NaN without one, like in a dry run.
+        suggested_limit: f64,
+        side: OrderSide,
+        quantity: u32,
+        gain_percent: f64,
+        /// Quote the limit was taken from, NaN without one.
+        bid: f64,
+        ask: f64,
+    },
+    Hold {
+        symbol: String,
+    },
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::Exit {
+                symbol,
+                reason,
+                suggested_limit,
+                side,
+                quantity,
+                ..
+            } => {
+                write!(f, "{symbol} ({reason}): {side:?} {quantity}")?;
+                if suggested_limit.is_nan() {
+                    write!(f, ", no limit price")
+                } else {
+                    write!(f, " @ ${suggested_limit:.2}")
+                }
+            }
+            Signal::Hold { symbol } => write!(f, "{symbol}: hold"),
+        }
+    }
+}
+
+/// Acts on the signals of `OptionsStopper`, every handler gets every signal.
+/// `handle` returns a boxed future so handlers can be stored as trait objects.
+pub trait SignalHandler: Send + Sync {
+    fn handle<'a>(&'a self, signal: &'a Signal) -> BoxFuture<'a, Result<(), PublicError>>;
+
+    /// Whether `handle` trades, such handlers are skipped by the dry runs of `OptionsStopper`.
+    fn places_orders(&self) -> bool {
+        false
+    }
+}
+
+/// Logs the exits, and the holds at debug level.
+pub struct LoggingHandler;
+
+impl SignalHandler for LoggingHandler {
+    fn handle<'a>(&'a self, signal: &'a Signal) -> BoxFuture<'a, Result<(), PublicError>> {
+        Box::pin(async move {
+            match signal {
+                Signal::Exit { .. } => info!("Exit signal {signal}"),
+                Signal::Hold { .. } => debug!("{signal}"),
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Closes positions with a limit order at the suggested limit.
+/// Exits without a limit price are skipped.
+pub struct OrderHandler {
+    public: PublicClient,
+}
+
+impl OrderHandler {
+    pub fn new(client: PublicClient) -> Self {
+        Self { public: client }
+    }
+}
+
+impl SignalHandler for OrderHandler {
+    fn handle<'a>(&'a self, signal: &'a Signal) -> BoxFuture<'a, Result<(), PublicError>> {
+        Box::pin(async move {
+            let Signal::Exit {
+                symbol,
+                suggested_limit,
+                side,
+                quantity,
+                ..
+            } = signal
+            else {
+                return Ok(());
+            };
+            if suggested_limit.is_nan() {
+                warn!("No limit price for {symbol}, not closing");
+                return Ok(());
+            }
+
+            let instrument = Instrument {
+                instrument_type: InstrumentType::Option,
+                symbol: symbol.clone(),
+                name: None,
+            };
+            let receipt = self
+                .public
+                .place_limit_order(
+                    instrument,
+                    side.clone(),
+                    OPIndicator::Close,
+                    *quantity,
+                    *suggested_limit,
+                )
+                .await?;
+            info!("Exit order {} is {:?}", receipt.order_id, receipt.status);
+
+            Ok(())
+        })
+    }
+
+    fn places_orders(&self) -> bool {
+        true
+    }
+}
+
+/// Records the exits in the trade journal. Failing to write one is logged,
+/// it doesn't fail the signal.
+pub struct JournalHandler {
+    journal: Mutex<Journal>,
+}
+
+impl JournalHandler {
+    pub fn new(journal: Journal) -> Self {
+        Self {
+            journal: Mutex::new(journal),
+        }
+    }
+}
+
+impl SignalHandler for JournalHandler {
+    fn handle<'a>(&'a self, signal: &'a Signal) -> BoxFuture<'a, Result<(), PublicError>> {
+        Box::pin(async move {
+            if let Err(e) = self.journal.lock().unwrap().record_signal(signal) {
+                warn!("Failed to record {signal} in the journal: {e}");
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::mock::{mock_client, mock_server};
+    use serde_json::{Value, json};
+    use std::path::Path;
+    use std::sync::Arc;
+    use warp::Filter;
+
+    fn exit_signal(suggested_limit: f64) -> Signal {
+        Signal::Exit {
+            symbol: "QCOM260220P00138000".to_string(),
+            reason: ExitReason::StopLoss,
+            suggested_limit,
+            side: OrderSide::Buy,
+            quantity: 2,
+            gain_percent: -212.5,
+            bid: 0.6,
+            ask: 0.68,
+        }
+    }
+
+    #[test]
+    fn test_signal_display() {
+        assert_eq!(
+            exit_signal(0.64).to_string(),
+            "QCOM260220P00138000 (stop loss): Buy 2 @ $0.64"
+        );
+        assert_eq!(
+            exit_signal(f64::NAN).to_string(),
+            "QCOM260220P00138000 (stop loss): Buy 2, no limit price"
+        );
+        let hold = Signal::Hold {
+            symbol: "QCOM".to_string(),
+        };
+        assert_eq!(hold.to_string(), "QCOM: hold");
+    }
+
+    #[tokio::test]
+    async fn test_logging_handler() {
+        assert!(LoggingHandler.handle(&exit_signal(0.64)).await.is_ok());
+        assert!(!LoggingHandler.places_orders());
+    }
+
+    #[tokio::test]
+    async fn test_order_handler() {
+        let orders: Arc<Mutex<Vec<Value>>> = Arc::default();
+        let requests = orders.clone();
+        let order = warp::path!("userapigateway" / "trading" / "TEST" / "order")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                let reply = json!({ "orderId": body["orderId"] });
+                requests.lock().unwrap().push(body);
+                warp::reply::json(&reply)
+            });
+        let handler = OrderHandler::new(mock_client(mock_server(order).await));
+        assert!(handler.places_orders());
+
+        handler.handle(&exit_signal(0.64)).await.unwrap();
+        // Nothing to place without a limit price, or for a hold
+        handler.handle(&exit_signal(f64::NAN)).await.unwrap();
+        let hold = Signal::Hold {
+            symbol: "QCOM260220P00138000".to_string(),
+        };
+        handler.handle(&hold).await.unwrap();
+
+        let orders = orders.lock().unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0]["instrument"]["symbol"], "QCOM260220P00138000");
+        assert_eq!(orders[0]["instrument"]["type"], "OPTION");
+        assert_eq!(orders[0]["orderSide"], "BUY");
+        assert_eq!(orders[0]["quantity"], "2");
+        assert_eq!(orders[0]["limitPrice"], "0.64");
+    }
+
+    #[tokio::test]
+    async fn test_journal_handler() {
+        let handler = JournalHandler::new(Journal::new(Path::new(":memory:")).unwrap());
+        handler.handle(&exit_signal(0.64)).await.unwrap();
+        let hold = Signal::Hold {
+            symbol: "QCOM260220P00138000".to_string(),
+        };
+        handler.handle(&hold).await.unwrap();
+
+        let exits = handler.journal.lock().unwrap().recent_exits(1).unwrap();
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].symbol, "QCOM260220P00138000");
+        assert_eq!(exits[0].quantity, -2);
+        assert_eq!(exits[0].exit_reason, ExitReason::StopLoss);
+    }
+}
@@ -0,0 +1,299 @@
+use serde::Serialize;
+
+use crate::pricing::{DAYS_PER_YEAR, norm_cdf};
+use crate::public::{ExpirationPair, OptionType, Quote};
+
+/// Strike of a leg, NaN when the quote isn't for an OSI option symbol.
+fn strike(leg: &Quote) -> f64 {
+    leg.strike().unwrap_or(f64::NAN)
+}
+
+/// Debit paid to open a spread at the natural price:
+/// the long leg bought at the ask and the short leg sold at the bid.
+fn net_debit(long: &Quote, short: &Quote) -> f64 {
+    long.ask - short.bid
+}
+
+/// Max loss over max profit, infinite when the spread can't make money.
+fn risk_reward(max_loss: f64, max_profit: f64) -> f64 {
+    if max_profit <= 0.0 {
+        return f64::INFINITY;
+    }
+
+    max_loss / max_profit
+}
+
+/// Long call with a short call struck above it, opened for a debit.
+/// Amounts are per share, like the quotes.
+#[derive(Clone, Debug, Serialize)]
+pub struct BullCallSpread {
+    pub long_call: Quote,
+    pub short_call: Quote,
+}
+
+impl BullCallSpread {
+    pub fn width(&self) -> f64 {
+        strike(&self.short_call) - strike(&self.long_call)
+    }
+
+    pub fn net_debit(&self) -> f64 {
+        net_debit(&self.long_call, &self.short_call)
+    }
+
+    /// Made when the underlying expires at or above the short strike.
+    pub fn max_profit(&self) -> f64 {
+        self.width() - self.net_debit()
+    }
+
+    /// Lost when the underlying expires at or below the long strike.
+    pub fn max_loss(&self) -> f64 {
+        self.net_debit()
+    }
+
+    pub fn break_even(&self) -> f64 {
+        strike(&self.long_call) + self.net_debit()
+    }
+
+    pub fn risk_reward_ratio(&self) -> f64 {
+        risk_reward(self.max_loss(), self.max_profit())
+    }
+}
+
+/// Long put with a short put struck below it, opened for a debit.
+/// Amounts are per share, like the quotes.
+#[derive(Clone, Debug, Serialize)]
+pub struct BearPutSpread {
+    pub long_put: Quote,
+    pub short_put: Quote,
+}
+
+impl BearPutSpread {
+    pub fn width(&self) -> f64 {
+        strike(&self.long_put) - strike(&self.short_put)
+    }
+
+    pub fn net_debit(&self) -> f64 {
+        net_debit(&self.long_put, &self.short_put)
+    }
+
+    /// Made when the underlying expires at or below the short strike.
+    pub fn max_profit(&self) -> f64 {
+        self.width() - self.net_debit()
+    }
+
+    /// Lost when the underlying expires at or above the long strike.
+    pub fn max_loss(&self) -> f64 {
+        self.net_debit()
+    }
+
+    pub fn break_even(&self) -> f64 {
+        strike(&self.long_put) - self.net_debit()
+    }
+
+    pub fn risk_reward_ratio(&self) -> f64 {
+        risk_reward(self.max_loss(), self.max_profit())
+    }
+}
+
+/// Short put spread below the market and short call spread above it, opened for a credit.
+/// Amounts are per share, like the quotes.
+#[derive(Clone, Debug, Serialize)]
+pub struct IronCondor {
+    pub short_put: Quote,
+    pub long_put: Quote,
+    pub short_call: Quote,
+    pub long_call: Quote,
+}
+
+impl IronCondor {
+    /// Width of the wider wing, what is at risk before the credit.
+    pub fn width(&self) -> f64 {
+        let put_width = strike(&self.short_put) - strike(&self.long_put);
+        let call_width = strike(&self.long_call) - strike(&self.short_call);
+
+        put_width.max(call_width)
+    }
+
+    /// Credit received at the natural price: short legs sold at the bid,
+    /// long legs bought at the ask.
+    pub fn net_credit(&self) -> f64 {
+        self.short_put.bid + self.short_call.bid - self.long_put.ask - self.long_call.ask
+    }
+
+    /// Lost when the underlying expires beyond either long strike.
+    pub fn max_loss(&self) -> f64 {
+        self.width() - self.net_credit()
+    }
+
+    pub fn credit_to_width(&self) -> f64 {
+        self.net_credit() / self.width()
+    }
+
+    pub fn upper_break_even(&self) -> f64 {
+        strike(&self.short_call) + self.net_credit()
+    }
+
+    pub fn lower_break_even(&self) -> f64 {
+        strike(&self.short_put) - self.net_credit()
+    }
+
+    /// Probability the underlying expires between the break evens, assuming it
+    /// moves lognormally without drift at `iv` (annualized) for `dte` days.
+    pub fn probability_of_profit_approx(&self, underlying: f64, iv: f64, dte: f64) -> f64 {
+        let vol_t = iv * (dte / DAYS_PER_YEAR).sqrt();
+        let below = |price: f64| -> f64 {
+            if price <= 0.0 {
+                return 0.0;
+            }
+            norm_cdf(((price / underlying).ln() + vol_t * vol_t / 2.0) / vol_t)
+        };
+
+        below(self.upper_break_even()) - below(self.lower_break_even())
+    }
+}
+
+/// Far mid minus near mid of the `side` options of `pair`, which a calendar
+/// spread buying the far one and selling the near one costs.
+/// None when either expiration lacks the strike.
+pub fn calendar_spread_debit(pair: &ExpirationPair, side: OptionType) -> Option<f64> {
+    let (near, far) = match side {
+        OptionType::Call => (&pair.near_call, &pair.far_call),
+        OptionType::Put => (&pair.near_put, &pair.far_put),
+    };
+
+    Some(far.as_ref()?.mid_price() - near.as_ref()?.mid_price())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public::{Instrument, InstrumentType, QuoteOutcome};
+
+    fn quote(symbol: &str, bid: f64, ask: f64) -> Quote {
+        Quote {
+            instrument: Instrument {
+                symbol: symbol.to_string(),
+                instrument_type: InstrumentType::Option,
+                name: None,
+            },
+            outcome: QuoteOutcome::Success,
+            last: (bid + ask) / 2.0,
+            last_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            bid,
+            bid_size: None,
+            bid_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            ask,
+            ask_size: None,
+            ask_timestamp: "2026-02-17T20:59:59Z".to_string(),
+            volume: 0,
+            open_interest: None,
+            previous_close: None,
+            one_day_change: None,
+            option_details: None,
+        }
+    }
+
+    #[test]
+    fn test_bull_call_spread() {
+        let spread = BullCallSpread {
+            long_call: quote("QCOM260320C00140000", 4.8, 5.0),
+            short_call: quote("QCOM260320C00145000", 2.5, 2.7),
+        };
+        // Debit 5.00 - 2.50, on a 5 wide spread
+        assert_eq!(spread.width(), 5.0);
+        assert_eq!(spread.net_debit(), 2.5);
+        assert_eq!(spread.max_profit(), 2.5);
+        assert_eq!(spread.max_loss(), 2.5);
+        assert_eq!(spread.break_even(), 142.5);
+        assert_eq!(spread.risk_reward_ratio(), 1.0);
+
+        let spread = BullCallSpread {
+            long_call: quote("QCOM260320C00140000", 4.8, 5.0),
+            short_call: quote("QCOM260320C00150000", 1.0, 1.2),
+        };
+        assert_eq!(spread.max_profit(), 6.0);
+        assert_eq!(spread.max_loss(), 4.0);
+        assert_eq!(spread.break_even(), 144.0);
+        assert_eq!(spread.risk_reward_ratio(), 4.0 / 6.0);
+    }
+
+    #[test]
+    fn test_bull_call_spread_without_profit() {
+        let spread = BullCallSpread {
+            long_call: quote("QCOM260320C00140000", 4.8, 6.0),
+            short_call: quote("QCOM260320C00141000", 4.0, 4.5),
+        };
+        assert_eq!(spread.max_profit(), -1.0);
+        assert_eq!(spread.risk_reward_ratio(), f64::INFINITY);
+    }
+
+    fn iron_condor() -> IronCondor {
+        IronCondor {
+            long_put: quote("QCOM260320P00125000", 0.4, 0.5),
+            short_put: quote("QCOM260320P00130000", 1.25, 1.4),
+            short_call: quote("QCOM260320C00150000", 1.5, 1.6),
+            long_call: quote("QCOM260320C00155000", 0.6, 0.75),
+        }
+    }
+
+    #[test]
+    fn test_iron_condor() {
+        let condor = iron_condor();
+        // 1.25 + 1.50 - 0.50 - 0.75
+        assert_eq!(condor.net_credit(), 1.5);
+        assert_eq!(condor.width(), 5.0);
+        assert_eq!(condor.max_loss(), 3.5);
+        assert_eq!(condor.credit_to_width(), 0.3);
+        assert_eq!(condor.upper_break_even(), 151.5);
+        assert_eq!(condor.lower_break_even(), 128.5);
+    }
+
+    #[test]
+    fn test_iron_condor_probability_of_profit() {
+        let condor = iron_condor();
+        let pop = condor.probability_of_profit_approx(140.0, 0.3, 30.0);
+        assert!((pop - 0.661543).abs() < 1e-6, "pop {pop}");
+
+        // More time or more volatility puts more of the distribution past the wings.
+        assert!(condor.probability_of_profit_approx(140.0, 0.3, 60.0) < pop);
+        assert!(condor.probability_of_profit_approx(140.0, 0.6, 30.0) < pop);
+        assert!(condor.probability_of_profit_approx(140.0, 0.05, 30.0) > 0.99);
+    }
+
+    #[test]
+    fn test_bear_put_spread() {
+        let spread = BearPutSpread {
+            long_put: quote("QCOM260320P00140000", 4.0, 4.25),
+            short_put: quote("QCOM260320P00135000", 1.75, 1.9),
+        };
+        // Debit 4.25 - 1.75, on a 5 wide spread
+        assert_eq!(spread.width(), 5.0);
+        assert_eq!(spread.net_debit(), 2.5);
+        assert_eq!(spread.max_profit(), 2.5);
+        assert_eq!(spread.max_loss(), 2.5);
+        assert_eq!(spread.break_even(), 137.5);
+        assert_eq!(spread.risk_reward_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_calendar_spread_debit() {
+        let pair = ExpirationPair {
+            strike: 145.0,
+            near_call: Some(quote("QCOM260320C00145000", 2.5, 2.7)),
+            far_call: Some(quote("QCOM260417C00145000", 4.0, 4.2)),
+            near_put: Some(quote("QCOM260320P00145000", 4.6, 4.8)),
+            far_put: None,
+        };
+
+        // Far mid 4.10 - near mid 2.60
+        let debit = calendar_spread_debit(&pair, OptionType::Call).unwrap();
+        assert!((debit - 1.5).abs() < 1e-9);
+        assert_eq!(calendar_spread_debit(&pair, OptionType::Put), None);
+
+        let near_only = ExpirationPair {
+            far_call: None,
+            ..pair
+        };
+        assert_eq!(calendar_spread_debit(&near_only, OptionType::Call), None);
+    }
+}